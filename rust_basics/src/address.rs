@@ -0,0 +1,394 @@
+// ============================================
+// Email addresses (RFC 5322)
+// ============================================
+// An address can be written two ways:
+//   ekom@example.com                  just the address (addr-spec)
+//   Ekom Otu <ekom@example.com>       a name and the address (name-addr)
+// The part before the @ (the local part) is dot separated "atoms": letters,
+// digits and !#$%&'*+-/=?^_`{|}~. Anything else has to be in quotes:
+//   "ekom otu"@example.com
+// Names with commas or other special characters are quoted too:
+//   "Otu, Ekom" <ekom@example.com>
+//
+// Domains can be in any script (IDN): münchen.de is sent as
+// xn--mnchen-3ya.de, that's the punycode at the bottom of this file.
+// (We only lowercase the labels, full IDNA also normalizes some characters.)
+//
+// Errors say which character (column, counting from 1) is wrong.
+
+use std::fmt;
+
+const MAX_LOCAL: usize = 64;
+const MAX_LABEL: usize = 63;
+const MAX_ADDRESS: usize = 254;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmailAddress {
+    name: Option<String>,
+    local: String,
+    domain: String,
+    ascii_domain: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AddressError {
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AddressError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "column {}: {}", self.column, self.message)
+    }
+}
+
+impl std::error::Error for AddressError {}
+
+impl EmailAddress {
+    pub fn parse(text: &str) -> Result<EmailAddress, AddressError> {
+        let mut parser = Parser { chars: text.chars().collect(), pos: 0 };
+        let address = parser.address()?;
+        let length = address.addr_spec().chars().count();
+        if length > MAX_ADDRESS {
+            return Err(AddressError {
+                column: 1,
+                message: format!("address is {} characters long, the most is {}", length, MAX_ADDRESS),
+            });
+        }
+        Ok(address)
+    }
+
+    // the display name, if there is one
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    // local@domain with the domain in ASCII, what SMTP and mail headers need
+    pub fn addr_spec(&self) -> String {
+        format!("{}@{}", quote_local(&self.local), self.ascii_domain)
+    }
+}
+
+// the way a person would write it, with the domain as typed
+impl fmt::Display for EmailAddress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let spec = format!("{}@{}", quote_local(&self.local), self.domain);
+        match &self.name {
+            Some(name) if name.chars().all(|c| is_atext(c) || c == ' ' || !c.is_ascii()) => {
+                write!(f, "{} <{}>", name, spec)
+            }
+            Some(name) => write!(f, "{} <{}>", quote(name), spec),
+            None => write!(f, "{}", spec),
+        }
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn error<T>(&self, at: usize, message: impl Into<String>) -> Result<T, AddressError> {
+        Err(AddressError { column: at + 1, message: message.into() })
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_spaces(&mut self) {
+        while self.peek().is_some_and(|c| c == ' ' || c == '\t') {
+            self.pos += 1;
+        }
+    }
+
+    fn address(&mut self) -> Result<EmailAddress, AddressError> {
+        self.skip_spaces();
+        if self.peek().is_none() {
+            return self.error(self.pos, "address is empty");
+        }
+
+        // a < outside of quotes means there's a name in front
+        let mut quoted = false;
+        let mut escaped = false;
+        let angle = self.chars.iter().position(|&c| {
+            let found = c == '<' && !quoted;
+            match c {
+                _ if escaped => escaped = false,
+                '\\' if quoted => escaped = true,
+                '"' => quoted = !quoted,
+                _ => {}
+            }
+            found
+        });
+
+        let mut address = match angle {
+            Some(angle) => {
+                let name = self.display_name(angle)?;
+                self.pos = angle + 1;
+                self.skip_spaces();
+                let mut address = self.addr_spec(Some('>'))?;
+                self.skip_spaces();
+                if self.peek() != Some('>') {
+                    return self.error(self.pos, "missing '>' after the address");
+                }
+                self.pos += 1;
+                address.name = name;
+                address
+            }
+            None => self.addr_spec(None)?,
+        };
+        self.skip_spaces();
+        if self.pos < self.chars.len() {
+            return self.error(self.pos, "unexpected text after the address");
+        }
+        address.name = address.name.filter(|name| !name.is_empty());
+        Ok(address)
+    }
+
+    // everything before the <: a "quoted string" or plain words
+    fn display_name(&mut self, end: usize) -> Result<Option<String>, AddressError> {
+        let text: String = self.chars[self.pos..end].iter().collect();
+        let trimmed = text.trim_end();
+        if trimmed.is_empty() {
+            return Ok(None);
+        }
+        if self.peek() == Some('"') {
+            let name = self.quoted_string()?;
+            self.skip_spaces();
+            if self.pos != end {
+                return self.error(self.pos, "unexpected text between the name and '<'");
+            }
+            return Ok(Some(name));
+        }
+        for (offset, c) in trimmed.chars().enumerate() {
+            // "." isn't allowed by the RFC but people write "Ekom O. Otu" all the time
+            if matches!(c, '(' | ')' | '<' | '>' | '[' | ']' | ':' | ';' | '@' | '\\' | ',' | '"') || c.is_control() {
+                return self.error(
+                    self.pos + offset,
+                    format!("{:?} can't be in a name without quotes, write \"{}\" instead", c, trimmed),
+                );
+            }
+        }
+        Ok(Some(trimmed.split_whitespace().collect::<Vec<_>>().join(" ")))
+    }
+
+    // local@domain, `end` is the character that may follow it ('>' inside a name-addr)
+    fn addr_spec(&mut self, end: Option<char>) -> Result<EmailAddress, AddressError> {
+        let start = self.pos;
+        let local = if self.peek() == Some('"') { self.quoted_string()? } else { self.dot_atom()? };
+        if let Some(c) = local.chars().find(|c| !c.is_ascii()) {
+            // that needs SMTPUTF8, which our smtp client doesn't speak
+            return self.error(start, format!("{:?} can't be in the part before @", c));
+        }
+        if local.chars().count() > MAX_LOCAL {
+            return self.error(start, format!("the part before @ can be at most {} characters", MAX_LOCAL));
+        }
+        match self.peek() {
+            Some('@') => self.pos += 1,
+            None => return self.error(self.pos, "missing @ and domain"),
+            Some(c) if Some(c) == end => return self.error(self.pos, "missing @ and domain"),
+            Some(c) => return self.error(self.pos, format!("unexpected {:?} in the part before @", c)),
+        }
+        let (domain, ascii_domain) = self.domain(end)?;
+        Ok(EmailAddress { name: None, local, domain, ascii_domain })
+    }
+
+    // atoms separated by single dots
+    fn dot_atom(&mut self) -> Result<String, AddressError> {
+        let start = self.pos;
+        let mut out = String::new();
+        while let Some(c) = self.peek() {
+            if c == '.' {
+                if self.pos == start {
+                    return self.error(self.pos, "the part before @ can't start with a dot");
+                }
+                if out.ends_with('.') {
+                    return self.error(self.pos, "two dots in a row");
+                }
+            } else if !is_atext(c) {
+                break;
+            }
+            out.push(c);
+            self.pos += 1;
+        }
+        if out.is_empty() {
+            return match self.peek() {
+                Some('@') => self.error(self.pos, "nothing before the @"),
+                Some(c) if !c.is_ascii() => self.error(self.pos, format!("{:?} can't be in the part before @", c)),
+                Some(c) => self.error(self.pos, format!("unexpected {:?}", c)),
+                None => self.error(self.pos, "address is empty"),
+            };
+        }
+        if out.ends_with('.') {
+            return self.error(self.pos - 1, "the part before @ can't end with a dot");
+        }
+        Ok(out)
+    }
+
+    // "..." with \" and \\ escapes, returns the text without the quotes
+    fn quoted_string(&mut self) -> Result<String, AddressError> {
+        let open = self.pos;
+        self.pos += 1;
+        let mut out = String::new();
+        loop {
+            match self.peek() {
+                None => return self.error(open, "quote is never closed"),
+                Some('"') => {
+                    self.pos += 1;
+                    return Ok(out);
+                }
+                Some('\\') => {
+                    self.pos += 1;
+                    match self.peek() {
+                        Some(c) if !c.is_control() => out.push(c),
+                        _ => return self.error(self.pos, "\\ must be followed by the character it escapes"),
+                    }
+                }
+                Some(c) if c.is_control() && c != '\t' => {
+                    return self.error(self.pos, "control characters can't be in quotes");
+                }
+                Some(c) => out.push(c),
+            }
+            self.pos += 1;
+        }
+    }
+
+    // labels of letters, digits and -, separated by dots. returns it as typed and in ASCII
+    fn domain(&mut self, end: Option<char>) -> Result<(String, String), AddressError> {
+        let start = self.pos;
+        let stop = |c: char| c == ' ' || c == '\t' || Some(c) == end;
+        let mut domain = String::new();
+        while let Some(c) = self.peek().filter(|&c| !stop(c)) {
+            domain.push(c);
+            self.pos += 1;
+        }
+        if domain.is_empty() {
+            return self.error(start, "nothing after the @");
+        }
+        if domain.starts_with('[') {
+            return self.error(start, "address literals like [192.0.2.1] aren't supported");
+        }
+
+        let mut ascii = Vec::new();
+        let mut at = start;
+        for label in domain.split('.') {
+            let length = label.chars().count();
+            if length == 0 {
+                return self.error(at, "empty part in the domain (two dots in a row, or a dot at the start or end)");
+            }
+            if let Some((offset, c)) = label.chars().enumerate().find(|&(_, c)| !(c.is_alphanumeric() || c == '-')) {
+                return self.error(at + offset, format!("{:?} can't be in a domain", c));
+            }
+            if label.starts_with('-') || label.ends_with('-') {
+                return self.error(at, format!("domain part {:?} can't start or end with '-'", label));
+            }
+            let label = label.to_lowercase();
+            let encoded = if label.is_ascii() {
+                label
+            } else {
+                punycode(&label).map(|code| format!("xn--{}", code)).ok_or(AddressError {
+                    column: at + 1,
+                    message: format!("domain part {:?} can't be encoded", label),
+                })?
+            };
+            if encoded.len() > MAX_LABEL {
+                return self.error(at, format!("domain part {:?} is longer than {} characters", encoded, MAX_LABEL));
+            }
+            ascii.push(encoded);
+            at += length + 1;
+        }
+        Ok((domain, ascii.join(".")))
+    }
+}
+
+fn is_atext(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "!#$%&'*+-/=?^_`{|}~".contains(c)
+}
+
+// a local part that isn't a plain dot-atom needs its quotes back
+fn quote_local(local: &str) -> String {
+    let dot_atom = !local.starts_with('.')
+        && !local.ends_with('.')
+        && !local.contains("..")
+        && local.chars().all(|c| c == '.' || is_atext(c));
+    if dot_atom { local.to_string() } else { quote(local) }
+}
+
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+// ============================================
+// Punycode (RFC 3492)
+// ============================================
+// Spells a Unicode label with only a-z, 0-9 and -: the ASCII letters are kept,
+// then every other character is written as a number saying where it goes and
+// what it is, in a base 36 code whose digit size adapts as it goes.
+
+const BASE: u32 = 36;
+const T_MIN: u32 = 1;
+const T_MAX: u32 = 26;
+const SKEW: u32 = 38;
+const DAMP: u32 = 700;
+
+fn punycode(label: &str) -> Option<String> {
+    let chars: Vec<u32> = label.chars().map(|c| c as u32).collect();
+    let mut out: String = label.chars().filter(char::is_ascii).collect();
+    let basic = out.len() as u32;
+    if basic > 0 {
+        out.push('-');
+    }
+
+    let mut n: u32 = 128;
+    let mut delta: u32 = 0;
+    let mut bias: u32 = 72;
+    let mut handled = basic;
+    while (handled as usize) < chars.len() {
+        // the smallest character we haven't written yet
+        let next = *chars.iter().filter(|&&c| c >= n).min()?;
+        delta = delta.checked_add((next - n).checked_mul(handled + 1)?)?;
+        n = next;
+        for &c in &chars {
+            if c < n {
+                delta = delta.checked_add(1)?;
+            }
+            if c == n {
+                let mut q = delta;
+                let mut k = BASE;
+                loop {
+                    let t = if k <= bias { T_MIN } else if k >= bias + T_MAX { T_MAX } else { k - bias };
+                    if q < t {
+                        break;
+                    }
+                    out.push(digit(t + (q - t) % (BASE - t)));
+                    q = (q - t) / (BASE - t);
+                    k += BASE;
+                }
+                out.push(digit(q));
+                bias = adapt(delta, handled + 1, handled == basic);
+                delta = 0;
+                handled += 1;
+            }
+        }
+        delta += 1;
+        n += 1;
+    }
+    Some(out)
+}
+
+fn adapt(delta: u32, points: u32, first: bool) -> u32 {
+    let mut delta = if first { delta / DAMP } else { delta / 2 };
+    delta += delta / points;
+    let mut k = 0;
+    while delta > ((BASE - T_MIN) * T_MAX) / 2 {
+        delta /= BASE - T_MIN;
+        k += BASE;
+    }
+    k + (BASE - T_MIN + 1) * delta / (delta + SKEW)
+}
+
+fn digit(d: u32) -> char {
+    if d < 26 { (b'a' + d as u8) as char } else { (b'0' + (d - 26) as u8) as char }
+}
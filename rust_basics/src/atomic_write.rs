@@ -0,0 +1,85 @@
+// ============================================
+// Crash-safe file writes
+// ============================================
+// fs::write truncates the file first and then writes, so a crash in the middle
+// leaves half a file behind. Instead we:
+//   1. write everything to a temp file in the same directory
+//   2. fsync the temp file so the bytes are really on disk
+//   3. rename it over the real file (rename is atomic on the same filesystem)
+//   4. fsync the directory so the rename itself survives a crash
+// A reader sees either the whole old file or the whole new file, never half.
+
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::data_dir::with_path;
+
+// errors name the file we were trying to write
+pub fn write_atomic(path: impl AsRef<Path>, contents: &[u8]) -> io::Result<()> {
+    let path = path.as_ref();
+    replace_file(path, contents).map_err(|e| with_path(e, path))
+}
+
+fn replace_file(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?;
+    let temp = dir.join(format!(
+        ".{}.tmp{}",
+        file_name.to_string_lossy(),
+        std::process::id()
+    ));
+
+    // if anything goes wrong before the rename we clean up the temp file and the old file is untouched
+    if let Err(e) = write_temp(&temp, contents).and_then(|()| {
+        fault("rename")?;
+        fs::rename(&temp, path)
+    }) {
+        let _ = fs::remove_file(&temp);
+        return Err(e);
+    }
+
+    fault("dir-fsync")?;
+    sync_dir(&dir)
+}
+
+fn write_temp(temp: &Path, contents: &[u8]) -> io::Result<()> {
+    fault("create")?;
+    let mut file = File::create(temp)?;
+    fault("write")?;
+    file.write_all(contents)?;
+    fault("fsync")?;
+    file.sync_all()
+}
+
+// on unix a directory can be opened and fsynced like a file, other platforms don't allow it
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> io::Result<()> {
+    File::open(dir)?.sync_all()
+}
+
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+// Test-only fault hook: with LEARNING_PROJECT_FAULT=<step> set, that step fails as if the
+// disk filled up or the process crashed there. Steps: create, write, fsync, rename, dir-fsync.
+// Debug builds only (cargo test builds debug), release builds never look at the variable.
+#[cfg(debug_assertions)]
+fn fault(step: &str) -> io::Result<()> {
+    match std::env::var("LEARNING_PROJECT_FAULT") {
+        Ok(wanted) if wanted == step => Err(io::Error::other(format!("injected fault at {}", step))),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(not(debug_assertions))]
+fn fault(_step: &str) -> io::Result<()> {
+    Ok(())
+}
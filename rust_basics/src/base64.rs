@@ -0,0 +1,25 @@
+// ============================================
+// Base64 (RFC 4648)
+// ============================================
+// Mail can only carry plain text, so binary data (a password for AUTH PLAIN,
+// an attachment) is sent as base64: every 3 bytes become 4 letters from the
+// 64 below, and "=" pads the end when the input isn't a multiple of 3.
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub fn encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        // glue up to 3 bytes into one 24 bit number, then cut it into four 6 bit pieces
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
@@ -0,0 +1,227 @@
+// ============================================
+// CSV import and export (RFC 4180)
+// ============================================
+// Admissions sends student lists as spreadsheets saved to CSV.
+// A field can be wrapped in double quotes, and then it may contain commas,
+// line breaks, and "" for a literal quote. Excel likes to put a BOM
+// (the bytes EF BB BF) at the start, so we skip it when reading and
+// can write one when exporting. A broken row is reported with its line
+// number and the rest of the file is still imported.
+
+use std::fmt;
+
+use crate::status::Status;
+use crate::{Student, StudentErrors};
+
+const HEADER: [&str; 3] = ["name", "age", "status"];
+// older exports had a true/false is_active column instead of status
+const LEGACY_HEADER: [&str; 3] = ["name", "age", "is_active"];
+const BOM: char = '\u{feff}';
+
+#[derive(Debug)]
+pub enum CsvError {
+    MissingHeader,
+    BadHeader { found: Vec<String> },
+    // the header row itself could not be read
+    Unreadable { line: usize, error: RowError },
+}
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CsvError::MissingHeader => write!(f, "file is empty, expected a header row"),
+            CsvError::BadHeader { found } => {
+                write!(f, "expected header {:?}, found {:?}", HEADER.join(","), found.join(","))
+            }
+            CsvError::Unreadable { line, error } => write!(f, "line {}: {}", line, error),
+        }
+    }
+}
+
+impl std::error::Error for CsvError {}
+
+// one parsed row, `line` is where the row starts in the file (rows can span lines)
+struct Row {
+    line: usize,
+    fields: Result<Vec<String>, RowError>,
+}
+
+// a broken row doesn't stop the import: it is reported and
+// reading starts over on the line after the one it started on
+fn parse_rows(text: &str) -> Vec<Row> {
+    let mut text = text.strip_prefix(BOM).unwrap_or(text);
+    let mut rows = Vec::new();
+    let mut line = 1;
+    while !text.is_empty() {
+        let (fields, used) = match parse_record(text) {
+            Ok((fields, used)) => (Ok(fields), used),
+            Err(e) => (Err(e), text.find('\n').map_or(text.len(), |end| end + 1)),
+        };
+        let (record, rest) = text.split_at(used);
+        // a completely blank line is not a row
+        if !matches!(&fields, Ok(fields) if fields.len() == 1 && fields[0].is_empty()) {
+            rows.push(Row { line, fields });
+        }
+        line += record.matches('\n').count();
+        text = rest;
+    }
+    rows
+}
+
+// reads the record at the start of `text`, and how many bytes it took
+fn parse_record(text: &str) -> Result<(Vec<String>, usize), RowError> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    // quoted: inside "...", field_was_quoted: the field started with a quote
+    let mut quoted = false;
+    let mut field_was_quoted = false;
+    let mut spans_lines = false;
+    let mut chars = text.char_indices().peekable();
+    // a quote that never closes usually shows up as a stray quote a few
+    // lines down, so once the row has run over a line blame where it started
+    let broken = |spans_lines| if spans_lines { RowError::UnterminatedQuote } else { RowError::StrayQuote };
+
+    while let Some((at, c)) = chars.next() {
+        if quoted {
+            match c {
+                '"' if matches!(chars.peek(), Some((_, '"'))) => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => quoted = false,
+                '\n' => {
+                    spans_lines = true;
+                    field.push(c);
+                }
+                _ => field.push(c),
+            }
+            continue;
+        }
+
+        match c {
+            '"' if field.is_empty() && !field_was_quoted => {
+                quoted = true;
+                field_was_quoted = true;
+            }
+            '"' => return Err(broken(spans_lines)),
+            ',' => {
+                fields.push(std::mem::take(&mut field));
+                field_was_quoted = false;
+            }
+            '\r' if matches!(chars.peek(), Some((_, '\n'))) => {}
+            '\n' => {
+                fields.push(field);
+                return Ok((fields, at + 1));
+            }
+            _ if field_was_quoted => return Err(broken(spans_lines)),
+            _ => field.push(c),
+        }
+    }
+
+    if quoted {
+        return Err(RowError::UnterminatedQuote);
+    }
+    // last row without a trailing newline
+    fields.push(field);
+    Ok((fields, text.len()))
+}
+
+fn quote(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) || field.starts_with(' ') || field.ends_with(' ') {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+pub fn export_students(students: &[Student], with_bom: bool) -> String {
+    let mut out = String::new();
+    if with_bom {
+        out.push(BOM);
+    }
+    out.push_str(&HEADER.join(","));
+    out.push_str("\r\n");
+    for student in students {
+        out.push_str(&format!(
+            "{},{},{}\r\n",
+            quote(&student.name),
+            student.age,
+            student.status
+        ));
+    }
+    out
+}
+
+// why a row was turned away
+#[derive(Debug)]
+pub enum RowError {
+    UnterminatedQuote,
+    StrayQuote,
+    WrongFieldCount { expected: usize, found: usize },
+    BadAge(String),
+    BadStatus(String),
+    Invalid(StudentErrors),
+}
+
+impl fmt::Display for RowError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RowError::UnterminatedQuote => write!(f, "quoted field is never closed"),
+            RowError::StrayQuote => write!(f, "quote in the middle of an unquoted field"),
+            RowError::WrongFieldCount { expected, found } => {
+                write!(f, "expected {} fields, found {}", expected, found)
+            }
+            RowError::BadAge(text) => write!(f, "age must be a number from 0 to 255, got {:?}", text),
+            RowError::BadStatus(text) => write!(f, "unknown status {:?}", text),
+            RowError::Invalid(errors) => write!(f, "{}", errors),
+        }
+    }
+}
+
+// what happened to every row of the file, line numbers point at the file
+pub struct ImportReport {
+    pub accepted: Vec<(usize, Student)>,
+    pub rejected: Vec<(usize, RowError)>,
+}
+
+pub fn import_students(text: &str) -> Result<ImportReport, CsvError> {
+    let mut rows = parse_rows(text).into_iter();
+    let header = rows.next().ok_or(CsvError::MissingHeader)?;
+    let line = header.line;
+    let header = header.fields.map_err(|error| CsvError::Unreadable { line, error })?;
+    let found: Vec<String> = header.iter().map(|f| f.trim().to_lowercase()).collect();
+    // the status column is optional, a list without it means everybody is active
+    let known = |header: &[&str]| found.len() >= 2 && found.len() <= 3 && found[..] == header[..found.len()];
+    if !known(&HEADER) && !known(&LEGACY_HEADER) {
+        return Err(CsvError::BadHeader { found: header });
+    }
+    let columns = found.len();
+
+    let mut report = ImportReport { accepted: Vec::new(), rejected: Vec::new() };
+    for row in rows {
+        match row.fields.and_then(|fields| student_from_row(&fields, columns)) {
+            Ok(student) => report.accepted.push((row.line, student)),
+            Err(e) => report.rejected.push((row.line, e)),
+        }
+    }
+    Ok(report)
+}
+
+fn student_from_row(fields: &[String], columns: usize) -> Result<Student, RowError> {
+    if fields.len() != columns {
+        return Err(RowError::WrongFieldCount { expected: columns, found: fields.len() });
+    }
+    let age = fields[1].trim();
+    let age: u8 = age.parse().map_err(|_| RowError::BadAge(age.to_string()))?;
+    // every row goes through the same create_student checks as a hand made student
+    let mut student = Student::create_student(fields[0].clone(), age).map_err(RowError::Invalid)?;
+    if let Some(status) = fields.get(2) {
+        // the list is where the student comes from, so its status is the starting one (no history)
+        student.status = match status.trim().to_lowercase().as_str() {
+            "true" | "yes" | "1" => Status::Active,
+            "false" | "no" | "0" => Status::Inactive,
+            other => Status::from_name(other).ok_or_else(|| RowError::BadStatus(status.clone()))?,
+        };
+    }
+    Ok(student)
+}
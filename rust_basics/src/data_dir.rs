@@ -0,0 +1,86 @@
+// ============================================
+// Where our data files live
+// ============================================
+// Plain names like "student.txt" are relative to whatever folder you ran the
+// program from, so running it somewhere else silently used other files.
+// Now every data file lives in one data directory, picked in this order:
+//   1. the --data-dir DIR flag, before the command
+//   2. the LEARNING_PROJECT_DATA_DIR environment variable
+//   3. $XDG_DATA_HOME/learning_project, or ~/.local/share/learning_project
+// The directory is created if it doesn't exist yet.
+//
+// This file only uses std, so lesson 6 can use it with `mod data_dir;` too.
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const ENV_VAR: &str = "LEARNING_PROJECT_DATA_DIR";
+const APP_DIR: &str = "learning_project";
+
+pub struct DataDir {
+    root: PathBuf,
+}
+
+impl DataDir {
+    pub fn resolve(flag: Option<&str>) -> io::Result<DataDir> {
+        let root = match flag {
+            Some(dir) => PathBuf::from(dir),
+            None => match env::var_os(ENV_VAR) {
+                Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+                _ => xdg_data_home()?.join(APP_DIR),
+            },
+        };
+        fs::create_dir_all(&root).map_err(|e| with_path(e, &root))?;
+        Ok(DataDir { root })
+    }
+
+    // the full path of one of our data files, e.g. file("student.txt")
+    pub fn file(&self, name: &str) -> PathBuf {
+        self.root.join(name)
+    }
+}
+
+// XDG says an empty or relative XDG_DATA_HOME must be ignored
+fn xdg_data_home() -> io::Result<PathBuf> {
+    if let Some(dir) = env::var_os("XDG_DATA_HOME") {
+        let dir = PathBuf::from(dir);
+        if dir.is_absolute() {
+            return Ok(dir);
+        }
+    }
+    match env::var_os("HOME") {
+        Some(home) if !home.is_empty() => Ok(PathBuf::from(home).join(".local").join("share")),
+        _ => Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("HOME is not set, use --data-dir or {}", ENV_VAR),
+        )),
+    }
+}
+
+// io errors don't say which file they are about ("Permission denied (os error 13)"),
+// this puts the path in front of the message
+pub fn with_path(e: io::Error, path: &Path) -> io::Error {
+    io::Error::new(e.kind(), format!("{}: {}", path.display(), e))
+}
+
+// fs::read_to_string, but the error names the file
+pub fn read_to_string(path: &Path) -> io::Result<String> {
+    fs::read_to_string(path).map_err(|e| with_path(e, path))
+}
+
+// pulls `--data-dir DIR` off the front of the arguments. It is an option for the
+// whole program, so it goes before the command: after it, it belongs to the command
+// (`email send ... --data-dir` is two words of the body)
+pub fn take_flag(args: &mut Vec<String>) -> Result<Option<String>, String> {
+    if args.first().map(String::as_str) != Some("--data-dir") {
+        return Ok(None);
+    }
+    if args.len() < 2 {
+        return Err(String::from("--data-dir needs a directory"));
+    }
+    let dir = args.remove(1);
+    args.remove(0);
+    Ok(Some(dir))
+}
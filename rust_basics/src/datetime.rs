@@ -0,0 +1,295 @@
+// ============================================
+// Timestamps without a date crate
+// ============================================
+// A Timestamp is whole seconds since 1970-01-01 00:00:00 UTC (the Unix epoch).
+// We print and read them as RFC 3339, e.g. 2026-10-18T07:51:11Z.
+// The calendar maths is Howard Hinnant's days_from_civil / civil_from_days.
+
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+// the years we read, four digits like the formats themselves. A bigger year
+// from a feed or a file would overflow the seconds in from_parts
+const YEARS: std::ops::RangeInclusive<i64> = 1..=9999;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Timestamp(pub i64);
+
+impl Timestamp {
+    pub fn now() -> Timestamp {
+        // a clock before 1970 is broken, we treat it as the epoch instead of crashing
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+        Timestamp(secs)
+    }
+
+    pub fn from_parts(year: i64, month: u32, day: u32, hour: u32, minute: u32, second: u32) -> Timestamp {
+        Timestamp(days_from_civil(year, month, day) * 86_400 + (hour * 3600 + minute * 60 + second) as i64)
+    }
+
+    // (year, month, day, hour, minute, second) in UTC
+    pub fn parts(self) -> (i64, u32, u32, u32, u32, u32) {
+        let days = self.0.div_euclid(86_400);
+        let secs = self.0.rem_euclid(86_400) as u32;
+        let (year, month, day) = civil_from_days(days);
+        (year, month, day, secs / 3600, secs % 3600 / 60, secs % 60)
+    }
+
+    // accepts 2026-10-18T07:51:11Z and offsets like 2026-10-18T09:51:11+02:00,
+    // fractions of a second are dropped
+    pub fn parse_rfc3339(text: &str) -> Option<Timestamp> {
+        let text = text.trim();
+        let (date, rest) = text.split_at_checked(10)?;
+        let rest = rest.strip_prefix(['T', 't', ' '])?;
+        let (year, month, day) = parse_date(date)?;
+        let (time, zone) = rest.split_at_checked(8)?;
+        let (hour, minute, second) = parse_time(time)?;
+
+        let mut zone = zone;
+        if let Some(fraction) = zone.strip_prefix('.') {
+            zone = fraction.trim_start_matches(|c: char| c.is_ascii_digit());
+        }
+        let offset = match zone {
+            "Z" | "z" => 0,
+            _ => parse_offset(zone)?,
+        };
+        Some(Timestamp(Timestamp::from_parts(year, month, day, hour, minute, second).0 - offset))
+    }
+
+    // the other way round, RSS uses this format too. The weekday and the seconds
+    // can be left out, a two digit year is 19xx or 20xx, and old zone names
+    // like GMT or EST are allowed: "18 Oct 26 07:51 EST"
+    pub fn parse_rfc2822(text: &str) -> Option<Timestamp> {
+        let text = text.trim();
+        // the weekday doesn't tell us anything the date doesn't
+        let text = text.split_once(',').map(|(_, rest)| rest).unwrap_or(text);
+        let words: Vec<&str> = text.split_whitespace().collect();
+        let [day, month, year, time, zone @ ..] = words.as_slice() else {
+            return None;
+        };
+        let day: u32 = number(day)?;
+        let month = month.get(..3)?;
+        let month = MONTHS.iter().position(|name| name.eq_ignore_ascii_case(month))? as u32 + 1;
+        let year = match number::<i64>(year)? {
+            short if year.len() <= 2 && short < 50 => 2000 + short,
+            short if year.len() <= 2 => 1900 + short,
+            full if year.len() == 4 => full,
+            _ => return None,
+        };
+        if !YEARS.contains(&year) || day == 0 || day > days_in_month(year, month) {
+            return None;
+        }
+
+        let mut clock = time.split(':');
+        let hour: u32 = number(clock.next()?)?;
+        let minute: u32 = number(clock.next()?)?;
+        let second: u32 = match clock.next() {
+            Some(second) => number(second)?,
+            None => 0,
+        };
+        if clock.next().is_some() || hour > 23 || minute > 59 || second > 60 {
+            return None;
+        }
+        // no zone at all is taken as UTC, anything after the zone (like "(PDT)") is a comment
+        let offset = match zone.first() {
+            Some(zone) => zone_offset(zone)?,
+            None => 0,
+        };
+        Some(Timestamp(Timestamp::from_parts(year, month, day, hour, minute, second).0 - offset))
+    }
+
+    // the date format mail headers use (RFC 5322), e.g. "Sun, 18 Oct 2026 07:51:11 +0000"
+    pub fn rfc5322(self) -> String {
+        let (year, month, day, hour, minute, second) = self.parts();
+        format!(
+            "{}, {:02} {} {:04} {:02}:{:02}:{:02} +0000",
+            self.weekday(),
+            day,
+            MONTHS[month as usize - 1],
+            year,
+            hour,
+            minute,
+            second
+        )
+    }
+
+    // C's asctime format that mbox files use, e.g. "Sun Oct 18 07:51:11 2026"
+    pub fn asctime(self) -> String {
+        let (year, month, day, hour, minute, second) = self.parts();
+        format!(
+            "{} {} {:2} {:02}:{:02}:{:02} {}",
+            self.weekday(),
+            MONTHS[month as usize - 1],
+            day,
+            hour,
+            minute,
+            second,
+            year
+        )
+    }
+
+    // strftime style: %Y %m %d %e %H %M %S %a %b and %%, anything else is
+    // left as it is. "%e %b %Y" -> "18 Oct 2026"
+    pub fn format(self, pattern: &str) -> String {
+        let (year, month, day, hour, minute, second) = self.parts();
+        let mut out = String::new();
+        let mut chars = pattern.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                out.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('Y') => out.push_str(&format!("{:04}", year)),
+                Some('m') => out.push_str(&format!("{:02}", month)),
+                Some('d') => out.push_str(&format!("{:02}", day)),
+                Some('e') => out.push_str(&day.to_string()),
+                Some('H') => out.push_str(&format!("{:02}", hour)),
+                Some('M') => out.push_str(&format!("{:02}", minute)),
+                Some('S') => out.push_str(&format!("{:02}", second)),
+                Some('a') => out.push_str(self.weekday()),
+                Some('b') => out.push_str(MONTHS[month as usize - 1]),
+                Some('%') => out.push('%'),
+                Some(other) => {
+                    out.push('%');
+                    out.push(other);
+                }
+                None => out.push('%'),
+            }
+        }
+        out
+    }
+
+    fn weekday(self) -> &'static str {
+        const DAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+        // 1970-01-01 was a Thursday
+        DAYS[(self.0.div_euclid(86_400) + 4).rem_euclid(7) as usize]
+    }
+}
+
+// RFC 3339 in UTC
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (year, month, day, hour, minute, second) = self.parts();
+        write!(f, "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", year, month, day, hour, minute, second)
+    }
+}
+
+fn number<T: std::str::FromStr>(text: &str) -> Option<T> {
+    if text.is_empty() || !text.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    text.parse().ok()
+}
+
+// YYYY-MM-DD, checking the year is in YEARS and the day exists in that month
+pub fn parse_date(text: &str) -> Option<(i64, u32, u32)> {
+    let mut parts = text.split('-');
+    let year: i64 = number(parts.next()?)?;
+    let month: u32 = number(parts.next()?)?;
+    let day: u32 = number(parts.next()?)?;
+    if parts.next().is_some() || !YEARS.contains(&year) || !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+        return None;
+    }
+    Some((year, month, day))
+}
+
+// HH:MM:SS
+fn parse_time(text: &str) -> Option<(u32, u32, u32)> {
+    let mut parts = text.split(':');
+    let hour: u32 = number(parts.next()?)?;
+    let minute: u32 = number(parts.next()?)?;
+    // 60 is allowed for leap seconds, we fold it into the next minute
+    let second: u32 = number(parts.next()?)?;
+    if parts.next().is_some() || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+    Some((hour, minute, second))
+}
+
+// +HH:MM or -HH:MM, returned in seconds
+fn parse_offset(text: &str) -> Option<i64> {
+    let (sign, rest) = match text.split_at_checked(1)? {
+        ("+", rest) => (1, rest),
+        ("-", rest) => (-1, rest),
+        _ => return None,
+    };
+    let (hours, minutes) = rest.split_once(':')?;
+    let hours: i64 = number(hours)?;
+    let minutes: i64 = number(minutes)?;
+    if hours > 23 || minutes > 59 {
+        return None;
+    }
+    Some(sign * (hours * 3600 + minutes * 60))
+}
+
+// +0200 / -0500 or a name from RFC 822, returned in seconds
+fn zone_offset(zone: &str) -> Option<i64> {
+    let hours = match zone.to_ascii_uppercase().as_str() {
+        "GMT" | "UT" | "UTC" | "Z" => 0,
+        "EDT" => -4,
+        "EST" | "CDT" => -5,
+        "CST" | "MDT" => -6,
+        "MST" | "PDT" => -7,
+        "PST" => -8,
+        _ => {
+            let (sign, digits) = match zone.split_at_checked(1)? {
+                ("+", digits) => (1, digits),
+                ("-", digits) => (-1, digits),
+                _ => return None,
+            };
+            if digits.len() != 4 {
+                return None;
+            }
+            let hours: i64 = number(&digits[..2])?;
+            let minutes: i64 = number(&digits[2..])?;
+            if hours > 23 || minutes > 59 {
+                return None;
+            }
+            return Some(sign * (hours * 3600 + minutes * 60));
+        }
+    };
+    Some(hours * 3600)
+}
+
+fn is_leap_year(year: i64) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// days since 1970-01-01 for a calendar date
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+// the calendar date for a number of days since 1970-01-01
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
@@ -0,0 +1,293 @@
+// ============================================
+// Digests: many summaries in one roundup
+// ============================================
+// notify sends one item at a time. A Digest takes a whole Vec<Box<dyn Item>>
+// (tweets, articles and emails mixed, that's what the dyn is for), puts them
+// in groups and in order, and writes them out as Markdown, HTML or plain text:
+//
+//   # Daily digest
+//
+//   ## Articles
+//
+//   - **high** Rust takes over the world by The Times (2026-10-18T07:51:11Z)
+//
+//   ## Tweets
+//
+//   - @ekom: Rust traits are cool!
+//
+// Groups come out in alphabetical order. Inside a group the order is either
+// priority (high first, then newest) or recency (newest first, then priority),
+// items that are equal keep the order they were given in.
+
+use std::cmp::{Ordering, Reverse};
+
+use crate::datetime::Timestamp;
+use crate::json::Json;
+use crate::item::Item;
+use crate::lesson_7_traits::Summary;
+use crate::tasks::Priority;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Grouping {
+    Kind,
+    Source,
+    None,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Order {
+    Priority,
+    Recent,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Markdown,
+    Html,
+    Text,
+}
+
+impl Grouping {
+    pub fn from_name(name: &str) -> Option<Grouping> {
+        match name {
+            "kind" => Some(Grouping::Kind),
+            "source" => Some(Grouping::Source),
+            "none" => Some(Grouping::None),
+            _ => None,
+        }
+    }
+}
+
+impl Order {
+    pub fn from_name(name: &str) -> Option<Order> {
+        match name {
+            "priority" => Some(Order::Priority),
+            "recent" => Some(Order::Recent),
+            _ => None,
+        }
+    }
+}
+
+impl Format {
+    pub fn from_name(name: &str) -> Option<Format> {
+        match name {
+            "markdown" | "md" => Some(Format::Markdown),
+            "html" => Some(Format::Html),
+            "text" => Some(Format::Text),
+            _ => None,
+        }
+    }
+}
+
+// an item with a priority and time of our choosing, for types that don't
+// have their own (a tweet doesn't know how important it is)
+pub struct Tagged {
+    pub item: Box<dyn Item>,
+    pub priority: Priority,
+    pub published: Option<Timestamp>,
+}
+
+impl Summary for Tagged {
+    fn summarize(&self) -> String {
+        self.item.summarize()
+    }
+
+    fn kind(&self) -> &'static str {
+        self.item.kind()
+    }
+
+    fn source(&self) -> String {
+        self.item.source()
+    }
+
+    fn published(&self) -> Option<Timestamp> {
+        self.published
+    }
+}
+
+impl Item for Tagged {
+    fn priority(&self) -> Priority {
+        self.priority
+    }
+
+    fn fields(&self) -> Vec<(String, Json)> {
+        self.item.fields()
+    }
+}
+
+pub struct Digest {
+    title: String,
+    items: Vec<Box<dyn Item>>,
+    grouping: Grouping,
+    order: Order,
+}
+
+// one group heading and its items, already in order
+struct Group<'a> {
+    heading: Option<String>,
+    items: Vec<&'a dyn Item>,
+}
+
+impl Digest {
+    // grouped by kind and ordered by priority unless group_by / order_by say otherwise
+    pub fn new(title: &str, items: Vec<Box<dyn Item>>) -> Digest {
+        Digest { title: title.to_string(), items, grouping: Grouping::Kind, order: Order::Priority }
+    }
+
+    pub fn group_by(mut self, grouping: Grouping) -> Digest {
+        self.grouping = grouping;
+        self
+    }
+
+    pub fn order_by(mut self, order: Order) -> Digest {
+        self.order = order;
+        self
+    }
+
+    pub fn render(&self, format: Format) -> String {
+        let groups = self.groups();
+        match format {
+            Format::Markdown => self.markdown(&groups),
+            Format::Html => self.html(&groups),
+            Format::Text => self.text(&groups),
+        }
+    }
+
+    fn groups(&self) -> Vec<Group<'_>> {
+        let mut groups: Vec<Group> = Vec::new();
+        for item in &self.items {
+            let heading = match self.grouping {
+                Grouping::Kind => Some(plural(item.kind())),
+                Grouping::Source => Some(item.source()),
+                Grouping::None => None,
+            };
+            match groups.iter_mut().find(|group| group.heading == heading) {
+                Some(group) => group.items.push(item.as_ref()),
+                None => groups.push(Group { heading, items: vec![item.as_ref()] }),
+            }
+        }
+        groups.sort_by(|a, b| a.heading.cmp(&b.heading));
+        for group in &mut groups {
+            // sort_by is stable, so equal items stay in the order they were given
+            group.items.sort_by(|a, b| self.compare(*a, *b));
+        }
+        groups
+    }
+
+    fn compare(&self, a: &dyn Item, b: &dyn Item) -> Ordering {
+        // Reverse puts high before low and new before old, Some(time) before None
+        let priority = |item: &dyn Item| Reverse(item.priority());
+        let recency = |item: &dyn Item| Reverse(item.published());
+        match self.order {
+            Order::Priority => priority(a).cmp(&priority(b)).then(recency(a).cmp(&recency(b))),
+            Order::Recent => recency(a).cmp(&recency(b)).then(priority(a).cmp(&priority(b))),
+        }
+    }
+
+    fn markdown(&self, groups: &[Group]) -> String {
+        let mut out = format!("# {}\n", markdown_escape(&self.title));
+        if groups.is_empty() {
+            out.push_str("\nNothing new.\n");
+        }
+        for group in groups {
+            if let Some(heading) = &group.heading {
+                out.push_str(&format!("\n## {}\n", markdown_escape(heading)));
+            }
+            out.push('\n');
+            for item in &group.items {
+                out.push_str("- ");
+                if item.priority() != Priority::Medium {
+                    out.push_str(&format!("**{}** ", item.priority().name()));
+                }
+                out.push_str(&markdown_escape(&item.summarize()));
+                if let Some(at) = item.published() {
+                    out.push_str(&format!(" ({})", at));
+                }
+                out.push('\n');
+            }
+        }
+        out
+    }
+
+    fn html(&self, groups: &[Group]) -> String {
+        let mut out = format!("<h1>{}</h1>\n", html_escape(&self.title));
+        if groups.is_empty() {
+            out.push_str("<p>Nothing new.</p>\n");
+        }
+        for group in groups {
+            if let Some(heading) = &group.heading {
+                out.push_str(&format!("<h2>{}</h2>\n", html_escape(heading)));
+            }
+            out.push_str("<ul>\n");
+            for item in &group.items {
+                out.push_str("<li>");
+                if item.priority() != Priority::Medium {
+                    out.push_str(&format!("<strong>{}</strong> ", item.priority().name()));
+                }
+                out.push_str(&html_escape(&item.summarize()));
+                if let Some(at) = item.published() {
+                    out.push_str(&format!(" <time datetime=\"{}\">{}</time>", at, at));
+                }
+                out.push_str("</li>\n");
+            }
+            out.push_str("</ul>\n");
+        }
+        out
+    }
+
+    fn text(&self, groups: &[Group]) -> String {
+        let mut out = format!("{}\n{}\n", self.title, "=".repeat(self.title.chars().count()));
+        if groups.is_empty() {
+            out.push_str("\nNothing new.\n");
+        }
+        for group in groups {
+            if let Some(heading) = &group.heading {
+                out.push_str(&format!("\n{}\n{}\n", heading, "-".repeat(heading.chars().count())));
+            } else {
+                out.push('\n');
+            }
+            for item in &group.items {
+                out.push_str(&format!("* {}", item.summarize()));
+                if item.priority() != Priority::Medium {
+                    out.push_str(&format!(" [{}]", item.priority().name()));
+                }
+                if let Some(at) = item.published() {
+                    out.push_str(&format!(" ({})", at));
+                }
+                out.push('\n');
+            }
+        }
+        out
+    }
+}
+
+// "tweet" -> "Tweets", for the group headings
+fn plural(kind: &str) -> String {
+    let mut chars = kind.chars();
+    match chars.next() {
+        Some(first) => format!("{}{}s", first.to_uppercase(), chars.as_str()),
+        None => String::from("Other"),
+    }
+}
+
+// a backslash in front of anything Markdown would treat as formatting
+fn markdown_escape(text: &str) -> String {
+    let mut out = String::new();
+    for c in text.chars() {
+        if "\\`*_[]<>#|".contains(c) {
+            out.push('\\');
+        }
+        // a line break would end the list item
+        out.push(if c == '\n' { ' ' } else { c });
+    }
+    out
+}
+
+// templates (template.rs) use this too
+pub fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
@@ -0,0 +1,341 @@
+// ============================================
+// Reading RSS and Atom feeds into NewsArticles
+// ============================================
+// The two feed formats say the same things with different tags:
+//
+//   RSS 2.0                                   Atom
+//   <rss><channel>                            <feed>
+//     <title>The Times</title>                  <title>The Times</title>
+//     <item>                                    <entry>
+//       <title>headline</title>                   <title>headline</title>
+//       <link>http://...</link>                   <link href="http://..."/>
+//       <guid>unique id</guid>                    <id>unique id</id>
+//       <pubDate>Sun, 18 Oct 2026 ...</pubDate>   <published>2026-10-18T07:51:11Z</published>
+//       <description>html</description>           <summary>text or html</summary>
+//       <author> or <dc:creator>                  <author><name>...</name></author>
+//
+// Articles we read are kept in articles.jsonl in the data directory, each with
+// a key: its guid (or id), else its link. An article whose key is already
+// there is skipped, so reading the same feed again doesn't add duplicates.
+
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use crate::atomic_write::write_atomic;
+use crate::data_dir::{self, DataDir};
+use crate::datetime::{self, Timestamp};
+use crate::digest::{Digest, Format, Grouping, Order};
+use crate::http;
+use crate::json::{FromJson, Json, ToJson};
+use crate::item::Item;
+use crate::lesson_7_traits::{NewsArticle, Summary};
+use crate::lock::{FileLock, Mode};
+use crate::xml::{self, Element, XmlError};
+
+const ARTICLE_FILE: &str = "articles.jsonl";
+// summaries longer than this are cut, a digest wants a teaser and not the whole article
+const MAX_SUMMARY_CHARS: usize = 500;
+
+// an article and the key that tells it apart from the others
+pub struct FeedItem {
+    pub key: String,
+    pub article: NewsArticle,
+}
+
+#[derive(Debug)]
+pub enum FeedError {
+    Io(io::Error),
+    Xml(XmlError),
+    Http { url: String, status_line: String },
+    NotAFeed(String),
+    Store { line: usize, message: String },
+}
+
+impl fmt::Display for FeedError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FeedError::Io(e) => write!(f, "{}", e),
+            FeedError::Xml(e) => write!(f, "bad XML, {}", e),
+            FeedError::Http { url, status_line } => write!(f, "{}: the server answered {:?}", url, status_line),
+            FeedError::NotAFeed(root) => write!(f, "<{}> is not an RSS or Atom feed", root),
+            FeedError::Store { line, message } => write!(f, "{} line {}: {}", ARTICLE_FILE, line, message),
+        }
+    }
+}
+
+impl std::error::Error for FeedError {}
+
+impl From<io::Error> for FeedError {
+    fn from(e: io::Error) -> Self {
+        FeedError::Io(e)
+    }
+}
+
+impl From<XmlError> for FeedError {
+    fn from(e: XmlError) -> Self {
+        FeedError::Xml(e)
+    }
+}
+
+// every article in an RSS or Atom document, in the order the feed has them
+pub fn parse_feed(text: &str) -> Result<Vec<FeedItem>, FeedError> {
+    let root = xml::parse(text)?;
+    match root.name.as_str() {
+        "rss" => {
+            let channel = root.child("channel").ok_or_else(|| FeedError::NotAFeed(String::from("rss without <channel>")))?;
+            Ok(channel.children_named("item").map(|item| rss_item(channel, item)).collect())
+        }
+        "feed" => Ok(root.children_named("entry").map(|entry| atom_entry(&root, entry)).collect()),
+        other => Err(FeedError::NotAFeed(other.to_string())),
+    }
+}
+
+fn rss_item(channel: &Element, item: &Element) -> FeedItem {
+    let text = |name: &str| item.child(name).map(|e| e.text().trim().to_string()).filter(|t| !t.is_empty());
+    // <author> is an email address, often with the name after it: "ann@example.com (Ann Lee)"
+    let author = text("dc:creator")
+        .or_else(|| text("author").map(|a| a.split_once('(').map(|(_, name)| name.trim_end_matches(')').trim().to_string()).unwrap_or(a)))
+        .or_else(|| channel.child("title").map(|t| t.text().trim().to_string()))
+        .unwrap_or_else(|| String::from("unknown"));
+    let summary = text("description").or_else(|| text("content:encoded")).map(|html| teaser(&plain_text(&html)));
+    let article = NewsArticle {
+        headline: text("title").map(|t| one_line(&t)).unwrap_or_else(|| String::from("(no title)")),
+        author,
+        link: text("link"),
+        published: text("pubDate").or_else(|| text("dc:date")).and_then(|date| parse_date(&date)),
+        summary,
+    };
+    FeedItem { key: key(text("guid"), &article), article }
+}
+
+fn atom_entry(feed: &Element, entry: &Element) -> FeedItem {
+    let text = |element: &Element, name: &str| {
+        element.child(name).map(atom_text).filter(|t| !t.is_empty())
+    };
+    let author = |element: &Element| element.child("author").and_then(|author| text(author, "name"));
+    // rel="alternate" (or no rel at all) is the page for people to read
+    let link = entry
+        .children_named("link")
+        .find(|link| matches!(link.attribute("rel"), None | Some("alternate")))
+        .and_then(|link| link.attribute("href"))
+        .map(String::from);
+    let article = NewsArticle {
+        headline: text(entry, "title").unwrap_or_else(|| String::from("(no title)")),
+        author: author(entry)
+            .or_else(|| author(feed))
+            .or_else(|| text(feed, "title"))
+            .unwrap_or_else(|| String::from("unknown")),
+        link,
+        published: text(entry, "published").or_else(|| text(entry, "updated")).and_then(|date| parse_date(&date)),
+        summary: text(entry, "summary").or_else(|| text(entry, "content")).map(|summary| teaser(&summary)),
+    };
+    FeedItem { key: key(text(entry, "id"), &article), article }
+}
+
+// Atom text can be type="text", "html" (escaped HTML) or "xhtml" (real tags)
+fn atom_text(element: &Element) -> String {
+    match element.attribute("type") {
+        Some("html") => plain_text(&element.text()),
+        _ => one_line(&element.text()),
+    }
+}
+
+// the guid if there is one, else the link, else the headline and date
+fn key(guid: Option<String>, article: &NewsArticle) -> String {
+    guid.or_else(|| article.link.clone()).unwrap_or_else(|| {
+        let published = article.published.map(|at| at.to_string()).unwrap_or_default();
+        format!("{}|{}", article.headline, published)
+    })
+}
+
+// feeds use RFC 822 dates (RSS), RFC 3339 (Atom), and now and then just a day
+pub fn parse_date(text: &str) -> Option<Timestamp> {
+    Timestamp::parse_rfc3339(text).or_else(|| Timestamp::parse_rfc2822(text)).or_else(|| {
+        let (year, month, day) = datetime::parse_date(text.trim())?;
+        Some(Timestamp::from_parts(year, month, day, 0, 0, 0))
+    })
+}
+
+// HTML to plain text: tags go, entities are decoded, runs of whitespace become one space
+fn plain_text(html: &str) -> String {
+    let mut text = String::new();
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => {
+                in_tag = true;
+                text.push(' ');
+            }
+            '>' if in_tag => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    // an & that isn't a known entity is left as it is, HTML in the wild is sloppy
+    let mut decoded = String::new();
+    let mut rest = text.as_str();
+    while let Some(amp) = rest.find('&') {
+        decoded.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let name = rest[1..].find(';').filter(|&end| end <= 10).map(|end| &rest[1..end + 1]);
+        match name.and_then(xml::entity) {
+            Some(c) => {
+                decoded.push(c);
+                rest = &rest[name.map_or(0, str::len) + 2..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    one_line(&decoded)
+}
+
+// line breaks and runs of spaces become one space
+fn one_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn teaser(text: &str) -> String {
+    if text.chars().count() <= MAX_SUMMARY_CHARS {
+        return text.to_string();
+    }
+    let cut: String = text.chars().take(MAX_SUMMARY_CHARS).collect();
+    // end at a word if there is one
+    let cut = cut.rsplit_once(' ').map(|(start, _)| start).unwrap_or(&cut);
+    format!("{}…", cut)
+}
+
+// a file name, or an http:// URL
+pub fn read_source(source: &str) -> Result<String, FeedError> {
+    if !source.starts_with("http://") {
+        return Ok(data_dir::read_to_string(source.as_ref())?);
+    }
+    let response = http::get(source)?;
+    if !response.is_success() {
+        return Err(FeedError::Http { url: source.to_string(), status_line: response.status_line });
+    }
+    Ok(String::from_utf8_lossy(&response.body).into_owned())
+}
+
+// articles.jsonl, one {"key": ..., "article": {...}} per line
+pub struct Store {
+    path: PathBuf,
+}
+
+impl Store {
+    pub fn open(data: &DataDir) -> Store {
+        Store { path: data.file(ARTICLE_FILE) }
+    }
+
+    pub fn load(&self) -> Result<Vec<FeedItem>, FeedError> {
+        let _lock = FileLock::acquire(&self.path, Mode::Shared)?;
+        self.read()
+    }
+
+    // adds the items whose key we don't have yet, returns how many were new
+    pub fn add(&self, items: Vec<FeedItem>) -> Result<usize, FeedError> {
+        let _lock = FileLock::acquire(&self.path, Mode::Exclusive)?;
+        let mut stored = self.read()?;
+        let before = stored.len();
+        for item in items {
+            // the same article can be in one feed twice too
+            if !stored.iter().any(|known| known.key == item.key) {
+                stored.push(item);
+            }
+        }
+        let added = stored.len() - before;
+        if added > 0 {
+            let lines: String = stored
+                .iter()
+                .map(|item| {
+                    let line = Json::Object(vec![
+                        (String::from("key"), Json::String(item.key.clone())),
+                        (String::from("article"), item.article.to_json()),
+                    ]);
+                    format!("{}\n", line)
+                })
+                .collect();
+            write_atomic(&self.path, lines.as_bytes())?;
+        }
+        Ok(added)
+    }
+
+    fn read(&self) -> Result<Vec<FeedItem>, FeedError> {
+        let content = match std::fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(data_dir::with_path(e, &self.path).into()),
+        };
+        let mut items = Vec::new();
+        for (index, line) in content.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let store_error = |message: String| FeedError::Store { line: index + 1, message };
+            let value = Json::parse(line).map_err(|e| store_error(e.to_string()))?;
+            let key = match value.get("key") {
+                Some(Json::String(key)) => key.clone(),
+                _ => return Err(store_error(String::from("\"key\" should be a string"))),
+            };
+            let article = value.get("article").ok_or_else(|| store_error(String::from("\"article\" is missing")))?;
+            let article = NewsArticle::from_json(article).map_err(|e| store_error(e.to_string()))?;
+            items.push(FeedItem { key, article });
+        }
+        Ok(items)
+    }
+}
+
+const USAGE: &str = "usage: feed <ingest FILE|URL... | list [--limit N] | digest [--format markdown|html|text]>";
+
+// `learning_project feed ...`
+pub fn run(data: &DataDir, args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let words: Vec<&str> = args.iter().map(String::as_str).collect();
+    let store = Store::open(data);
+    match words.as_slice() {
+        ["ingest", sources @ ..] if !sources.is_empty() => {
+            // every source is read before anything is stored, a bad feed adds nothing
+            let mut items = Vec::new();
+            for source in sources {
+                let found = parse_feed(&read_source(source)?).map_err(|e| format!("{}: {}", source, e))?;
+                println!("{}: {} articles", source, found.len());
+                items.extend(found);
+            }
+            let found = items.len();
+            let added = store.add(items)?;
+            println!("added {}, {} already known", added, found - added);
+        }
+        ["list", rest @ ..] => {
+            let limit = match rest {
+                [] => usize::MAX,
+                ["--limit", n] => n.parse().map_err(|_| format!("--limit needs a number, got {:?}", n))?,
+                _ => return Err(USAGE.into()),
+            };
+            let mut items = store.load()?;
+            // newest first, undated ones last
+            items.sort_by_key(|item| std::cmp::Reverse(item.article.published));
+            for item in items.iter().take(limit) {
+                let published = item.article.published.map(|at| at.to_string()).unwrap_or_else(|| String::from("undated"));
+                println!("{}  {}", published, item.article.summarize());
+                if let Some(link) = &item.article.link {
+                    println!("    {}", link);
+                }
+            }
+        }
+        ["digest", rest @ ..] => {
+            let format = match rest {
+                [] => Format::Markdown,
+                ["--format", name] => Format::from_name(name).ok_or_else(|| format!("--format must be markdown, html or text, got {:?}", name))?,
+                _ => return Err(USAGE.into()),
+            };
+            let items: Vec<Box<dyn Item>> =
+                store.load()?.into_iter().map(|item| Box::new(item.article) as Box<dyn Item>).collect();
+            print!("{}", Digest::new("News", items).group_by(Grouping::Source).order_by(Order::Recent).render(format));
+        }
+        _ => return Err(USAGE.into()),
+    }
+    Ok(())
+}
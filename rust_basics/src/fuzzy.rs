@@ -0,0 +1,113 @@
+// ============================================
+// Fuzzy name search
+// ============================================
+// Looking someone up by exact name fails on the smallest typo. Here a name
+// matches if it is *close* to what you typed:
+//   - case and accents don't count: "jose" finds "José", "ZOE" finds "Zoë"
+//   - every typo (a wrong, missing, extra or swapped letter) costs one step,
+//     that's the edit distance: "Charlei" is 1 step from "Charlie"
+//   - the score is 1.0 for a perfect match, less for every step, relative to the
+//     length of the name (one typo in a long name matters less than in "Bob")
+// Each word of a name is tried too, so "otu" finds "Ekom Otu".
+//
+// This file only uses std, so lesson 4 can use it with `mod fuzzy;` too.
+
+// below this score it's a different name, not a typo
+pub const MIN_SCORE: f64 = 0.6;
+
+// one search result, best first
+#[derive(Debug)]
+pub struct Hit<'a, T> {
+    pub item: &'a T,
+    pub score: f64,
+}
+
+// every item whose name is close enough to the query, best match first
+pub fn search<'a, T>(items: &'a [T], query: &str, name: impl Fn(&T) -> &str) -> Vec<Hit<'a, T>> {
+    let query = fold(query);
+    let mut hits: Vec<Hit<T>> = items
+        .iter()
+        .map(|item| Hit { item, score: score(&query, &fold(name(item))) })
+        .filter(|hit| hit.score >= MIN_SCORE)
+        .collect();
+    // equal scores are sorted by name so the order doesn't depend on the input order
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| name(a.item).cmp(name(b.item))));
+    hits
+}
+
+// just the best match, None if nothing is close
+pub fn best<'a, T>(items: &'a [T], query: &str, name: impl Fn(&T) -> &str) -> Option<Hit<'a, T>> {
+    search(items, query, name).into_iter().next()
+}
+
+// both already folded, the whole name or its best word
+fn score(query: &str, name: &str) -> f64 {
+    name.split_whitespace()
+        .map(|word| similarity(query, word))
+        .fold(similarity(query, name), f64::max)
+}
+
+fn similarity(a: &str, b: &str) -> f64 {
+    let longest = a.chars().count().max(b.chars().count());
+    if longest == 0 {
+        return 1.0;
+    }
+    1.0 - edit_distance(a, b) as f64 / longest as f64
+}
+
+// how many letters have to be changed, added, removed or swapped to turn a into b.
+// we fill in a table row by row: row[j] is the distance between the first i
+// letters of a and the first j letters of b
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    // we need two rows back to spot a swap like "ei" -> "ie"
+    let mut before: Vec<usize> = vec![0; b.len() + 1];
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for i in 1..=a.len() {
+        let mut current = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let change = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            current[j] = (previous[j] + 1) // remove a letter
+                .min(current[j - 1] + 1) // add a letter
+                .min(previous[j - 1] + change); // keep or change a letter
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                current[j] = current[j].min(before[j - 2] + 1); // swap two letters
+            }
+        }
+        before = std::mem::replace(&mut previous, current);
+    }
+    previous[b.len()]
+}
+
+// lowercase without accents, so "Zoë Ünal" becomes "zoe unal"
+pub fn fold(text: &str) -> String {
+    let mut out = String::new();
+    for c in text.chars().flat_map(char::to_lowercase) {
+        match c {
+            // combining accents, for text that stores "e" + "◌́" instead of "é"
+            '\u{300}'..='\u{36f}' => {}
+            'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ă' | 'ą' => out.push('a'),
+            'ç' | 'ć' | 'č' => out.push('c'),
+            'ď' | 'đ' => out.push('d'),
+            'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ė' | 'ę' | 'ě' => out.push('e'),
+            'ğ' => out.push('g'),
+            'ì' | 'í' | 'î' | 'ï' | 'ī' | 'į' | 'ı' => out.push('i'),
+            'ł' | 'ľ' => out.push('l'),
+            'ñ' | 'ń' | 'ň' => out.push('n'),
+            'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ő' => out.push('o'),
+            'ř' => out.push('r'),
+            'ś' | 'š' | 'ş' => out.push('s'),
+            'ť' | 'ţ' => out.push('t'),
+            'ù' | 'ú' | 'û' | 'ü' | 'ū' | 'ů' | 'ű' | 'ų' => out.push('u'),
+            'ý' | 'ÿ' => out.push('y'),
+            'ź' | 'ż' | 'ž' => out.push('z'),
+            'ß' => out.push_str("ss"),
+            'æ' => out.push_str("ae"),
+            'œ' => out.push_str("oe"),
+            c => out.push(c),
+        }
+    }
+    out
+}
@@ -0,0 +1,140 @@
+// ============================================
+// A very small HTTP/1.1 client
+// ============================================
+// Just enough for the webhook sink and the feed reader to talk to a server:
+//
+//   C: GET /feed.xml HTTP/1.1
+//   C: Host: localhost:8080
+//   C: Connection: close
+//   C:
+//   S: HTTP/1.1 200 OK
+//   S: Content-Type: application/rss+xml
+//   S:
+//   S: <rss>...
+//
+// Only plain http://, https needs TLS and std doesn't have that.
+// Every request gets its own connection (Connection: close), so the body is
+// everything until the server hangs up, or the chunks if it sends it in chunks.
+
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+// a feed or a webhook answer bigger than this is a mistake
+const MAX_RESPONSE: u64 = 16 * 1024 * 1024;
+
+#[derive(Debug)]
+pub struct Response {
+    pub status: u16,
+    // e.g. "HTTP/1.1 404 Not Found", for error messages
+    pub status_line: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    // header names are case insensitive
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find(|(key, _)| key.eq_ignore_ascii_case(name)).map(|(_, value)| value.as_str())
+    }
+
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+}
+
+pub fn get(url: &str) -> io::Result<Response> {
+    request("GET", url, None)
+}
+
+pub fn post(url: &str, content_type: &str, body: &[u8]) -> io::Result<Response> {
+    request("POST", url, Some((content_type, body)))
+}
+
+fn request(method: &str, url: &str, body: Option<(&str, &[u8])>) -> io::Result<Response> {
+    let (host, path) = split_url(url)?;
+    // a host without a port is port 80
+    let mut stream = if host.contains(':') { TcpStream::connect(host)? } else { TcpStream::connect((host, 80))? };
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+
+    let mut head = format!("{} {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n", method, path, host);
+    if let Some((content_type, body)) = body {
+        head.push_str(&format!("Content-Type: {}\r\nContent-Length: {}\r\n", content_type, body.len()));
+    }
+    head.push_str("\r\n");
+    let mut request = head.into_bytes();
+    if let Some((_, body)) = body {
+        request.extend_from_slice(body);
+    }
+    stream.write_all(&request)?;
+
+    let mut raw = Vec::new();
+    stream.take(MAX_RESPONSE + 1).read_to_end(&mut raw)?;
+    if raw.len() as u64 > MAX_RESPONSE {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("{}: answer is bigger than {} bytes", url, MAX_RESPONSE)));
+    }
+    parse_response(&raw)
+}
+
+fn parse_response(raw: &[u8]) -> io::Result<Response> {
+    let bad = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+    let end = raw.windows(4).position(|window| window == b"\r\n\r\n");
+    let (head, body) = match end {
+        Some(end) => (&raw[..end], &raw[end + 4..]),
+        None => (raw, &[][..]),
+    };
+    let head = String::from_utf8_lossy(head);
+    let mut lines = head.split("\r\n");
+    let status_line = lines.next().unwrap_or_default().to_string();
+    let status = match status_line.split_whitespace().collect::<Vec<_>>().as_slice() {
+        [version, code, ..] if version.starts_with("HTTP/") => code.parse::<u16>().ok(),
+        _ => None,
+    };
+    let Some(status) = status else {
+        return Err(bad(format!("not an HTTP answer: {:?}", status_line)));
+    };
+    let headers: Vec<(String, String)> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect();
+
+    let mut response = Response { status, status_line, headers, body: Vec::new() };
+    let chunked = response.header("Transfer-Encoding").is_some_and(|value| value.eq_ignore_ascii_case("chunked"));
+    response.body = if chunked { dechunk(body).ok_or_else(|| bad(String::from("broken chunked body")))? } else { body.to_vec() };
+    Ok(response)
+}
+
+// "1a\r\n...26 bytes...\r\n0\r\n\r\n" -> the bytes in between
+fn dechunk(mut rest: &[u8]) -> Option<Vec<u8>> {
+    let mut body = Vec::new();
+    loop {
+        let line_end = rest.windows(2).position(|window| window == b"\r\n")?;
+        let size = std::str::from_utf8(&rest[..line_end]).ok()?;
+        // anything after a ; is a chunk extension, nobody uses them
+        let size = usize::from_str_radix(size.split(';').next()?.trim(), 16).ok()?;
+        rest = &rest[line_end + 2..];
+        if size == 0 {
+            return Some(body);
+        }
+        body.extend_from_slice(rest.get(..size)?);
+        rest = rest.get(size + 2..)?;
+    }
+}
+
+// "http://localhost:8080/hook" -> ("localhost:8080", "/hook")
+fn split_url(url: &str) -> io::Result<(&str, &str)> {
+    let bad = |message: &str| io::Error::new(io::ErrorKind::InvalidInput, format!("{:?}: {}", url, message));
+    let Some(rest) = url.strip_prefix("http://") else {
+        return Err(bad("only http:// URLs are supported"));
+    };
+    let (host, path) = match rest.find('/') {
+        Some(slash) => rest.split_at(slash),
+        None => (rest, "/"),
+    };
+    if host.is_empty() {
+        return Err(bad("no host in the URL"));
+    }
+    Ok((host, path))
+}
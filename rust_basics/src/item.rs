@@ -0,0 +1,194 @@
+// ============================================
+// Summary items in the rest of the program
+// ============================================
+// lesson_7_traits.rs only uses std, so it still builds on its own with rustc.
+// What an item needs from the rest of learning_project is here instead, in
+// a trait that extends lesson 7's Summary:
+//
+//   priority        the digest sorts by it (digest.rs)
+//   fields          what a template can use besides the summary (template.rs)
+//   summarize_with  the summary written the way a template says
+//   broadcast       the item to the notification sinks, through the outbox
+//   publish         only to who subscribed to it (subscriptions.rs)
+//
+// Tweet and NewsArticle implement it here, Email in learning_project.rs.
+// Every Item is a Summary too, so a Box<dyn Item> can do both.
+//
+// The notifications broadcast, publish and Summary::notify send are written
+// by a template too (template.rs). NOTIFICATION_TEMPLATE in the data directory
+// replaces the built in one, so how they look can change without a rebuild.
+// Its first line is the title, the rest is the text. It gets the item's
+// template fields, `title` (the title the program would have used) and
+// `subscriptions`, the {topic, token} a published item was sent for:
+//
+//   {{ kind | upper }}: {{ title }}
+//   {{ summary | truncate(60) }}
+//   {% for subscription in subscriptions %}
+//   stop these with: learning_project subscription remove {{ subscription.token }}
+//   {% endfor %}
+
+use crate::digest::{Digest, Format};
+use crate::json::Json;
+use crate::lesson_7_traits::{NewsArticle, Summary, Tweet};
+use crate::notifier::{Memory, Notification, Notifiers, NotifyErrors};
+use crate::outbox::{self, OutboxError, Report};
+use crate::subscriptions::{self, Route, SubscriptionError, Topic};
+use crate::tasks::Priority;
+use crate::template::{self, Template, TemplateError};
+
+pub trait Item: Summary {
+    fn priority(&self) -> Priority {
+        Priority::Medium
+    }
+
+    // the item's own fields for templates, like "headline".
+    // summary, kind, source, priority and published are always there too
+    fn fields(&self) -> Vec<(String, Json)> {
+        Vec::new()
+    }
+
+    // the summary written the way a template says instead of the format! in summarize()
+    fn summarize_with(&self, template: &Template) -> Result<String, TemplateError> {
+        template.render(&template::context(self))
+    }
+
+    // goes to the sinks in LEARNING_PROJECT_NOTIFY (stdout if that isn't set)
+    // through the outbox, so a sink that is down gets it later
+    fn broadcast(&self) -> Result<Report, OutboxError> {
+        outbox::notify(notification("(Default Announcement)", template::context(self))?)
+    }
+
+    // broadcast goes to everyone, publish only to the recipients subscribed to
+    // this author, user, sender or a keyword in it. Subscriptions::route finds
+    // them, so the caller can show who it goes to before sending
+    fn publish(&self, routes: &[Route]) -> Result<Report, SubscriptionError> {
+        subscriptions::publish(self, routes)
+    }
+}
+
+pub const NOTIFICATION_TEMPLATE: &str = "notification.txt";
+
+// what is used without a NOTIFICATION_TEMPLATE: the title, then the summary
+// and a line for every subscription it was sent for
+const DEFAULT_NOTIFICATION: &str = "\
+{{ title }}
+{{ summary }}
+{% if subscriptions %}
+
+{% for subscription in subscriptions %}
+(you get this for {{ subscription.topic }}, unsubscribe: learning_project subscription remove {{ subscription.token }})
+{% endfor %}
+{% endif %}
+";
+
+// the notification template from the data directory, or the built in one
+fn notification_template() -> Result<Template, TemplateError> {
+    let path = outbox::data_file(NOTIFICATION_TEMPLATE)?;
+    match Template::load(&path) {
+        Err(TemplateError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => Template::parse("notification", DEFAULT_NOTIFICATION),
+        result => result,
+    }
+}
+
+// a notification written by the notification template. `context` is an
+// object like template::context gives, `title` is added to it
+pub fn notification(title: &str, context: Json) -> Result<Notification, TemplateError> {
+    let mut context = context;
+    if let Json::Object(fields) = &mut context {
+        fields.push((String::from("title"), Json::String(title.to_string())));
+    }
+    let text = notification_template()?.render(&context)?;
+    let (first, rest) = text.split_once('\n').unwrap_or((&text, ""));
+    // a template that leaves the first line empty keeps the usual title
+    let first = first.trim();
+    let title = if first.is_empty() { title } else { first };
+    Ok(Notification::new(title, rest.trim_start_matches(['\r', '\n']).trim_end()))
+}
+
+impl Item for Tweet {
+    fn fields(&self) -> Vec<(String, Json)> {
+        let names = |names: &[String]| Json::Array(names.iter().cloned().map(Json::String).collect());
+        vec![
+            (String::from("username"), Json::String(self.username().to_string())),
+            (String::from("content"), Json::String(self.content().to_string())),
+            (String::from("mentions"), names(self.mentions())),
+            (String::from("hashtags"), names(self.hashtags())),
+            (String::from("length"), Json::Number(self.length() as f64)),
+        ]
+    }
+}
+
+impl Item for NewsArticle {
+    // "summary" in a template is summarize(), so the article's own summary is "teaser"
+    fn fields(&self) -> Vec<(String, Json)> {
+        let optional = |field: &Option<String>| field.clone().map(Json::String).unwrap_or(Json::Null);
+        vec![
+            (String::from("headline"), Json::String(self.headline.clone())),
+            (String::from("author"), Json::String(self.author.clone())),
+            (String::from("link"), optional(&self.link)),
+            (String::from("teaser"), optional(&self.summary)),
+        ]
+    }
+}
+
+// lesson 7's notify writes to anything that implements Write,
+// this one sends to the notification sinks (anything that implements Notifier)
+fn notify(item: &impl Summary, sinks: &mut Notifiers) -> Result<(), NotifyErrors> {
+    sinks.send(&Notification::new("Breaking News", &item.summarize()))
+}
+
+// the rest of `learning_project lesson7`, after the lesson's own demo
+pub fn demo() -> Result<(), Box<dyn std::error::Error>> {
+    let tweet = Tweet::new("ekom", "Rust traits are cool! 🦀 #rustlang @rustaceans")?;
+    let article = NewsArticle {
+        headline: String::from("Rust takes over the world"),
+        author: String::from("The Times"),
+        link: None,
+        published: None,
+        summary: None,
+    };
+
+    println!("\n--- Notification sinks ---");
+    let mut sinks = Notifiers::configured();
+    notify(&tweet, &mut sinks)?;
+    notify(&article, &mut sinks)?;
+    tweet.broadcast()?;
+    article.broadcast()?;
+    // broadcasting it again in a loop doesn't spam anyone, the limiter
+    // (limiter.rs) drops content a sink got in the last few minutes
+    for _ in 0..3 {
+        if let Some(held_back) = tweet.broadcast()?.held_back() {
+            println!("broadcast again: {}", held_back);
+        }
+    }
+
+    // a Memory sink keeps notifications instead of printing them,
+    // the clone we keep sees everything the one in `captured` got
+    println!("\n--- Captured by a Memory sink ---");
+    let memory = Memory::new();
+    let mut captured = Notifiers::new().add(memory.clone());
+    notify(&tweet, &mut captured)?;
+    notify(&article, &mut captured)?;
+    for note in memory.taken() {
+        println!("captured {:?}: {:?}", note.title, note.text);
+    }
+
+    // the same item written by a template instead of a format! string,
+    // a template can come from a file too: Template::load(path)
+    println!("\n--- Template ---");
+    let template = Template::parse("inline", "{{ username | upper }} says {{ content | truncate(15) }}{% if hashtags %} (tags: {{ hashtags | join }}){% endif %}")?;
+    println!("{}", tweet.summarize_with(&template)?);
+
+    // publish() sends an item only to who subscribed to it, these are the
+    // topics it checks (`learning_project subscription add` saves them)
+    println!("\n--- Subscriptions ---");
+    for topic in ["user:ekom", "author:the times", "keyword:traits", "keyword:the world"] {
+        let topic = Topic::parse(topic)?;
+        println!("{}: tweet {}, article {}", topic, topic.matches(&tweet), topic.matches(&article));
+    }
+
+    println!("\n--- Digest ---");
+    let items: Vec<Box<dyn Item>> = vec![Box::new(tweet), Box::new(article)];
+    print!("{}", Digest::new("Today in Rust", items).render(Format::Text));
+    Ok(())
+}
@@ -0,0 +1,608 @@
+// ============================================
+// JSON encode and decode
+// ============================================
+// A small JSON reader and writer so students, emails, tweets and articles
+// can be handed to other programs. Json is an enum, one variant per JSON type,
+// the same "IS one of these" idea as the Status enum.
+//
+// Decoding goes through the same checks as building the value by hand,
+// so {"name": "kid", "age": 12} is rejected just like create_student would.
+
+use std::fmt;
+
+use crate::datetime::Timestamp;
+use crate::digest::Tagged;
+use crate::item::Item;
+use crate::lesson_7_traits::{NewsArticle, Tweet, TweetError};
+use crate::status::{Status, Transition};
+use crate::tasks::Priority;
+use crate::{Email, Student, StudentErrors};
+
+// deeper nesting than this is almost certainly garbage (or an attack)
+const MAX_DEPTH: usize = 128;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    // a Vec instead of a map so keys come out in the order they went in
+    Object(Vec<(String, Json)>),
+}
+
+#[derive(Debug)]
+pub enum JsonError {
+    Syntax { line: usize, column: usize, message: String },
+    Field { field: String, message: String },
+    Student(StudentErrors),
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            JsonError::Syntax { line, column, message } => {
+                write!(f, "line {}, column {}: {}", line, column, message)
+            }
+            JsonError::Field { field, message } => write!(f, "field {:?}: {}", field, message),
+            JsonError::Student(errors) => write!(f, "invalid student: {}", errors),
+        }
+    }
+}
+
+impl std::error::Error for JsonError {}
+
+impl Json {
+    pub fn parse(text: &str) -> Result<Json, JsonError> {
+        let mut parser = Parser { chars: text.chars().collect(), pos: 0 };
+        parser.skip_whitespace();
+        let value = parser.value(0)?;
+        parser.skip_whitespace();
+        if parser.pos < parser.chars.len() {
+            return Err(parser.error("unexpected text after the JSON value"));
+        }
+        Ok(value)
+    }
+
+    // looks up a key in an object
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(b) => write!(f, "{}", b),
+            // whole numbers print without the ".0" that f64 would add
+            Json::Number(n) if n.fract() == 0.0 && n.abs() < 1e15 => write!(f, "{}", *n as i64),
+            Json::Number(n) if n.is_finite() => write!(f, "{}", n),
+            // inf and NaN are not JSON, null is what JavaScript writes for them too
+            Json::Number(_) => write!(f, "null"),
+            Json::String(s) => write_string(f, s),
+            Json::Array(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(fields) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+// quotes, backslashes and control characters get escaped, everything else
+// (including emoji like 🦀) is written as plain UTF-8
+fn write_string(f: &mut fmt::Formatter, s: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            '\u{08}' => write!(f, "\\b")?,
+            '\u{0c}' => write!(f, "\\f")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn error(&self, message: &str) -> JsonError {
+        let before = &self.chars[..self.pos.min(self.chars.len())];
+        let line = before.iter().filter(|&&c| c == '\n').count() + 1;
+        let column = before.iter().rev().take_while(|&&c| c != '\n').count() + 1;
+        JsonError::Syntax { line, column, message: message.to_string() }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t' | '\n' | '\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), JsonError> {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected {:?}", c)))
+        }
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Json, JsonError> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("nested too deeply"));
+        }
+        match self.peek() {
+            Some('{') => self.object(depth),
+            Some('[') => self.array(depth),
+            Some('"') => Ok(Json::String(self.string()?)),
+            Some('t') => self.literal("true", Json::Bool(true)),
+            Some('f') => self.literal("false", Json::Bool(false)),
+            Some('n') => self.literal("null", Json::Null),
+            Some('-' | '0'..='9') => self.number(),
+            Some(_) => Err(self.error("expected a JSON value")),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    fn literal(&mut self, word: &str, value: Json) -> Result<Json, JsonError> {
+        for expected in word.chars() {
+            if self.peek() != Some(expected) {
+                return Err(self.error(&format!("expected {}", word)));
+            }
+            self.pos += 1;
+        }
+        Ok(value)
+    }
+
+    fn number(&mut self) -> Result<Json, JsonError> {
+        let start = self.pos;
+        if self.peek() == Some('-') {
+            self.pos += 1;
+        }
+        // no leading zeros, so 0 is fine but 01 is not
+        match self.peek() {
+            Some('0') => self.pos += 1,
+            Some('1'..='9') => self.digits(),
+            _ => return Err(self.error("expected a digit")),
+        }
+        if self.peek() == Some('.') {
+            self.pos += 1;
+            if !matches!(self.peek(), Some('0'..='9')) {
+                return Err(self.error("expected a digit after the decimal point"));
+            }
+            self.digits();
+        }
+        if matches!(self.peek(), Some('e' | 'E')) {
+            self.pos += 1;
+            if matches!(self.peek(), Some('+' | '-')) {
+                self.pos += 1;
+            }
+            if !matches!(self.peek(), Some('0'..='9')) {
+                return Err(self.error("expected a digit in the exponent"));
+            }
+            self.digits();
+        }
+        let text: String = self.chars[start..self.pos].iter().collect();
+        // Rust parses 1e400 as infinity, which JSON has no way to write back
+        match text.parse::<f64>() {
+            Ok(n) if n.is_finite() => Ok(Json::Number(n)),
+            _ => {
+                // point at the start of the number, not the end
+                self.pos = start;
+                Err(self.error("number is out of range"))
+            }
+        }
+    }
+
+    fn digits(&mut self) {
+        while matches!(self.peek(), Some('0'..='9')) {
+            self.pos += 1;
+        }
+    }
+
+    fn hex4(&mut self) -> Result<u32, JsonError> {
+        let mut code = 0;
+        for _ in 0..4 {
+            let digit = self
+                .peek()
+                .and_then(|c| c.to_digit(16))
+                .ok_or_else(|| self.error("expected 4 hex digits after \\u"))?;
+            code = code * 16 + digit;
+            self.pos += 1;
+        }
+        Ok(code)
+    }
+
+    fn string(&mut self) -> Result<String, JsonError> {
+        self.expect('"')?;
+        let mut out = String::new();
+        loop {
+            let c = self.peek().ok_or_else(|| self.error("string is never closed"))?;
+            self.pos += 1;
+            match c {
+                '"' => return Ok(out),
+                '\\' => {
+                    let escape = self.peek().ok_or_else(|| self.error("string is never closed"))?;
+                    self.pos += 1;
+                    match escape {
+                        '"' => out.push('"'),
+                        '\\' => out.push('\\'),
+                        '/' => out.push('/'),
+                        'b' => out.push('\u{08}'),
+                        'f' => out.push('\u{0c}'),
+                        'n' => out.push('\n'),
+                        'r' => out.push('\r'),
+                        't' => out.push('\t'),
+                        'u' => out.push(self.unicode_escape()?),
+                        _ => return Err(self.error("unknown escape")),
+                    }
+                }
+                c if (c as u32) < 0x20 => return Err(self.error("control character inside a string")),
+                c => out.push(c),
+            }
+        }
+    }
+
+    // \uXXXX, characters outside the BMP (like 🦀) come as a surrogate pair like \\ud83e\\udd80
+    fn unicode_escape(&mut self) -> Result<char, JsonError> {
+        let high = self.hex4()?;
+        let code = match high {
+            0xD800..=0xDBFF => {
+                if self.peek() != Some('\\') || self.chars.get(self.pos + 1) != Some(&'u') {
+                    return Err(self.error("high surrogate without a low surrogate"));
+                }
+                self.pos += 2;
+                let low = self.hex4()?;
+                if !(0xDC00..=0xDFFF).contains(&low) {
+                    return Err(self.error("invalid low surrogate"));
+                }
+                0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+            }
+            0xDC00..=0xDFFF => return Err(self.error("low surrogate without a high surrogate")),
+            code => code,
+        };
+        char::from_u32(code).ok_or_else(|| self.error("invalid unicode escape"))
+    }
+
+    fn array(&mut self, depth: usize) -> Result<Json, JsonError> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.pos += 1;
+            return Ok(Json::Array(items));
+        }
+        loop {
+            self.skip_whitespace();
+            items.push(self.value(depth + 1)?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some(']') => {
+                    self.pos += 1;
+                    return Ok(Json::Array(items));
+                }
+                _ => return Err(self.error("expected ',' or ']'")),
+            }
+        }
+    }
+
+    fn object(&mut self, depth: usize) -> Result<Json, JsonError> {
+        self.expect('{')?;
+        let mut fields: Vec<(String, Json)> = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.pos += 1;
+            return Ok(Json::Object(fields));
+        }
+        loop {
+            self.skip_whitespace();
+            let key_pos = self.pos;
+            let key = self.string()?;
+            if fields.iter().any(|(k, _)| *k == key) {
+                self.pos = key_pos;
+                return Err(self.error(&format!("duplicate key {:?}", key)));
+            }
+            self.skip_whitespace();
+            self.expect(':')?;
+            self.skip_whitespace();
+            let value = self.value(depth + 1)?;
+            fields.push((key, value));
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some('}') => {
+                    self.pos += 1;
+                    return Ok(Json::Object(fields));
+                }
+                _ => return Err(self.error("expected ',' or '}'")),
+            }
+        }
+    }
+}
+
+// ============================================
+// Converting our structs to and from Json
+// ============================================
+
+pub trait ToJson {
+    fn to_json(&self) -> Json;
+}
+
+pub trait FromJson: Sized {
+    fn from_json(value: &Json) -> Result<Self, JsonError>;
+}
+
+fn field_error(field: &str, message: &str) -> JsonError {
+    JsonError::Field { field: field.to_string(), message: message.to_string() }
+}
+
+// checks the value is an object with only the keys we know about
+fn expect_object<'a>(value: &'a Json, allowed: &[&str]) -> Result<&'a [(String, Json)], JsonError> {
+    match value {
+        Json::Object(fields) => {
+            if let Some((key, _)) = fields.iter().find(|(k, _)| !allowed.contains(&k.as_str())) {
+                return Err(field_error(key, "unknown field"));
+            }
+            Ok(fields)
+        }
+        _ => Err(field_error("", "expected an object")),
+    }
+}
+
+fn string_field(value: &Json, field: &str) -> Result<String, JsonError> {
+    match value.get(field) {
+        Some(Json::String(s)) => Ok(s.clone()),
+        Some(_) => Err(field_error(field, "expected a string")),
+        None => Err(field_error(field, "missing")),
+    }
+}
+
+impl ToJson for Student {
+    fn to_json(&self) -> Json {
+        Json::Object(vec![
+            (String::from("name"), Json::String(self.name.clone())),
+            (String::from("age"), Json::Number(self.age as f64)),
+            (String::from("status"), Json::String(self.status.name().to_string())),
+            (String::from("history"), Json::Array(self.history.iter().map(ToJson::to_json).collect())),
+        ])
+    }
+}
+
+impl FromJson for Student {
+    fn from_json(value: &Json) -> Result<Student, JsonError> {
+        expect_object(value, &["name", "age", "status", "history", "is_active"])?;
+        let name = string_field(value, "name")?;
+        let age = match value.get("age") {
+            Some(Json::Number(n)) if n.fract() == 0.0 && (0.0..=255.0).contains(n) => *n as u8,
+            Some(Json::Number(_)) => return Err(field_error("age", "expected a whole number from 0 to 255")),
+            Some(_) => return Err(field_error("age", "expected a number")),
+            None => return Err(field_error("age", "missing")),
+        };
+        let mut student = Student::create_student(name, age).map_err(JsonError::Student)?;
+        // status is optional, new students are active. older JSON has is_active instead
+        match (value.get("status"), value.get("is_active")) {
+            (Some(_), Some(_)) => return Err(field_error("is_active", "can't be used together with status")),
+            (Some(Json::String(name)), None) => {
+                student.status = Status::from_name(name).ok_or_else(|| field_error("status", "unknown status"))?;
+            }
+            (Some(_), None) => return Err(field_error("status", "expected a string")),
+            (None, Some(Json::Bool(true))) => student.status = Status::Active,
+            (None, Some(Json::Bool(false))) => student.status = Status::Inactive,
+            (None, Some(_)) => return Err(field_error("is_active", "expected true or false")),
+            (None, None) => {}
+        }
+        match value.get("history") {
+            Some(Json::Array(items)) => {
+                student.history = items.iter().map(Transition::from_json).collect::<Result<_, _>>()?;
+            }
+            Some(_) => return Err(field_error("history", "expected an array")),
+            None => {}
+        }
+        if let Some(last) = student.history.last()
+            && last.to != student.status
+        {
+            return Err(field_error("status", "does not match the end of the history"));
+        }
+        Ok(student)
+    }
+}
+
+impl ToJson for Transition {
+    fn to_json(&self) -> Json {
+        Json::Object(vec![
+            (String::from("at"), Json::String(self.at.to_string())),
+            (String::from("from"), Json::String(self.from.name().to_string())),
+            (String::from("to"), Json::String(self.to.name().to_string())),
+            (String::from("reason"), Json::String(self.reason.clone())),
+        ])
+    }
+}
+
+impl FromJson for Transition {
+    fn from_json(value: &Json) -> Result<Transition, JsonError> {
+        expect_object(value, &["at", "from", "to", "reason"])?;
+        let status = |field: &str| {
+            Status::from_name(&string_field(value, field)?).ok_or_else(|| field_error(field, "unknown status"))
+        };
+        let reason = string_field(value, "reason")?;
+        if reason.contains(char::is_control) {
+            return Err(field_error("reason", "must not contain control characters"));
+        }
+        Ok(Transition {
+            at: Timestamp::parse_rfc3339(&string_field(value, "at")?)
+                .ok_or_else(|| field_error("at", "expected an RFC 3339 time"))?,
+            from: status("from")?,
+            to: status("to")?,
+            reason,
+        })
+    }
+}
+
+impl ToJson for Email {
+    fn to_json(&self) -> Json {
+        Json::Object(vec![
+            (String::from("from"), Json::String(self.from.to_string())),
+            (String::from("subject"), Json::String(self.subject.clone())),
+        ])
+    }
+}
+
+impl FromJson for Email {
+    fn from_json(value: &Json) -> Result<Email, JsonError> {
+        expect_object(value, &["from", "subject"])?;
+        let from = string_field(value, "from")?;
+        Email::new(&from, &string_field(value, "subject")?).map_err(|e| field_error("from", &e.to_string()))
+    }
+}
+
+impl ToJson for Tweet {
+    fn to_json(&self) -> Json {
+        Json::Object(vec![
+            (String::from("username"), Json::String(self.username().to_string())),
+            (String::from("content"), Json::String(self.content().to_string())),
+        ])
+    }
+}
+
+impl FromJson for Tweet {
+    fn from_json(value: &Json) -> Result<Tweet, JsonError> {
+        expect_object(value, &["username", "content"])?;
+        let username = string_field(value, "username")?;
+        Tweet::new(&username, &string_field(value, "content")?).map_err(|e| match e {
+            TweetError::BadUsername(_) => field_error("username", "must be one word"),
+            other => field_error("content", &other.to_string()),
+        })
+    }
+}
+
+impl ToJson for NewsArticle {
+    fn to_json(&self) -> Json {
+        let mut fields = vec![
+            (String::from("headline"), Json::String(self.headline.clone())),
+            (String::from("author"), Json::String(self.author.clone())),
+        ];
+        // the optional fields are left out when they are None
+        if let Some(link) = &self.link {
+            fields.push((String::from("link"), Json::String(link.clone())));
+        }
+        if let Some(published) = self.published {
+            fields.push((String::from("published"), Json::String(published.to_string())));
+        }
+        if let Some(summary) = &self.summary {
+            fields.push((String::from("summary"), Json::String(summary.clone())));
+        }
+        Json::Object(fields)
+    }
+}
+
+impl FromJson for NewsArticle {
+    fn from_json(value: &Json) -> Result<NewsArticle, JsonError> {
+        expect_object(value, &["headline", "author", "link", "published", "summary"])?;
+        let headline = string_field(value, "headline")?;
+        if headline.trim().is_empty() {
+            return Err(field_error("headline", "must not be empty"));
+        }
+        let optional = |field: &str| match value.get(field) {
+            None => Ok(None),
+            Some(_) => string_field(value, field).map(Some),
+        };
+        let published = match optional("published")? {
+            None => None,
+            Some(text) => Some(
+                Timestamp::parse_rfc3339(&text)
+                    .ok_or_else(|| field_error("published", "expected a time like 2026-10-18T07:51:11Z"))?,
+            ),
+        };
+        Ok(NewsArticle {
+            headline,
+            author: string_field(value, "author")?,
+            link: optional("link")?,
+            published,
+            summary: optional("summary")?,
+        })
+    }
+}
+
+// a JSON array of students, e.g. a whole roster
+pub fn students_from_json(text: &str) -> Result<Vec<Student>, JsonError> {
+    match Json::parse(text)? {
+        Json::Array(items) => items.iter().map(Student::from_json).collect(),
+        _ => Err(field_error("", "expected an array of students")),
+    }
+}
+
+pub fn students_to_json(students: &[Student]) -> String {
+    Json::Array(students.iter().map(ToJson::to_json).collect()).to_string()
+}
+
+// a digest file is an array of
+//   {"type": "tweet", "item": {...the tweet...}, "priority": "high", "published": "2026-10-18T07:51:11Z"}
+// priority and published can be left out
+pub fn digest_items_from_json(text: &str) -> Result<Vec<Box<dyn Item>>, JsonError> {
+    let Json::Array(entries) = Json::parse(text)? else {
+        return Err(field_error("", "expected an array of digest items"));
+    };
+    let mut items: Vec<Box<dyn Item>> = Vec::new();
+    for entry in &entries {
+        expect_object(entry, &["type", "item", "priority", "published"])?;
+        let value = entry.get("item").ok_or_else(|| field_error("item", "missing"))?;
+        let item: Box<dyn Item> = match string_field(entry, "type")?.as_str() {
+            "tweet" => Box::new(Tweet::from_json(value)?),
+            "article" => Box::new(NewsArticle::from_json(value)?),
+            "email" => Box::new(Email::from_json(value)?),
+            _ => return Err(field_error("type", "must be tweet, article or email")),
+        };
+        let priority = match entry.get("priority") {
+            None => item.priority(),
+            Some(Json::String(name)) => {
+                Priority::from_name(name).ok_or_else(|| field_error("priority", "must be low, medium or high"))?
+            }
+            Some(_) => return Err(field_error("priority", "expected a string")),
+        };
+        let published = match entry.get("published") {
+            None => item.published(),
+            Some(_) => Some(
+                Timestamp::parse_rfc3339(&string_field(entry, "published")?)
+                    .ok_or_else(|| field_error("published", "expected a time like 2026-10-18T07:51:11Z"))?,
+            ),
+        };
+        items.push(Box::new(Tagged { item, priority, published }));
+    }
+    Ok(items)
+}
@@ -1,54 +1,385 @@
-use std ::fs;
+use std ::fmt;
 use std ::io;
 
+mod address;
+mod atomic_write;
+mod base64;
+mod csv;
+mod data_dir;
+mod digest;
+mod feed;
+mod fuzzy;
+mod http;
+mod item;
+mod json;
+mod lesson_7_traits;
+mod limiter;
+mod lock;
+mod message;
+mod notifier;
+mod outbox;
+mod query;
+mod roster;
+mod schema;
+mod shell;
+mod smtp;
+mod status;
+mod subscriptions;
+mod tasks;
+mod template;
+mod xml;
+
+// lesson 7 builds on its own with rustc, so it brings in these two std only
+// files itself, and the rest of the program uses them from there
+use lesson_7_traits::{datetime, unicode};
+
+use address::{AddressError, EmailAddress};
+use data_dir::DataDir;
+use lock::{FileLock, Mode};
+use message::{Attachment, MessageBuilder};
+use notifier::Notification;
+use outbox::{OutboxError, Report};
+use smtp::{Delivery, SmtpConfig, SmtpError};
+use status::{Status, Transition, TransitionError};
+
 struct Student{
     name: String,
     age: u8,
-    is_active: bool,
+    // status replaced `is_active: bool`, it can only change through the methods in status.rs
+    status: Status,
+    history: Vec<Transition>,
+}
+
+// the rules create_student checks
+const MIN_AGE: u8 = 18;
+const MAX_AGE: u8 = 120;
+const MAX_NAME_CHARS: usize = 64;
+
+// one thing that is wrong with a student, each variant knows which field it is about
+#[derive(Debug, Clone, PartialEq)]
+enum StudentError {
+    Underage { age: u8 },
+    ImplausibleAge { age: u8 },
+    EmptyName,
+    NameTooLong { chars: usize },
+    NameHasControlChar { position: usize },
+}
+
+impl StudentError {
+    // the form field to highlight
+    fn field(&self) -> &'static str {
+        match self {
+            StudentError::Underage { .. } | StudentError::ImplausibleAge { .. } => "age",
+            StudentError::EmptyName
+            | StudentError::NameTooLong { .. }
+            | StudentError::NameHasControlChar { .. } => "name",
+        }
+    }
+}
+
+impl fmt::Display for StudentError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StudentError::Underage { age } => {
+                write!(f, "age {} is too young, students must be at least {}", age, MIN_AGE)
+            }
+            StudentError::ImplausibleAge { age } => {
+                write!(f, "age {} is not plausible, the limit is {}", age, MAX_AGE)
+            }
+            StudentError::EmptyName => write!(f, "name must not be empty"),
+            StudentError::NameTooLong { chars } => {
+                write!(f, "name is {} characters long, the limit is {}", chars, MAX_NAME_CHARS)
+            }
+            StudentError::NameHasControlChar { position } => {
+                write!(f, "name has a control character at position {}", position)
+            }
+        }
+    }
+}
+
+impl std::error::Error for StudentError {}
+
+// create_student checks every field before giving up, so this holds all the problems at once
+#[derive(Debug, Clone, PartialEq)]
+struct StudentErrors(Vec<StudentError>);
+
+impl StudentErrors {
+    fn iter(&self) -> std::slice::Iter<'_, StudentError> {
+        self.0.iter()
+    }
+}
+
+impl fmt::Display for StudentErrors {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, error) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{}", error)?;
+        }
+        Ok(())
+    }
 }
 
+impl std::error::Error for StudentErrors {}
+
 impl Student{
-    // We return Result<Student, String> (String is our error type)
-    fn create_student(name: String, age: u8) -> Result<Student, String> {
-        if age<18{
-          return Err(String::from("age is not valid. student must be older than 18"))
-        }else{
+    // We return Result<Student, StudentErrors> so the caller sees every bad field, not just the first
+    fn create_student(name: String, age: u8) -> Result<Student, StudentErrors> {
+        let mut errors = Vec::new();
+
+        if name.trim().is_empty() {
+            errors.push(StudentError::EmptyName);
+        } else {
+            let chars = name.chars().count();
+            if chars > MAX_NAME_CHARS {
+                errors.push(StudentError::NameTooLong { chars });
+            }
+            if let Some(position) = name.chars().position(char::is_control) {
+                errors.push(StudentError::NameHasControlChar { position });
+            }
+        }
+
+        if age < MIN_AGE {
+            errors.push(StudentError::Underage { age });
+        } else if age > MAX_AGE {
+            errors.push(StudentError::ImplausibleAge { age });
+        }
+
+        if errors.is_empty() {
             Ok(Student{
                 name,
                 age,
-                is_active:true
+                status: Status::Active,
+                history: Vec::new(),
             })
+        } else {
+            Err(StudentErrors(errors))
         }
-       
+    }
+
+    // same checks, but the student waits for approve_pending before they are active
+    fn create_pending(name: String, age: u8) -> Result<Student, StudentErrors> {
+        let mut student = Student::create_student(name, age)?;
+        student.status = Status::Pending;
+        Ok(student)
+    }
+
+    fn is_active(&self) -> bool {
+        self.status == Status::Active
+    }
 }
 
+// the moves and their rules are in status.rs, the student keeps the result
+impl Student {
+    fn activate(&mut self, reason: &str) -> Result<(), TransitionError> {
+        let step = self.status.activate(reason)?;
+        self.record(step);
+        Ok(())
+    }
+
+    fn suspend(&mut self, reason: &str) -> Result<(), TransitionError> {
+        let step = self.status.suspend(reason)?;
+        self.record(step);
+        Ok(())
+    }
+
+    fn approve_pending(&mut self, reason: &str) -> Result<(), TransitionError> {
+        let step = self.status.approve_pending(reason)?;
+        self.record(step);
+        Ok(())
+    }
+
+    fn withdraw(&mut self, reason: &str) -> Result<(), TransitionError> {
+        let step = self.status.withdraw(reason)?;
+        self.record(step);
+        Ok(())
+    }
+
+    fn record(&mut self, step: Transition) {
+        self.status = step.to;
+        self.history.push(step);
+    }
 }
+
 // so this is how you read a file in rust you use fs::Read_to_string(then the file name in string form).
 // it has inbuilt error handling so you can use? and it will return error if the file is not found
-fn load_task()-> Result<String,io::Error>{
-    let content = fs::read_to_string("task.txt")?;
+// now the file comes from the data directory instead of the folder we were started in
+fn load_task(data: &DataDir)-> Result<String,io::Error>{
+    let path = data.file("task.txt");
+    let _lock = FileLock::acquire(&path, Mode::Shared)?;
+    let content = data_dir::read_to_string(&path)?;
     Ok(content)
 }
 
-fn save_student(student: &Student)-> Result<(),io::Error>{
-    let content = format!("Name:{}\nAge:{}\nis_active{}",student.name,student.age,student.is_active);
-    fs::write("student.txt",content)?;
+// format_student is the text layout of one student, the roster uses it too.
+// every history entry is one line: time|from|to|reason
+fn format_student(student: &Student) -> String {
+    let mut content = format!("Name:{}\nAge:{}\nStatus:{}",student.name,student.age,student.status);
+    for step in &student.history {
+        content.push_str(&format!("\nHistory:{}|{}|{}|{}", step.at, step.from, step.to, step.reason));
+    }
+    content
+}
+
+fn save_student(data: &DataDir, student: &Student)-> Result<(),io::Error>{
+    let content = format!("{}{}", schema::header(), format_student(student));
+    let path = data.file("student.txt");
+    // the lock keeps another copy of the program from writing at the same time,
+    // write_atomic never leaves a half written student.txt behind, even if we crash
+    let _lock = FileLock::acquire(&path, Mode::Exclusive)?;
+    // an old student.txt is only replaced by migrate, which keeps a backup
+    schema::check_on_disk(&path)?;
+    atomic_write::write_atomic(&path,content.as_bytes())?;
     // we use OK(()) to return no value the () returns nothing just success
     Ok(())
 }
 
+// load_student is the other half of save_student, it reads student.txt back into a Student.
+// errors carry the line number so you can find the bad line in the file
+#[derive(Debug)]
+enum LoadError {
+    Io(io::Error),
+    Malformed { line: usize, reason: String },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Io(e) => write!(f, "could not read student file: {}", e),
+            LoadError::Malformed { line, reason } => write!(f, "line {}: {}", line, reason),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+// this lets ? turn an io::Error into a LoadError for us
+impl From<io::Error> for LoadError {
+    fn from(e: io::Error) -> LoadError {
+        LoadError::Io(e)
+    }
+}
+
+fn load_student(data: &DataDir) -> Result<Student, LoadError> {
+    let path = data.file("student.txt");
+    let _lock = FileLock::acquire(&path, Mode::Shared)?;
+    let content = data_dir::read_to_string(&path)?;
+    parse_student(&content)
+}
+
+// older files are upgraded to the current layout first, see schema.rs
+fn parse_student(content: &str) -> Result<Student, LoadError> {
+    let upgraded = schema::upgrade(content)?;
+    parse_record(upgraded.body.lines().enumerate().map(|(index, text)| (index + 1, text)))
+}
+
+// parse_record takes (line number, text) pairs so a record in the middle of a bigger
+// file (like the roster) still reports the real line number
+fn parse_record<'a>(lines: impl Iterator<Item = (usize, &'a str)>) -> Result<Student, LoadError> {
+    let mut name: Option<(usize, String)> = None;
+    let mut age: Option<(usize, u8)> = None;
+    let mut status: Option<(usize, Status)> = None;
+    let mut history: Vec<Transition> = Vec::new();
+    let mut last_line = 0;
+
+    for (line, raw) in lines {
+        last_line = line;
+        let text = raw.trim_end_matches('\r');
+        if text.trim().is_empty() {
+            continue;
+        }
+        let malformed = |reason: String| LoadError::Malformed { line, reason };
+
+        if let Some(value) = text.strip_prefix("Name:") {
+            if name.is_some() {
+                return Err(malformed(String::from("Name appears more than once")));
+            }
+            name = Some((line, value.to_string()));
+        } else if let Some(value) = text.strip_prefix("Age:") {
+            if age.is_some() {
+                return Err(malformed(String::from("Age appears more than once")));
+            }
+            let parsed = value
+                .trim()
+                .parse::<u8>()
+                .map_err(|_| malformed(format!("Age must be a number from 0 to 255, got {:?}", value)))?;
+            age = Some((line, parsed));
+        } else if let Some(value) = text.strip_prefix("Status:") {
+            if status.is_some() {
+                return Err(malformed(String::from("Status appears more than once")));
+            }
+            let parsed = Status::from_name(value.trim())
+                .ok_or_else(|| malformed(format!("unknown status {:?}", value)))?;
+            status = Some((line, parsed));
+        } else if let Some(value) = text.strip_prefix("History:") {
+            history.push(parse_transition(value).ok_or_else(|| {
+                malformed(format!("History must look like TIME|FROM|TO|REASON, got {:?}", value))
+            })?);
+        } else {
+            return Err(malformed(format!("unexpected line {:?}", text)));
+        }
+    }
+
+    let missing = |field: &str| LoadError::Malformed {
+        line: last_line + 1,
+        reason: format!("missing {} line", field),
+    };
+    let (name_line, name) = name.ok_or_else(|| missing("Name"))?;
+    let (age_line, age) = age.ok_or_else(|| missing("Age"))?;
+    let (status_line, status) = status.ok_or_else(|| missing("Status"))?;
+
+    // the history has to end where the status says we are
+    if let Some(last) = history.last()
+        && last.to != status
+    {
+        return Err(LoadError::Malformed {
+            line: status_line,
+            reason: format!("Status is {} but the history ends at {}", status, last.to),
+        });
+    }
+
+    // the file has to pass the same checks as a student made by hand,
+    // we point at the line of the first field that failed
+    let mut student = Student::create_student(name, age).map_err(|errors| {
+        let line = match errors.iter().next().map(StudentError::field) {
+            Some("name") => name_line,
+            _ => age_line,
+        };
+        LoadError::Malformed { line, reason: errors.to_string() }
+    })?;
+    student.status = status;
+    student.history = history;
+    Ok(student)
+}
+
+fn parse_transition(text: &str) -> Option<Transition> {
+    let mut parts = text.splitn(4, '|');
+    Some(Transition {
+        at: datetime::Timestamp::parse_rfc3339(parts.next()?)?,
+        from: Status::from_name(parts.next()?)?,
+        to: Status::from_name(parts.next()?)?,
+        reason: parts.next()?.to_string(),
+    })
+}
+
 // so this is how we implement a trait in rust we use impl then followed by the trait name
 // 
 pub trait Summary{
     fn summarize(&self)-> String;
 
-    fn notify(&self){
-        println!("an email has been sent: {}",self.summarize());
+    // goes to the sinks in LEARNING_PROJECT_NOTIFY (stdout if it isn't set),
+    // through the outbox, so a sink that fails gets it again later.
+    // the notification template (see item.rs) writes it, it only gets the summary here
+    fn notify(&self) -> Result<Report, OutboxError>{
+        let context = json::Json::Object(vec![(String::from("summary"), json::Json::String(self.summarize()))]);
+        outbox::notify(item::notification("an email has been sent", context)?)
     }
     
 }
+// from is an EmailAddress, so an Email can only be made with an address that parsed
 struct Email{
-    from:String,
+    from:EmailAddress,
     subject:String,
 }
 // so this is how we use traits with structs and functions we use impl the trait name than the struct name
@@ -58,18 +389,433 @@ impl Summary for Email{
     }
 
 }
+// lesson 7 has its own Summary trait, that one is what the digest takes,
+// so an Email can go into a digest next to tweets and articles
+impl lesson_7_traits::Summary for Email{
+    fn summarize(&self)-> String{
+        Summary::summarize(self)
+    }
+
+    fn kind(&self)-> &'static str{
+        "email"
+    }
+
+    fn source(&self)-> String{
+        self.from.addr_spec()
+    }
+}
+
+// and what the rest of the program needs from it, see item.rs
+impl item::Item for Email{
+    fn fields(&self)-> Vec<(String, json::Json)>{
+        vec![
+            (String::from("from"), json::Json::String(self.from.to_string())),
+            (String::from("subject"), json::Json::String(self.subject.clone())),
+        ]
+    }
+}
+
+// the trait's notify only tells the notification sinks, this one really sends the email over SMTP.
+// (a method on the struct wins over the trait method with the same name,
+// Summary::notify(&email) still gets the trait's one)
+impl Email{
+    // "Ekom Otu <ekom@example.com>" or just "ekom@example.com"
+    fn new(from: &str, subject: &str) -> Result<Email, AddressError>{
+        Ok(Email{ from: EmailAddress::parse(from)?, subject: subject.to_string() })
+    }
+
+    // a message from this email's sender with its subject, add recipients and a body to it
+    fn message(&self) -> MessageBuilder{
+        MessageBuilder::new(&self.from).subject(&self.subject)
+    }
+
+    // `fill` adds the recipients and the body, like
+    // email.notify(&smtp, |message| message.to("ann@example.com").text("hello"))
+    fn notify(&self, smtp: &SmtpConfig, fill: impl FnOnce(MessageBuilder) -> MessageBuilder) -> Result<Delivery, SmtpError>{
+        smtp::send_message(smtp, &fill(self.message()))
+    }
+}
+
+// `learning_project email send|build --from ADDR --subject TEXT --to ADDR ... BODY...`
+// send needs a server, from --smtp HOST:PORT or LEARNING_PROJECT_SMTP, and logs in when
+// LEARNING_PROJECT_SMTP_USER and LEARNING_PROJECT_SMTP_PASSWORD are set.
+// build prints the raw message instead, to see what would be sent
+fn run_email(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let usage = "usage: email <send|build> --from ADDR --subject TEXT [--to|--cc|--bcc ADDR]... \
+                 [--reply-to ADDR] [--html FILE] [--attach FILE]... [--date RFC3339] [--message-id ID] \
+                 [--smtp HOST:PORT] BODY...";
+    let Some((action, flags)) = args.split_first() else {
+        return Err(usage.into());
+    };
+
+    // addresses are checked as they are read, the error says which flag was wrong
+    let address = |flag: &str, text: String| {
+        EmailAddress::parse(&text).map_err(|e| format!("{} {:?}: {}", flag, text, e))
+    };
+    let mut from = None;
+    let mut subject = None;
+    let mut server = std::env::var("LEARNING_PROJECT_SMTP").ok();
+    // (--to, --cc or --bcc, address) in the order they were given
+    let mut recipients: Vec<(String, EmailAddress)> = Vec::new();
+    let mut reply_to = None;
+    let mut date = None;
+    let mut message_id = None;
+    let mut html = None;
+    let mut attachments = Vec::new();
+    let mut body = Vec::new();
+    let mut flags = flags.iter();
+    while let Some(flag) = flags.next() {
+        let mut value = || flags.next().cloned().ok_or_else(|| format!("{} needs a value", flag));
+        match flag.as_str() {
+            "--from" => from = Some(value()?),
+            "--subject" => subject = Some(value()?),
+            "--to" | "--cc" | "--bcc" => recipients.push((flag.clone(), address(flag, value()?)?)),
+            "--reply-to" => reply_to = Some(address(flag, value()?)?),
+            "--date" => {
+                let text = value()?;
+                let parsed = datetime::Timestamp::parse_rfc3339(&text);
+                date = Some(parsed.ok_or_else(|| format!("--date must look like 2026-10-18T07:51:11Z, got {:?}", text))?);
+            }
+            "--message-id" => message_id = Some(value()?),
+            "--html" => html = Some(data_dir::read_to_string(value()?.as_ref())?),
+            "--attach" => {
+                let file = std::path::PathBuf::from(value()?);
+                let data = std::fs::read(&file).map_err(|e| data_dir::with_path(e, &file))?;
+                let name = file.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+                attachments.push(Attachment::new(&name, data));
+            }
+            "--smtp" => server = Some(value()?),
+            word => body.push(word.to_string()),
+        }
+    }
+    let (Some(from), Some(subject)) = (from, subject) else {
+        return Err(usage.into());
+    };
+
+    let fill = |mut message: MessageBuilder| {
+        for (kind, address) in &recipients {
+            message = match kind.as_str() {
+                "--cc" => message.cc(address),
+                "--bcc" => message.bcc(address),
+                _ => message.to(address),
+            };
+        }
+        if let Some(reply_to) = &reply_to {
+            message = message.reply_to(reply_to);
+        }
+        if let Some(date) = date {
+            message = message.date(date);
+        }
+        if let Some(id) = &message_id {
+            message = message.message_id(id);
+        }
+        message = message.text(&body.join(" "));
+        if let Some(html) = &html {
+            message = message.html(html);
+        }
+        for attachment in &attachments {
+            message = message.attach(attachment.clone());
+        }
+        message
+    };
+
+    let email = Email::new(&from, &subject).map_err(|e| format!("--from {:?}: {}", from, e))?;
+    match action.as_str() {
+        "build" => {
+            use std::io::Write;
+            io::stdout().write_all(&fill(email.message()).build()?)?;
+        }
+        "send" => {
+            let server = server.ok_or("no mail server, use --smtp HOST:PORT or LEARNING_PROJECT_SMTP")?;
+            let mut smtp = SmtpConfig::new(&server);
+            if let Ok(user) = std::env::var("LEARNING_PROJECT_SMTP_USER") {
+                smtp = smtp.login(&user, &std::env::var("LEARNING_PROJECT_SMTP_PASSWORD").unwrap_or_default());
+            }
+            let delivery = email.notify(&smtp, fill)?;
+            for address in &delivery.accepted {
+                println!("sent to {}", address);
+            }
+            for refused in &delivery.rejected {
+                println!("refused {}", refused);
+            }
+            // the email went out, but not to everyone
+            if !delivery.rejected.is_empty() {
+                let total = delivery.accepted.len() + delivery.rejected.len();
+                return Err(format!("{} of {} recipients were refused", delivery.rejected.len(), total).into());
+            }
+        }
+        _ => return Err(usage.into()),
+    }
+    Ok(())
+}
+
+// `learning_project student ...` works on the single student.txt file
+fn run_student(data: &DataDir, args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let words: Vec<&str> = args.iter().map(String::as_str).collect();
+    match words.as_slice() {
+        ["save", name, age] => {
+            let age: u8 = age.parse().map_err(|_| format!("age must be a number, got {:?}", age))?;
+            let student = Student::create_student(name.to_string(), age)?;
+            save_student(data, &student)?;
+            println!("saved {}", student.name);
+        }
+        ["show"] => {
+            let student = load_student(data)?;
+            println!("{} ({}), {}", student.name, student.age, student.status);
+        }
+        _ => return Err("usage: student <save NAME AGE | show>".into()),
+    }
+    Ok(())
+}
+
+// `learning_project notify [--title TEXT] TEXT...` sends one notification to the
+// sinks in LEARNING_PROJECT_NOTIFY, every sink that failed is in the error
+// (and gets it again from the outbox later)
+fn run_notify(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let usage = "usage: notify [--title TEXT] TEXT...";
+    let mut title = String::from("notification");
+    let mut text = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--title" => title = args.next().cloned().ok_or("--title needs a value")?,
+            _ => text.push(arg.as_str()),
+        }
+    }
+    if text.is_empty() {
+        return Err(usage.into());
+    }
+    check_report(outbox::notify(Notification::new(&title, &text.join(" ")))?)
+}
+
+// what the limiter held back goes to stderr, the sinks that failed become the error.
+// (it is still in the outbox for those, but the caller should know)
+fn check_report(report: Report) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(held_back) = report.held_back() {
+        eprintln!("{}", held_back);
+    }
+    if report.failed.is_empty() {
+        Ok(())
+    } else {
+        let failures: Vec<String> = report.failed.iter().map(|failure| failure.to_string()).collect();
+        Err(failures.join("; ").into())
+    }
+}
+
+// `learning_project digest FILE` prints the items in a JSON file as one roundup,
+// with --send it goes out as a single notification instead
+fn run_digest(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    use digest::{Digest, Format, Grouping, Order};
+
+    let usage = "usage: digest FILE [--title TEXT] [--group kind|source|none] [--order priority|recent] \
+                 [--format markdown|html|text] [--send]";
+    let mut file = None;
+    let mut title = String::from("Daily digest");
+    let mut grouping = Grouping::Kind;
+    let mut order = Order::Priority;
+    let mut format = Format::Markdown;
+    let mut send = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().cloned().ok_or_else(|| format!("{} needs a value", arg));
+        match arg.as_str() {
+            "--title" => title = value()?,
+            "--group" => {
+                let name = value()?;
+                grouping = Grouping::from_name(&name).ok_or_else(|| format!("--group must be kind, source or none, got {:?}", name))?;
+            }
+            "--order" => {
+                let name = value()?;
+                order = Order::from_name(&name).ok_or_else(|| format!("--order must be priority or recent, got {:?}", name))?;
+            }
+            "--format" => {
+                let name = value()?;
+                format = Format::from_name(&name).ok_or_else(|| format!("--format must be markdown, html or text, got {:?}", name))?;
+            }
+            "--send" => send = true,
+            _ if file.is_none() => file = Some(arg.clone()),
+            _ => return Err(usage.into()),
+        }
+    }
+    let file = file.ok_or(usage)?;
+    let items = json::digest_items_from_json(&data_dir::read_to_string(file.as_ref())?)?;
+    let text = Digest::new(&title, items).group_by(grouping).order_by(order).render(format);
+    if !send {
+        print!("{}", text);
+        return Ok(());
+    }
+    check_report(outbox::notify(Notification::new(&title, &text))?)
+}
+
+// `learning_project tweet USERNAME TEXT...` checks a tweet and shows what counts
+// towards its length, --width N cuts the summary to N terminal columns
+fn run_tweet(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    use lesson_7_traits::{MAX_TWEET, Tweet};
+    use lesson_7_traits::Summary as _;
+
+    let usage = "usage: tweet [--width N] USERNAME TEXT...";
+    let mut width = None;
+    let mut words = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--width" => {
+                let value = args.next().ok_or("--width needs a value")?;
+                width = Some(value.parse::<usize>().map_err(|_| format!("--width must be a number, got {:?}", value))?);
+            }
+            _ => words.push(arg.as_str()),
+        }
+    }
+    let [username, text @ ..] = words.as_slice() else {
+        return Err(usage.into());
+    };
+    if text.is_empty() {
+        return Err(usage.into());
+    }
+    let tweet = Tweet::new(username, &text.join(" "))?;
+    match width {
+        Some(width) => println!("{}", tweet.summarize_to(width)),
+        None => println!("{}", tweet.summarize()),
+    }
+    println!("{}/{} characters", tweet.length(), MAX_TWEET);
+    let tagged = |prefix: &str, names: &[String]| names.iter().map(|name| format!("{}{}", prefix, name)).collect::<Vec<_>>().join(" ");
+    if !tweet.mentions().is_empty() {
+        println!("mentions: {}", tagged("@", tweet.mentions()));
+    }
+    if !tweet.hashtags().is_empty() {
+        println!("hashtags: {}", tagged("#", tweet.hashtags()));
+    }
+    Ok(())
+}
+
+// `learning_project render TEMPLATE FILE` fills in a template file with the items
+// of a digest file (see run_digest), they are `items` in the template and the
+// title is `title`. With --send the result goes out as a notification.
+fn run_render(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    use json::Json;
+    use template::Template;
+
+    let usage = "usage: render TEMPLATE FILE [--title TEXT] [--send]";
+    let mut files = Vec::new();
+    let mut title = String::from("Daily digest");
+    let mut send = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--title" => title = args.next().cloned().ok_or("--title needs a value")?,
+            "--send" => send = true,
+            _ => files.push(arg.as_str()),
+        }
+    }
+    let [template, file] = files.as_slice() else {
+        return Err(usage.into());
+    };
+    let template = Template::load(template.as_ref())?;
+    let items = json::digest_items_from_json(&data_dir::read_to_string(file.as_ref())?)?;
+    let data = Json::Object(vec![
+        (String::from("title"), Json::String(title.clone())),
+        (String::from("items"), Json::Array(items.iter().map(|item| template::context(item.as_ref())).collect())),
+    ]);
+    let text = template.render(&data)?;
+    if !send {
+        print!("{}", text);
+        return Ok(());
+    }
+    check_report(outbox::notify(Notification::new(&title, text.trim_end()))?)
+}
 
+// `learning_project json KIND FILE` decodes a JSON file, checks it and prints it back
+fn run_json(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    use json::{FromJson, Json, ToJson};
+    use lesson_7_traits::Summary as _;
+
+    let words: Vec<&str> = args.iter().map(String::as_str).collect();
+    let (kind, file) = match words.as_slice() {
+        [kind, file] => (*kind, *file),
+        _ => return Err("usage: json <student|email|tweet|article> FILE".into()),
+    };
+    let value = Json::parse(&data_dir::read_to_string(file.as_ref())?)?;
+    match kind {
+        "student" => {
+            let student = Student::from_json(&value)?;
+            println!("{} ({}), {}", student.name, student.age, student.status);
+            println!("{}", student.to_json());
+        }
+        "email" => {
+            let email = Email::from_json(&value)?;
+            println!("{}", Summary::summarize(&email));
+            println!("{}", email.to_json());
+        }
+        "tweet" => {
+            let tweet = lesson_7_traits::Tweet::from_json(&value)?;
+            println!("{}", tweet.summarize());
+            println!("{}", tweet.to_json());
+        }
+        "article" => {
+            let article = lesson_7_traits::NewsArticle::from_json(&value)?;
+            println!("{}", article.summarize());
+            println!("{}", article.to_json());
+        }
+        other => return Err(format!("unknown kind {:?}", other).into()),
+    }
+    Ok(())
+}
 
 // Main must return Result to use `?`
 // so we have to use box<dyn std::error ::Error> to return the inbuilt error from the function we cant just mix error types 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
 
-    let email = Email{
-       from: String::from("ekom otu"),
-       subject:String::from("learning rust"
- )
+    // `learning_project [--data-dir DIR] COMMAND ...`, the flag comes first
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let data = match data_dir::take_flag(&mut args)
+        .map_err(io::Error::other)
+        .and_then(|flag| DataDir::resolve(flag.as_deref()))
+    {
+        Ok(data) => data,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            std::process::exit(1);
+        }
     };
-    email.notify();
+    // notifications are queued in this data directory too
+    outbox::use_data_dir(&data);
+
+    // with arguments we run a command, e.g. `learning_project roster list`
+    if let Some(command) = args.first() {
+        let result = match command.as_str() {
+            "roster" => roster::run(&data, &args[1..]),
+            "student" => run_student(&data, &args[1..]),
+            "json" => run_json(&args[1..]),
+            "email" => run_email(&args[1..]),
+            "shell" => shell::run(&data, &args[1..]),
+            "task" => tasks::run(&data, &args[1..]),
+            "migrate" => schema::run(&data, &args[1..]),
+            "notify" => run_notify(&args[1..]),
+            "digest" => run_digest(&args[1..]),
+            "tweet" => run_tweet(&args[1..]),
+            "render" => run_render(&args[1..]),
+            "feed" => feed::run(&data, &args[1..]),
+            "outbox" => outbox::run(&args[1..]),
+            "subscription" => subscriptions::run(&args[1..]),
+            // lesson 7 is part of this program too now, this runs its demo
+            "lesson7" => lesson_7_traits::main().and_then(|()| item::demo()),
+            other => Err(format!("unknown command {:?} (try: roster, student, task, json, email, tweet, notify, digest, render, feed, outbox, subscription, shell, migrate, lesson7)", other).into()),
+        };
+        // print the readable Display message instead of the Debug one main would show
+        if let Err(e) = result {
+            eprintln!("error: {}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    // "ekom otu" used to go here, an Email needs a real address now
+    let email = Email::new("Ekom Otu <ekom@example.com>", "learning rust")?;
+    // email.notify really sends, and that needs a mail server and someone to send to.
+    // `learning_project email send ...` does that, here we only tell the notification sinks
+    for failure in Summary::notify(&email)?.failed {
+        eprintln!("notification not delivered yet: {}", failure);
+    }
  
     // Call Student::create_student (capital 'S')
     // create_student takes (String, u8)
@@ -77,9 +823,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     
     println!("Created student: {}", student1.name);
 
-    save_student(&student1)?;
+    save_student(&data, &student1)?;
+
+    let loaded = load_student(&data)?;
+    println!("Loaded student: {} ({}), active: {}", loaded.name, loaded.age, loaded.is_active());
 
-    match load_task(){
+    match load_task(&data){
         Ok(content)=>println!("{}",content),
         Err(e)=> println!("error loading task {}",e)
     }
@@ -10,6 +10,9 @@
 //     None,
 // }
 
+// fuzzy.rs finds names even with a typo, we use it in part 4
+mod fuzzy;
+
 fn main() {
     println!("🦀 Lesson 4: Option<T>\n");
 
@@ -74,6 +77,23 @@ fn main() {
     
     handle_developer(first);
     handle_developer(tenth);
+
+    // ==========================================
+    // PART 4: Finding by name instead of index
+    // ==========================================
+    // Searching can also come up empty, so it returns an Option too.
+    // fuzzy::best forgives typos and gives a score (1.0 = exact match).
+
+    for typed in ["Charlei", "alice", "Zed"] {
+        match fuzzy::best(&developers, typed, |dev| dev) {
+            Some(hit) => println!("{:?} found {} (score {:.2})", typed, hit.item, hit.score),
+            None => println!("{:?} is not close to any developer", typed),
+        }
+    }
+
+    // fuzzy::search gives every match that is close enough, best first (a Vec, maybe empty)
+    let hits = fuzzy::search(&developers, "Bobb", |dev| dev);
+    println!("{} developers look like \"Bobb\"", hits.len());
 }
 
 fn handle_developer(dev: Option<&&str>) {
@@ -7,27 +7,36 @@
 use std::fs;
 use std::io;
 
+// data_dir.rs decides which folder our files go in (--data-dir, env var or ~/.local/share)
+mod data_dir;
+
 fn main() -> Result<(), io::Error> {
     println!("🦀 Lesson 6: File I/O\n");
 
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let flag = data_dir::take_flag(&mut args).map_err(io::Error::other)?;
+    let data = data_dir::DataDir::resolve(flag.as_deref())?;
+
     // ==========================================
     // PART 1: Writing Files
     // ==========================================
     
     let content = "Hello from Rust!\nThis file was created by code.";
-    let filename = "lesson6_output.txt";
+    let path = data.file("lesson6_output.txt");
+    let filename = path.as_path();
 
     // Write string to file (overwrites if exists)
     // Returns Result<(), io::Error>
-    fs::write(filename, content)?; 
-    println!("✅ Written to '{}'", filename);
+    // map_err puts the file name in the error, so "Permission denied" says which file
+    fs::write(filename, content).map_err(|e| data_dir::with_path(e, filename))?;
+    println!("✅ Written to '{}'", filename.display());
 
     // ==========================================
     // PART 2: Reading Files (Text)
     // ==========================================
     
     // Read entire file as String
-    let text_content = fs::read_to_string(filename)?;
+    let text_content = data_dir::read_to_string(filename)?;
     
     println!("\n--- File Content (Text) ---");
     println!("{}", text_content);
@@ -61,8 +70,8 @@ fn main() -> Result<(), io::Error> {
     // ==========================================
     
     // Delete the file we created
-    fs::remove_file(filename)?;
-    println!("\n✅ Deleted '{}'", filename);
+    fs::remove_file(filename).map_err(|e| data_dir::with_path(e, filename))?;
+    println!("\n✅ Deleted '{}'", filename.display());
 
     Ok(())
 }
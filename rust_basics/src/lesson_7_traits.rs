@@ -7,27 +7,211 @@
 //
 // Why use them?
 // So you can write ONE function that works on MANY types (Polymorphism).
+//
+// This file only uses std, so it still builds on its own:
+//   rustc --edition 2024 src/lesson_7_traits.rs
+// learning_project uses it too, `learning_project lesson7` runs this demo.
+// What an item needs from the rest of the program (the outbox, templates,
+// subscriptions) is added in item.rs, by a trait that extends Summary.
+
+use std::fmt;
+use std::io::{self, Write};
+
+// two helper files that only use std. The rest of learning_project uses them
+// from here, so there is one Timestamp type and not two.
+// On its own the lesson doesn't use all of them, hence the allow
+#[allow(dead_code)]
+#[path = "datetime.rs"]
+pub mod datetime;
+#[allow(dead_code)]
+#[path = "unicode.rs"]
+pub mod unicode;
+
+use datetime::Timestamp;
+
+// the most a tweet can be, in characters as people see them (see unicode.rs)
+pub const MAX_TWEET: usize = 280;
+// a link counts as this many characters however long it is,
+// Twitter shortens them all to the same length
+pub const URL_WEIGHT: usize = 23;
+// @names are 1 to 15 letters, digits or _
+const MAX_MENTION: usize = 15;
 
 // 1. Define the Trait (The Contract)
 pub trait Summary {
     // Anyone implementing Summary MUST create this function
     fn summarize(&self) -> String;
 
-    // Default implementation (optional to override)
-    fn announce(&self) {
-        println!("(Default Announcement): {}", self.summarize());
+    // There used to be a default announce() here that println!'d the summary.
+    // Nothing could send that anywhere else or test it, so it's gone:
+    // notify below writes an item to anything that implements Write, and in
+    // learning_project Item::broadcast (item.rs) sends it to the notification sinks.
+
+    // the digest (digest.rs) groups and sorts with these, the defaults are fine to keep.
+    // what sort of thing this is, e.g. "tweet"
+    fn kind(&self) -> &'static str {
+        "item"
+    }
+
+    // who it is from
+    fn source(&self) -> String {
+        String::from("unknown")
+    }
+
+    // when it came out, None if we don't know
+    fn published(&self) -> Option<Timestamp> {
+        None
+    }
+
+    // the summary cut to fit in `columns` terminal columns, ending in … if it
+    // was cut. 🦀 and 漢 take two columns, an accent none.
+    fn summarize_to(&self, columns: usize) -> String {
+        unicode::truncate(&self.summarize(), columns)
     }
 }
 
 // 2. Define some Structs
-struct Tweet {
+// pub so other files (like the JSON code in learning_project) can use them too
+// the fields of Tweet are private so every Tweet goes through Tweet::new,
+// which checks the length and finds the @mentions and #hashtags
+pub struct Tweet {
     username: String,
     content: String,
+    mentions: Vec<String>,
+    hashtags: Vec<String>,
+    length: usize,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum TweetError {
+    BadUsername(String),
+    Empty,
+    TooLong { length: usize },
 }
 
-struct NewsArticle {
-    headline: String,
-    author: String,
+impl fmt::Display for TweetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TweetError::BadUsername(name) => write!(f, "username {:?} must be one word", name),
+            TweetError::Empty => write!(f, "a tweet can't be empty"),
+            TweetError::TooLong { length } => write!(f, "tweet is {} characters long, the most is {}", length, MAX_TWEET),
+        }
+    }
+}
+
+impl std::error::Error for TweetError {}
+
+impl Tweet {
+    pub fn new(username: &str, content: &str) -> Result<Tweet, TweetError> {
+        if username.is_empty() || username.contains(char::is_whitespace) {
+            return Err(TweetError::BadUsername(username.to_string()));
+        }
+        if content.trim().is_empty() {
+            return Err(TweetError::Empty);
+        }
+        let (length, mentions, hashtags) = scan(content);
+        if length > MAX_TWEET {
+            return Err(TweetError::TooLong { length });
+        }
+        Ok(Tweet { username: username.to_string(), content: content.to_string(), mentions, hashtags, length })
+    }
+
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+
+    // the @names in it, without the @
+    pub fn mentions(&self) -> &[String] {
+        &self.mentions
+    }
+
+    // the #tags in it, without the #
+    pub fn hashtags(&self) -> &[String] {
+        &self.hashtags
+    }
+
+    // how long it counts as: graphemes, with every link counted as URL_WEIGHT
+    pub fn length(&self) -> usize {
+        self.length
+    }
+}
+
+// goes through a tweet once and gives back its length, mentions and hashtags
+fn scan(content: &str) -> (usize, Vec<String>, Vec<String>) {
+    let mut length = 0;
+    let mut mentions: Vec<String> = Vec::new();
+    let mut hashtags: Vec<String> = Vec::new();
+    let clusters = unicode::graphemes(content);
+    // where each cluster starts in `content`
+    let starts: Vec<usize> = clusters.iter().scan(0, |at, c| Some(std::mem::replace(at, *at + c.len()))).collect();
+    let mut i = 0;
+    while i < clusters.len() {
+        // @ and # only count at the start of a word, so ann@example.com and
+        // C# aren't picked up
+        let word_start = i == 0 || !is_word(clusters[i - 1]);
+        let rest = &content[starts[i]..];
+        if word_start && let Some(url) = url_at(rest) {
+            length += URL_WEIGHT;
+            // the url is ASCII, so one cluster per byte
+            i += url.len();
+            continue;
+        }
+        if word_start && matches!(clusters[i], "@" | "#") {
+            let run = clusters[i + 1..].iter().take_while(|c| is_word(c)).count();
+            let name: String = clusters[i + 1..i + 1 + run].concat();
+            let found = if clusters[i] == "@" {
+                let valid = (1..=MAX_MENTION).contains(&run) && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+                valid.then_some(&mut mentions)
+            } else {
+                // #2024 is just a number
+                let valid = !name.is_empty() && !name.chars().all(|c| c.is_ascii_digit());
+                valid.then_some(&mut hashtags)
+            };
+            if let Some(found) = found
+                && !found.iter().any(|seen| seen.to_lowercase() == name.to_lowercase())
+            {
+                found.push(name);
+            }
+            length += 1 + run;
+            i += 1 + run;
+            continue;
+        }
+        length += 1;
+        i += 1;
+    }
+    (length, mentions, hashtags)
+}
+
+fn is_word(cluster: &str) -> bool {
+    cluster.chars().next().is_some_and(|c| c.is_alphanumeric() || c == '_')
+}
+
+// "https://example.com/a, and more" -> "https://example.com/a"
+fn url_at(text: &str) -> Option<&str> {
+    // compare bytes, so a check at every position of a long tweet stays cheap.
+    // get() is None when the text is shorter than the prefix
+    let starts_with = |prefix: &[u8]| text.as_bytes().get(..prefix.len()).is_some_and(|b| b.eq_ignore_ascii_case(prefix));
+    if !(starts_with(b"http://") || starts_with(b"https://")) {
+        return None;
+    }
+    let end = text.find(|c: char| c.is_whitespace() || !c.is_ascii()).unwrap_or(text.len());
+    // punctuation at the end belongs to the sentence, not the link
+    let url = text[..end].trim_end_matches(['.', ',', ':', ';', '!', '?', ')', '\'', '"']);
+    // "http://" on its own isn't a link
+    url.split_once("://").filter(|(_, host)| !host.is_empty()).map(|_| url)
+}
+
+// the feed reader (feed.rs) fills in the last three, by hand they can stay None
+pub struct NewsArticle {
+    pub headline: String,
+    pub author: String,
+    pub link: Option<String>,
+    pub published: Option<Timestamp>,
+    pub summary: Option<String>,
 }
 
 // 3. Implement the Trait for the Structs
@@ -35,39 +219,85 @@ impl Summary for Tweet {
     fn summarize(&self) -> String {
         format!("@{}: {}", self.username, self.content)
     }
+
+    fn kind(&self) -> &'static str {
+        "tweet"
+    }
+
+    fn source(&self) -> String {
+        format!("@{}", self.username)
+    }
 }
 
 impl Summary for NewsArticle {
     fn summarize(&self) -> String {
         format!("{} by {}", self.headline, self.author)
     }
+
+    fn kind(&self) -> &'static str {
+        "article"
+    }
+
+    fn source(&self) -> String {
+        self.author.clone()
+    }
+
+    fn published(&self) -> Option<Timestamp> {
+        self.published
+    }
 }
 
 // 4. Use the Trait as a Parameter (Polymorphism!)
 // This function accepts ANYTHING that implements Summary
-fn notify(item: &impl Summary) {
-    println!("Breaking News! {}", item.summarize());
+// (and `out` can be anything that implements Write, polymorphism again:
+// stdout, a file, or a Vec<u8> that keeps the bytes).
+// It replaces the old announce(), which could only print.
+// learning_project's Item::broadcast goes to the notification sinks instead.
+fn notify(item: &impl Summary, out: &mut impl Write) -> io::Result<()> {
+    writeln!(out, "Breaking News! {}", item.summarize())
 }
 
-fn main() {
-    let tweet = Tweet {
-        username: String::from("ekom"),
-        content: String::from("Rust traits are cool!"),
-    };
+pub fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let tweet = Tweet::new("ekom", "Rust traits are cool! 🦀 #rustlang @rustaceans")?;
 
     let article = NewsArticle {
         headline: String::from("Rust takes over the world"),
         author: String::from("The Times"),
+        link: None,
+        published: None,
+        summary: None,
     };
 
+    // the length counts the 🦀 as one, like a person would
+    println!("{} characters, mentions {:?}, hashtags {:?}", tweet.length(), tweet.mentions(), tweet.hashtags());
+    println!("{}", tweet.summarize_to(30));
+
     // Both work passing to notify()!
-    notify(&tweet);
-    notify(&article);
+    let mut stdout = io::stdout();
+    notify(&tweet, &mut stdout)?;
+    notify(&article, &mut stdout)?;
 
-    // Calling the default method
+    // Calling a default method, neither Tweet nor NewsArticle writes its own summarize_to
     println!("\n--- Default Implementation ---");
-    tweet.announce();
-    article.announce();
+    println!("{}", tweet.summarize_to(20));
+    println!("{}", article.summarize_to(20));
+
+    // a Vec<u8> keeps what is written to it instead of printing it
+    println!("\n--- Captured in memory ---");
+    let mut captured: Vec<u8> = Vec::new();
+    notify(&tweet, &mut captured)?;
+    notify(&article, &mut captured)?;
+    for line in String::from_utf8(captured)?.lines() {
+        println!("captured {:?}", line);
+    }
+
+    // different types in one Vec, as long as they all implement Summary (trait objects)
+    println!("\n--- Trait objects ---");
+    let items: Vec<Box<dyn Summary>> = vec![Box::new(tweet), Box::new(article)];
+    for item in &items {
+        println!("{} from {}: {}", item.kind(), item.source(), item.summarize_to(30));
+    }
+    Ok(())
 }
 
 // ============================================
@@ -0,0 +1,303 @@
+// ============================================
+// Rate limits and duplicates for notifications
+// ============================================
+// Calling broadcast() in a loop used to send the same thing again and again.
+// Now, before a notification goes into the outbox, every sink it is for (a
+// "recipient", e.g. webhook:http://localhost:8080/hook) is checked:
+//
+//   1. did the same title and text go to this recipient in the last 5 minutes?
+//      Then it is suppressed, nobody gets it twice. (We compare a hash of the
+//      content, not the whole text.)
+//   2. token buckets: every recipient has a bucket of tokens and so does every
+//      channel (the kind of sink: stdout, file, mbox, maildir, webhook).
+//      Sending takes a token from both, and tokens come back at a steady rate.
+//      An empty bucket doesn't lose the notification, it is deferred: the
+//      outbox holds it until a token is back.
+//
+//   10/60 means 10 in a row, then one more every 6 seconds (10 per 60s)
+//
+// The buckets, what was sent lately and the counts live in limiter.json next
+// to the outbox, so a restart doesn't start from full buckets again.
+// LEARNING_PROJECT_RATE (per recipient, default 10/60), LEARNING_PROJECT_CHANNEL_RATE
+// (default 30/60) and LEARNING_PROJECT_DEDUP_WINDOW (seconds, default 300)
+// change the limits, "off" turns one off.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::atomic_write::write_atomic;
+use crate::data_dir::with_path;
+use crate::datetime::Timestamp;
+use crate::json::Json;
+use crate::lock::{FileLock, Mode};
+use crate::notifier::Notification;
+use crate::outbox::OutboxError;
+
+pub const LIMITER_FILE: &str = "limiter.json";
+pub const RATE_ENV: &str = "LEARNING_PROJECT_RATE";
+pub const CHANNEL_RATE_ENV: &str = "LEARNING_PROJECT_CHANNEL_RATE";
+pub const DEDUP_ENV: &str = "LEARNING_PROJECT_DEDUP_WINDOW";
+const DEFAULT_RATE: Rate = Rate { burst: 10.0, per_second: 10.0 / 60.0 };
+const DEFAULT_CHANNEL_RATE: Rate = Rate { burst: 30.0, per_second: 30.0 / 60.0 };
+const DEFAULT_WINDOW: i64 = 300;
+
+// `burst` tokens when full, `per_second` tokens come back every second
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rate {
+    pub burst: f64,
+    pub per_second: f64,
+}
+
+impl Rate {
+    // "10/60" -> 10 per 60 seconds
+    pub fn parse(text: &str) -> Option<Rate> {
+        let (count, seconds) = text.trim().split_once('/')?;
+        let count: f64 = count.trim().parse().ok()?;
+        let seconds: f64 = seconds.trim().parse().ok()?;
+        if count < 1.0 || count.fract() != 0.0 || seconds <= 0.0 || !seconds.is_finite() {
+            return None;
+        }
+        Some(Rate { burst: count, per_second: count / seconds })
+    }
+}
+
+// None turns that check off
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+    pub recipient: Option<Rate>,
+    pub channel: Option<Rate>,
+    // seconds
+    pub window: Option<i64>,
+}
+
+impl Limits {
+    pub fn configured() -> io::Result<Limits> {
+        let bad = |name: &str, value: &str, expected: &str| {
+            io::Error::new(io::ErrorKind::InvalidInput, format!("{} must be {} or off, got {:?}", name, expected, value))
+        };
+        let rate = |name: &str, default: Rate| match std::env::var(name) {
+            Ok(value) if value.trim() == "off" => Ok(None),
+            Ok(value) => Rate::parse(&value).map(Some).ok_or_else(|| bad(name, &value, "COUNT/SECONDS like 10/60")),
+            Err(_) => Ok(Some(default)),
+        };
+        let window = match std::env::var(DEDUP_ENV) {
+            Ok(value) if value.trim() == "off" => None,
+            Ok(value) => match value.trim().parse::<i64>() {
+                Ok(seconds) if seconds > 0 => Some(seconds),
+                _ => return Err(bad(DEDUP_ENV, &value, "a number of seconds")),
+            },
+            Err(_) => Some(DEFAULT_WINDOW),
+        };
+        Ok(Limits { recipient: rate(RATE_ENV, DEFAULT_RATE)?, channel: rate(CHANNEL_RATE_ENV, DEFAULT_CHANNEL_RATE)?, window })
+    }
+}
+
+// what happens to a notification for one recipient
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Decision {
+    Send,
+    // not before this time
+    Defer(Timestamp),
+    // it had the same content not long ago
+    Suppress,
+}
+
+// everything in limiter.json
+#[derive(Debug, Default)]
+pub struct State {
+    // "recipient webhook:http://..." or "channel webhook". A bucket that isn't
+    // here is full.
+    pub buckets: Vec<(String, Bucket)>,
+    pub seen: Vec<Seen>,
+    // counts since the file was made
+    pub suppressed: u64,
+    pub deferred: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Bucket {
+    // below 0 when tokens are promised to deferred notifications
+    pub tokens: f64,
+    pub at: Timestamp,
+}
+
+// this content went to this recipient at this time
+#[derive(Debug, Clone)]
+pub struct Seen {
+    pub recipient: String,
+    pub hash: u64,
+    pub at: Timestamp,
+}
+
+pub struct Limiter {
+    path: PathBuf,
+    limits: Limits,
+}
+
+impl Limiter {
+    pub fn open(path: &Path, limits: Limits) -> Limiter {
+        Limiter { path: path.to_path_buf(), limits }
+    }
+
+    // decides for every recipient and remembers it, so the next call sees
+    // the tokens this one took
+    pub fn check(&self, note: &Notification, recipients: &[String]) -> Result<Vec<(String, Decision)>, OutboxError> {
+        let _lock = FileLock::acquire(&self.path, Mode::Exclusive)?;
+        let mut state = self.read()?;
+        let now = note.at;
+        let hash = content_hash(note);
+        let mut decisions = Vec::new();
+        for recipient in recipients {
+            let duplicate = self.limits.window.is_some_and(|window| {
+                state.seen.iter().any(|seen| &seen.recipient == recipient && seen.hash == hash && now.0 - seen.at.0 < window)
+            });
+            if duplicate {
+                state.suppressed += 1;
+                decisions.push((recipient.clone(), Decision::Suppress));
+                continue;
+            }
+            if self.limits.window.is_some() {
+                state.seen.push(Seen { recipient: recipient.clone(), hash, at: now });
+            }
+            // "webhook:http://..." is on the webhook channel
+            let channel = recipient.split_once(':').map_or(recipient.as_str(), |(kind, _)| kind);
+            let mut ready = now;
+            if let Some(rate) = self.limits.recipient {
+                ready = ready.max(take(&mut state, &format!("recipient {}", recipient), rate, now));
+            }
+            if let Some(rate) = self.limits.channel {
+                ready = ready.max(take(&mut state, &format!("channel {}", channel), rate, now));
+            }
+            if ready > now {
+                state.deferred += 1;
+                decisions.push((recipient.clone(), Decision::Defer(ready)));
+            } else {
+                decisions.push((recipient.clone(), Decision::Send));
+            }
+        }
+        self.forget_old(&mut state, now);
+        self.write(&state)?;
+        Ok(decisions)
+    }
+
+    pub fn state(&self) -> Result<State, OutboxError> {
+        let _lock = FileLock::acquire(&self.path, Mode::Shared)?;
+        self.read()
+    }
+
+    // old entries only make the file bigger: content from before the window
+    // can't be a duplicate any more, and a bucket that has filled up again is
+    // the same as no bucket
+    fn forget_old(&self, state: &mut State, now: Timestamp) {
+        match self.limits.window {
+            Some(window) => state.seen.retain(|seen| now.0 - seen.at.0 < window),
+            None => state.seen.clear(),
+        }
+        let limits = self.limits;
+        state.buckets.retain(|(key, bucket)| {
+            let rate = if key.starts_with("channel ") { limits.channel } else { limits.recipient };
+            rate.is_some_and(|rate| bucket.tokens + (now.0 - bucket.at.0) as f64 * rate.per_second < rate.burst)
+        });
+    }
+
+    fn read(&self) -> Result<State, OutboxError> {
+        let content = match fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(State::default()),
+            Err(e) => return Err(with_path(e, &self.path).into()),
+        };
+        let malformed = |message: String| OutboxError::Malformed { path: self.path.clone(), line: 1, message };
+        let value = Json::parse(&content).map_err(|e| malformed(e.to_string()))?;
+        state_from_json(&value).map_err(malformed)
+    }
+
+    fn write(&self, state: &State) -> io::Result<()> {
+        write_atomic(&self.path, format!("{}\n", state_to_json(state)).as_bytes())
+    }
+}
+
+// takes a token from the bucket and says when it can be used: now if there
+// was one, otherwise when the bucket has earned it back
+fn take(state: &mut State, key: &str, rate: Rate, now: Timestamp) -> Timestamp {
+    let index = match state.buckets.iter().position(|(k, _)| k == key) {
+        Some(index) => index,
+        None => {
+            state.buckets.push((key.to_string(), Bucket { tokens: rate.burst, at: now }));
+            state.buckets.len() - 1
+        }
+    };
+    let bucket = &mut state.buckets[index].1;
+    let elapsed = (now.0 - bucket.at.0).max(0) as f64;
+    bucket.tokens = (bucket.tokens + elapsed * rate.per_second).min(rate.burst) - 1.0;
+    bucket.at = now;
+    if bucket.tokens >= 0.0 {
+        now
+    } else {
+        Timestamp(now.0 + (-bucket.tokens / rate.per_second).ceil() as i64)
+    }
+}
+
+// FNV-1a: simple, and the same number every run (the hasher in std is
+// seeded randomly, so its hashes can't be saved in a file)
+fn content_hash(note: &Notification) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in note.title.bytes().chain([0]).chain(note.text.bytes()) {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+fn state_to_json(state: &State) -> Json {
+    let text = |value: &str| Json::String(value.to_string());
+    let buckets = state.buckets.iter().map(|(key, bucket)| {
+        Json::Object(vec![
+            (String::from("key"), text(key)),
+            (String::from("tokens"), Json::Number(bucket.tokens)),
+            (String::from("at"), text(&bucket.at.to_string())),
+        ])
+    });
+    let seen = state.seen.iter().map(|seen| {
+        Json::Object(vec![
+            (String::from("recipient"), text(&seen.recipient)),
+            (String::from("hash"), text(&format!("{:016x}", seen.hash))),
+            (String::from("at"), text(&seen.at.to_string())),
+        ])
+    });
+    Json::Object(vec![
+        (String::from("suppressed"), Json::Number(state.suppressed as f64)),
+        (String::from("deferred"), Json::Number(state.deferred as f64)),
+        (String::from("buckets"), Json::Array(buckets.collect())),
+        (String::from("seen"), Json::Array(seen.collect())),
+    ])
+}
+
+fn state_from_json(value: &Json) -> Result<State, String> {
+    let text = |value: &Json, field: &str| match value.get(field) {
+        Some(Json::String(s)) => Ok(s.clone()),
+        _ => Err(format!("{:?} should be a string", field)),
+    };
+    let time = |value: &Json, field: &str| Timestamp::parse_rfc3339(&text(value, field)?).ok_or_else(|| format!("{:?} is not a time", field));
+    let count = |field: &str| match value.get(field) {
+        Some(Json::Number(n)) if n.fract() == 0.0 && *n >= 0.0 => Ok(*n as u64),
+        _ => Err(format!("{:?} should be a whole number", field)),
+    };
+    let list = |field: &str| match value.get(field) {
+        Some(Json::Array(items)) => Ok(items.clone()),
+        _ => Err(format!("{:?} should be a list", field)),
+    };
+    let mut state = State { suppressed: count("suppressed")?, deferred: count("deferred")?, ..State::default() };
+    for bucket in list("buckets")? {
+        let tokens = match bucket.get("tokens") {
+            Some(Json::Number(n)) => *n,
+            _ => return Err(String::from("\"tokens\" should be a number")),
+        };
+        state.buckets.push((text(&bucket, "key")?, Bucket { tokens, at: time(&bucket, "at")? }));
+    }
+    for seen in list("seen")? {
+        let hash = u64::from_str_radix(&text(&seen, "hash")?, 16).map_err(|_| String::from("\"hash\" should be hex"))?;
+        state.seen.push(Seen { recipient: text(&seen, "recipient")?, hash, at: time(&seen, "at")? });
+    }
+    Ok(state)
+}
@@ -0,0 +1,138 @@
+// ============================================
+// Locking our data files between processes
+// ============================================
+// write_atomic stops a crash from leaving half a file, but two copies of the
+// program can still both read roster.txt, both add a student and both save.
+// The second save then throws away the first student. So every
+// read-modify-write of a data file takes a lock first:
+//   - Shared for reading, any number of readers at once
+//   - Exclusive for writing, nobody else at all
+// The lock is on a separate file next to the data (.roster.txt.lock), because
+// write_atomic replaces the data file and a lock on the old one would be lost.
+// These are advisory locks (flock on unix), only programs that ask for the lock wait for it.
+//
+// If the lock isn't free within the timeout (5 seconds, or LEARNING_PROJECT_LOCK_TIMEOUT
+// seconds, 0 means try once) we give up with an error naming the process that holds it.
+// The lock file only has room for one PID, the last process to take the lock.
+// With several readers that is one of them, which is enough to go and look.
+
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::data_dir::with_path;
+
+pub const TIMEOUT_ENV: &str = "LEARNING_PROJECT_LOCK_TIMEOUT";
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+const RETRY_EVERY: Duration = Duration::from_millis(50);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    Shared,
+    Exclusive,
+}
+
+// the lock is held until this is dropped
+pub struct FileLock {
+    file: File,
+    lock_path: PathBuf,
+}
+
+impl FileLock {
+    // locks the data file at `path`, errors name the data file
+    pub fn acquire(path: impl AsRef<Path>, mode: Mode) -> io::Result<FileLock> {
+        let path = path.as_ref();
+        lock(path, mode).map_err(|e| with_path(e, path))
+    }
+}
+
+impl Drop for FileLock {
+    // the lock itself goes away when the file is closed, we only clear our PID.
+    // a reader that came after us may have written its own, that one stays
+    fn drop(&mut self) {
+        let ours = fs::read_to_string(&self.lock_path).is_ok_and(|content| content.trim() == std::process::id().to_string());
+        if ours {
+            let _ = self.file.set_len(0);
+        }
+    }
+}
+
+fn lock(path: &Path, mode: Mode) -> io::Result<FileLock> {
+    let lock_path = lock_path(path)?;
+    let timeout = timeout()?;
+    // append mode so the PID always goes at the start of the file we just emptied
+    let mut file = OpenOptions::new().read(true).append(true).create(true).open(&lock_path)?;
+
+    let started = Instant::now();
+    loop {
+        let attempt = match mode {
+            Mode::Shared => file.try_lock_shared(),
+            Mode::Exclusive => file.try_lock(),
+        };
+        match attempt {
+            Ok(()) => break,
+            Err(TryLockError::WouldBlock) => {}
+            Err(TryLockError::Error(e)) => return Err(e),
+        }
+        if started.elapsed() >= timeout {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("gave up after {:?} waiting for the lock, {}", timeout, holders(&lock_path)),
+            ));
+        }
+        thread::sleep(RETRY_EVERY);
+    }
+
+    // the lock file says who holds the lock, so whoever is waiting can tell the user
+    file.set_len(0)?;
+    writeln!(file, "{}", std::process::id())?;
+    Ok(FileLock { file, lock_path })
+}
+
+// roster.txt -> .roster.txt.lock in the same directory
+fn lock_path(path: &Path) -> io::Result<PathBuf> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?;
+    Ok(path.with_file_name(format!(".{}.lock", file_name.to_string_lossy())))
+}
+
+fn timeout() -> io::Result<Duration> {
+    let Some(value) = std::env::var_os(TIMEOUT_ENV) else {
+        return Ok(DEFAULT_TIMEOUT);
+    };
+    let value = value.to_string_lossy();
+    value
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} must be a number of seconds, got {:?}", TIMEOUT_ENV, value),
+            )
+        })
+}
+
+// "held by process 1234" from the PID in the lock file
+fn holders(lock_path: &Path) -> String {
+    let content = fs::read_to_string(lock_path).unwrap_or_default();
+    // a process that crashed never cleared its PID, so skip it if it is gone
+    match content.trim().parse::<u32>() {
+        Ok(pid) if still_running(pid) => format!("held by process {}", pid),
+        _ => String::from("held by another process"),
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn still_running(pid: u32) -> bool {
+    Path::new("/proc").join(pid.to_string()).exists()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn still_running(_pid: u32) -> bool {
+    true
+}
@@ -0,0 +1,443 @@
+// ============================================
+// Building a mail message (RFC 5322 and MIME)
+// ============================================
+// A mail message is headers, a blank line, then the body:
+//
+//   Date: Sun, 18 Oct 2026 07:51:11 +0000
+//   From: <ekom@example.com>
+//   To: <ann@example.com>
+//   Subject: learning rust
+//   Message-ID: <1792309871.4242.1@example.com>
+//   MIME-Version: 1.0
+//   Content-Type: text/plain; charset=utf-8
+//
+//   hello!
+//
+// The rules that make this more than format!():
+//   - every line ends in \r\n, and header lines over 78 characters are
+//     folded: broken at a space and continued on a line starting with a space
+//   - headers are ASCII only, so a subject like "Grüße" is sent as an
+//     encoded word: =?utf-8?B?R3LDvMOfZQ==?= (RFC 2047)
+//   - but not inside quotes, so an attachment called "Grüße.pdf" is sent as
+//     filename*=UTF-8''Gr%C3%BC%C3%9Fe.pdf (RFC 2231) next to a plain filename="Gr__e.pdf"
+//   - a text and an HTML version of the body go in a multipart/alternative
+//     (the reader's mail program shows the best one it can), attachments go
+//     next to that in a multipart/mixed, each part separated by a boundary line
+//   - anything that isn't plain ASCII text (attachments, accented text) is base64
+//   - Bcc recipients get the message but aren't in any header
+//
+// MessageBuilder collects the pieces, build() gives the bytes to hand to smtp::send.
+
+use std::fmt;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use crate::address::EmailAddress;
+use crate::base64;
+use crate::datetime::Timestamp;
+
+const MAX_LINE: usize = 78;
+// base64 lines in a body, RFC 2045 allows at most 76
+const BASE64_LINE: usize = 76;
+// how many bytes of a header fit into one encoded word, which can be 75 characters long
+const ENCODED_WORD_BYTES: usize = 45;
+// how long one piece of a filename*0*=, filename*1*=... value can be
+const PARAMETER_PIECE: usize = 60;
+
+// counts messages and boundaries made by this process, so two are never the same
+static COUNTER: AtomicU32 = AtomicU32::new(0);
+
+#[derive(Debug, Clone)]
+pub struct Attachment {
+    pub filename: String,
+    pub content_type: String,
+    pub data: Vec<u8>,
+}
+
+impl Attachment {
+    // guesses the content type from the file name
+    pub fn new(filename: &str, data: Vec<u8>) -> Attachment {
+        let extension = filename.rsplit_once('.').map(|(_, ext)| ext.to_lowercase()).unwrap_or_default();
+        let content_type = match extension.as_str() {
+            "txt" => "text/plain",
+            "csv" => "text/csv",
+            "html" | "htm" => "text/html",
+            "json" => "application/json",
+            "pdf" => "application/pdf",
+            "png" => "image/png",
+            "jpg" | "jpeg" => "image/jpeg",
+            "gif" => "image/gif",
+            _ => "application/octet-stream",
+        };
+        Attachment { filename: filename.to_string(), content_type: content_type.to_string(), data }
+    }
+}
+
+#[derive(Debug)]
+pub enum MessageError {
+    // a message needs somebody to go to
+    NoRecipients,
+    // a line break in a header would let the text start new headers of its own
+    LineBreak { header: &'static str },
+}
+
+impl fmt::Display for MessageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MessageError::NoRecipients => write!(f, "message has no To, Cc or Bcc recipients"),
+            MessageError::LineBreak { header } => write!(f, "{} must not contain a line break", header),
+        }
+    }
+}
+
+impl std::error::Error for MessageError {}
+
+#[derive(Debug, Clone)]
+pub struct MessageBuilder {
+    from: EmailAddress,
+    to: Vec<EmailAddress>,
+    cc: Vec<EmailAddress>,
+    bcc: Vec<EmailAddress>,
+    reply_to: Option<EmailAddress>,
+    subject: String,
+    text: Option<String>,
+    html: Option<String>,
+    attachments: Vec<Attachment>,
+    date: Option<Timestamp>,
+    message_id: Option<String>,
+}
+
+impl MessageBuilder {
+    pub fn new(from: &EmailAddress) -> MessageBuilder {
+        MessageBuilder {
+            from: from.clone(),
+            to: Vec::new(),
+            cc: Vec::new(),
+            bcc: Vec::new(),
+            reply_to: None,
+            subject: String::new(),
+            text: None,
+            html: None,
+            attachments: Vec::new(),
+            date: None,
+            message_id: None,
+        }
+    }
+
+    pub fn to(mut self, address: &EmailAddress) -> MessageBuilder {
+        self.to.push(address.clone());
+        self
+    }
+
+    pub fn cc(mut self, address: &EmailAddress) -> MessageBuilder {
+        self.cc.push(address.clone());
+        self
+    }
+
+    pub fn bcc(mut self, address: &EmailAddress) -> MessageBuilder {
+        self.bcc.push(address.clone());
+        self
+    }
+
+    pub fn reply_to(mut self, address: &EmailAddress) -> MessageBuilder {
+        self.reply_to = Some(address.clone());
+        self
+    }
+
+    pub fn subject(mut self, subject: &str) -> MessageBuilder {
+        self.subject = subject.to_string();
+        self
+    }
+
+    // the plain text body
+    pub fn text(mut self, body: &str) -> MessageBuilder {
+        self.text = Some(body.to_string());
+        self
+    }
+
+    // an HTML version of the body, sent next to the text one
+    pub fn html(mut self, body: &str) -> MessageBuilder {
+        self.html = Some(body.to_string());
+        self
+    }
+
+    pub fn attach(mut self, attachment: Attachment) -> MessageBuilder {
+        self.attachments.push(attachment);
+        self
+    }
+
+    // defaults to the time build() is called
+    pub fn date(mut self, date: Timestamp) -> MessageBuilder {
+        self.date = Some(date);
+        self
+    }
+
+    // defaults to a new unique id, give one without the <> to repeat a message
+    pub fn message_id(mut self, id: &str) -> MessageBuilder {
+        self.message_id = Some(id.to_string());
+        self
+    }
+
+    // the envelope sender, for MAIL FROM
+    pub fn sender(&self) -> &EmailAddress {
+        &self.from
+    }
+
+    // everyone who gets the message, Bcc included, for RCPT TO
+    pub fn recipients(&self) -> Vec<EmailAddress> {
+        self.to.iter().chain(&self.cc).chain(&self.bcc).cloned().collect()
+    }
+
+    pub fn build(&self) -> Result<Vec<u8>, MessageError> {
+        if self.recipients().is_empty() {
+            return Err(MessageError::NoRecipients);
+        }
+        let message_id = match &self.message_id {
+            Some(id) => id.clone(),
+            None => new_message_id(&self.from),
+        };
+
+        let mut out = String::new();
+        header(&mut out, "Date", &self.date.unwrap_or_else(Timestamp::now).rfc5322())?;
+        header(&mut out, "From", &address_list(std::slice::from_ref(&self.from)))?;
+        if let Some(reply_to) = &self.reply_to {
+            header(&mut out, "Reply-To", &address_list(std::slice::from_ref(reply_to)))?;
+        }
+        if !self.to.is_empty() {
+            header(&mut out, "To", &address_list(&self.to))?;
+        }
+        if !self.cc.is_empty() {
+            header(&mut out, "Cc", &address_list(&self.cc))?;
+        }
+        header(&mut out, "Subject", &encode_words(&self.subject))?;
+        header(&mut out, "Message-ID", &format!("<{}>", message_id))?;
+        out.push_str("MIME-Version: 1.0\r\n");
+
+        let body = Part::body(self.text.as_deref(), self.html.as_deref());
+        let content = if self.attachments.is_empty() {
+            body
+        } else {
+            let mut parts = vec![body];
+            for attachment in &self.attachments {
+                if attachment.filename.contains(['\r', '\n']) {
+                    return Err(MessageError::LineBreak { header: "attachment file name" });
+                }
+                parts.push(Part::attachment(attachment));
+            }
+            Part::multipart("mixed", parts)
+        };
+        content.write(&mut out);
+        Ok(out.into_bytes())
+    }
+}
+
+// one piece of the message: headers and an already encoded body
+struct Part {
+    headers: Vec<(String, String)>,
+    body: String,
+}
+
+impl Part {
+    // text, HTML or both as a multipart/alternative (the simplest first, as MIME asks)
+    fn body(text: Option<&str>, html: Option<&str>) -> Part {
+        match (text, html) {
+            (text, None) => Part::text("plain", text.unwrap_or("")),
+            (None, Some(html)) => Part::text("html", html),
+            (Some(text), Some(html)) => {
+                Part::multipart("alternative", vec![Part::text("plain", text), Part::text("html", html)])
+            }
+        }
+    }
+
+    fn text(subtype: &str, text: &str) -> Part {
+        let content_type = (String::from("Content-Type"), format!("text/{}; charset=utf-8", subtype));
+        // short ASCII lines can go as they are, anything else is base64
+        if text.is_ascii() && text.lines().all(|line| line.len() <= 998) {
+            let body = text.lines().collect::<Vec<_>>().join("\r\n");
+            Part {
+                headers: vec![content_type, (String::from("Content-Transfer-Encoding"), String::from("7bit"))],
+                body,
+            }
+        } else {
+            Part {
+                headers: vec![content_type, (String::from("Content-Transfer-Encoding"), String::from("base64"))],
+                body: base64_lines(text.as_bytes()),
+            }
+        }
+    }
+
+    fn attachment(attachment: &Attachment) -> Part {
+        // encoded words aren't allowed inside quotes, a non-ASCII name goes in filename*
+        // (RFC 2231) and mail programs that don't know it get an ASCII look-alike
+        let name = quoted(&ascii_fallback(&attachment.filename));
+        let mut disposition = format!("attachment; filename={}", name);
+        if !attachment.filename.is_ascii() {
+            disposition.push_str("; ");
+            disposition.push_str(&extended_parameter("filename", &attachment.filename));
+        }
+        Part {
+            headers: vec![
+                (String::from("Content-Type"), format!("{}; name={}", attachment.content_type, name)),
+                (String::from("Content-Disposition"), disposition),
+                (String::from("Content-Transfer-Encoding"), String::from("base64")),
+            ],
+            body: base64_lines(&attachment.data),
+        }
+    }
+
+    fn multipart(subtype: &str, parts: Vec<Part>) -> Part {
+        let mut rendered: Vec<String> = Vec::new();
+        for part in parts {
+            let mut text = String::new();
+            part.write(&mut text);
+            rendered.push(text);
+        }
+        // the boundary must not show up inside any part
+        let boundary = loop {
+            let candidate = format!("=_part_{}_{}", std::process::id(), COUNTER.fetch_add(1, Ordering::Relaxed));
+            if !rendered.iter().any(|part| part.contains(&candidate)) {
+                break candidate;
+            }
+        };
+
+        let mut body = String::new();
+        for part in rendered {
+            body.push_str(&format!("--{}\r\n", boundary));
+            body.push_str(&part);
+            body.push_str("\r\n");
+        }
+        body.push_str(&format!("--{}--", boundary));
+        Part {
+            headers: vec![(String::from("Content-Type"), format!("multipart/{}; boundary=\"{}\"", subtype, boundary))],
+            body,
+        }
+    }
+
+    fn write(&self, out: &mut String) {
+        for (name, value) in &self.headers {
+            out.push_str(&fold(&format!("{}: {}", name, value)));
+        }
+        out.push_str("\r\n");
+        out.push_str(&self.body);
+    }
+}
+
+// adds "Name: value\r\n", folded, after checking for line breaks
+fn header(out: &mut String, name: &'static str, value: &str) -> Result<(), MessageError> {
+    if value.contains(['\r', '\n']) {
+        return Err(MessageError::LineBreak { header: name });
+    }
+    out.push_str(&fold(&format!("{}: {}", name, value)));
+    Ok(())
+}
+
+// "Ekom Otu <ekom@example.com>, <ann@example.com>", names outside ASCII as encoded words
+fn address_list(addresses: &[EmailAddress]) -> String {
+    let mailboxes: Vec<String> = addresses
+        .iter()
+        .map(|address| match address.name() {
+            Some(name) if name.is_ascii() => format!("{} <{}>", quoted(name), address.addr_spec()),
+            Some(name) => format!("{} <{}>", encode_words(name), address.addr_spec()),
+            None => format!("<{}>", address.addr_spec()),
+        })
+        .collect();
+    mailboxes.join(", ")
+}
+
+// breaks a header line at spaces so no line is longer than 78, the next line starts with a space
+fn fold(line: &str) -> String {
+    let mut out = String::new();
+    let mut rest = line;
+    while rest.len() > MAX_LINE {
+        // the last space that keeps this line short enough, or the first one after if a word is too long.
+        // a space at 0 is the fold we just made, cutting there again would loop forever
+        let spaces: Vec<usize> = rest.match_indices(' ').map(|(at, _)| at).filter(|&at| at > 0).collect();
+        let cut = spaces.iter().rev().find(|&&at| at <= MAX_LINE).or(spaces.first());
+        let Some(&cut) = cut else { break };
+        out.push_str(&rest[..cut]);
+        out.push_str("\r\n");
+        rest = &rest[cut..];
+    }
+    out.push_str(rest);
+    out.push_str("\r\n");
+    out
+}
+
+// plain ASCII stays as it is, anything else becomes one or more =?utf-8?B?...?= words
+pub fn encode_words(text: &str) -> String {
+    if text.is_ascii() {
+        return text.to_string();
+    }
+    let mut words = Vec::new();
+    let mut chunk = String::new();
+    for c in text.chars() {
+        // a character can't be split over two encoded words
+        if chunk.len() + c.len_utf8() > ENCODED_WORD_BYTES {
+            words.push(format!("=?utf-8?B?{}?=", base64::encode(chunk.as_bytes())));
+            chunk.clear();
+        }
+        chunk.push(c);
+    }
+    if !chunk.is_empty() {
+        words.push(format!("=?utf-8?B?{}?=", base64::encode(chunk.as_bytes())));
+    }
+    // the space between encoded words is dropped when they are decoded
+    words.join(" ")
+}
+
+// "Grüße….pdf" becomes "Gr__e_.pdf", for the plain filename= next to filename*
+fn ascii_fallback(text: &str) -> String {
+    text.chars().map(|c| if c.is_ascii() { c } else { '_' }).collect()
+}
+
+// filename*=UTF-8''Gr%C3%BC%C3%9Fe.pdf, every byte that isn't a plain letter or digit
+// (or one of the few safe symbols) written as %XX. A long one is cut into numbered
+// pieces, filename*0*=...; filename*1*=..., so the header can be folded between them
+fn extended_parameter(name: &str, value: &str) -> String {
+    let mut pieces = Vec::new();
+    let mut piece = String::from("UTF-8''");
+    for byte in value.bytes() {
+        let encoded = if byte.is_ascii_alphanumeric() || b"!#$&+-.^_`|~".contains(&byte) {
+            (byte as char).to_string()
+        } else {
+            format!("%{:02X}", byte)
+        };
+        if piece.len() + encoded.len() > PARAMETER_PIECE {
+            pieces.push(std::mem::take(&mut piece));
+        }
+        piece.push_str(&encoded);
+    }
+    pieces.push(piece);
+    if pieces.len() == 1 {
+        return format!("{}*={}", name, pieces[0]);
+    }
+    let numbered: Vec<String> = pieces.iter().enumerate().map(|(i, piece)| format!("{}*{}*={}", name, i, piece)).collect();
+    numbered.join("; ")
+}
+
+// "report.pdf" with the quotes, for name= and filename=
+fn quoted(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn base64_lines(data: &[u8]) -> String {
+    let encoded = base64::encode(data);
+    let lines: Vec<&str> = encoded
+        .as_bytes()
+        .chunks(BASE64_LINE)
+        .map(|line| std::str::from_utf8(line).unwrap_or_default())
+        .collect();
+    lines.join("\r\n")
+}
+
+// <time.pid.count@domain of the sender>
+fn new_message_id(from: &EmailAddress) -> String {
+    let spec = from.addr_spec();
+    let domain = spec.rsplit_once('@').map(|(_, domain)| domain).unwrap_or("localhost");
+    format!(
+        "{}.{}.{}@{}",
+        Timestamp::now().0,
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed),
+        domain
+    )
+}
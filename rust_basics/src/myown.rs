@@ -17,14 +17,20 @@ struct Student {
 // ============================================sting too
 
 
-// ENUM = IS one of these options (Active OR Inactive OR Pending)
+// ENUM = IS one of these options (Active OR Inactive OR Pending OR Withdrawn)
 // A status can only BE one of these, not all
 // ============================================
-enum Status {
-    Active,
-    Inactive,
-    Pending,
-}
+// learning_project has the same enum, so instead of a second copy this file
+// brings in status.rs (and datetime.rs, which status.rs needs).
+// Still builds on its own: rustc --edition 2024 src/myown.rs
+#[allow(dead_code)]
+#[path = "datetime.rs"]
+mod datetime;
+#[allow(dead_code)]
+#[path = "status.rs"]
+mod status;
+
+use status::Status;
 // this is me using result in a function
 fn addition(a :i32 , b:i32)-> Result<i32,String>{
     if a < 0 || b < 0 {
@@ -176,6 +182,7 @@ fn main() -> Result<(), String> {
         Status::Active => println!("Status: Active!"),
         Status::Inactive => println!("Status: Inactive"),
         Status::Pending => println!("Status: Pending..."),
+        Status::Withdrawn => println!("Status: Withdrawn"),
     }
     Ok(())
 }
@@ -0,0 +1,354 @@
+// ============================================
+// Notifications, sent to one or more sinks
+// ============================================
+// Summary::notify used to println! and that was it. Now a notification goes
+// to every sink in a Notifiers list, and each sink is one type that
+// implements the Notifier trait:
+//
+//   Stdout      prints "title: text", what notify always did
+//   AppendFile  adds one line per notification to a log file
+//   Mbox        appends a mail message to an mbox file (one file, many messages)
+//   Maildir     writes a mail message as its own file in a Maildir folder
+//   Webhook     POSTs the notification as JSON to an http:// URL
+//   Memory      keeps them in a Vec, handy for tests and the lesson 7 demo
+//
+// Sinks are tried one after another. If one fails the rest still get the
+// notification, and every failure is handed back together at the end.
+//
+// Which sinks notify uses comes from LEARNING_PROJECT_NOTIFY, a comma
+// separated list like "stdout,file:notes.log,webhook:http://localhost:8080/hook".
+// Without it notifications go to stdout like before.
+
+use std::cell::RefCell;
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::data_dir::with_path;
+use crate::datetime::Timestamp;
+use crate::http;
+use crate::json::Json;
+use crate::lock::{FileLock, Mode};
+use crate::message::encode_words;
+
+pub const NOTIFY_ENV: &str = "LEARNING_PROJECT_NOTIFY";
+// the sender of the mbox and Maildir messages
+const MAIL_FROM: &str = "learning_project@localhost";
+
+// Maildir file names need to be unique even for two notifications in the same second
+static DELIVERIES: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notification {
+    pub title: String,
+    pub text: String,
+    pub at: Timestamp,
+}
+
+impl Notification {
+    pub fn new(title: &str, text: &str) -> Notification {
+        Notification { title: title.to_string(), text: text.to_string(), at: Timestamp::now() }
+    }
+}
+
+// anything that can pass a notification on
+pub trait Notifier {
+    // shown in error messages, e.g. "file:notes.log"
+    fn name(&self) -> String;
+
+    fn send(&mut self, note: &Notification) -> io::Result<()>;
+}
+
+pub struct Stdout;
+
+impl Notifier for Stdout {
+    fn name(&self) -> String {
+        String::from("stdout")
+    }
+
+    fn send(&mut self, note: &Notification) -> io::Result<()> {
+        writeln!(io::stdout(), "{}: {}", note.title, note.text)
+    }
+}
+
+// one line per notification, "2026-10-18T07:51:11Z title: text"
+pub struct AppendFile {
+    pub path: PathBuf,
+}
+
+impl Notifier for AppendFile {
+    fn name(&self) -> String {
+        format!("file:{}", self.path.display())
+    }
+
+    fn send(&mut self, note: &Notification) -> io::Result<()> {
+        // a line break in the text would start a new entry, so it becomes a space
+        let line = format!("{} {}: {}", note.at, note.title, note.text).replace(['\r', '\n'], " ");
+        append(&self.path, format!("{}\n", line).as_bytes())
+    }
+}
+
+// mbox is every message in one file, each starting with a "From " line:
+//
+//   From learning_project@localhost Sun Oct 18 07:51:11 2026
+//   From: learning_project@localhost
+//   Subject: ...
+//
+//   the text
+//
+// a line in the text starting with "From " would look like the next message,
+// so it gets a ">" in front (and ">From " gets another one, so it can be undone)
+pub struct Mbox {
+    pub path: PathBuf,
+}
+
+impl Notifier for Mbox {
+    fn name(&self) -> String {
+        format!("mbox:{}", self.path.display())
+    }
+
+    fn send(&mut self, note: &Notification) -> io::Result<()> {
+        let mut body = String::new();
+        for line in note.text.lines() {
+            if line.trim_start_matches('>').starts_with("From ") {
+                body.push('>');
+            }
+            body.push_str(line);
+            body.push('\n');
+        }
+        let message = format!("From {} {}\n{}\n{}\n", MAIL_FROM, note.at.asctime(), mail_headers(note), body);
+        // mail programs lock the mbox too, two messages written at once would be mixed up
+        let _lock = FileLock::acquire(&self.path, Mode::Exclusive)?;
+        append(&self.path, message.as_bytes())
+    }
+}
+
+// a Maildir is a folder with tmp/, new/ and cur/ in it, one file per message.
+// The message is written in tmp/ and then renamed into new/, so a mail program
+// never sees half a message and no locking is needed
+pub struct Maildir {
+    pub dir: PathBuf,
+}
+
+impl Notifier for Maildir {
+    fn name(&self) -> String {
+        format!("maildir:{}", self.dir.display())
+    }
+
+    fn send(&mut self, note: &Notification) -> io::Result<()> {
+        for sub in ["tmp", "new", "cur"] {
+            let dir = self.dir.join(sub);
+            fs::create_dir_all(&dir).map_err(|e| with_path(e, &dir))?;
+        }
+        // time.P<pid>Q<count>.host is the usual unique name
+        let name = format!(
+            "{}.P{}Q{}.{}",
+            note.at.0,
+            std::process::id(),
+            DELIVERIES.fetch_add(1, Ordering::Relaxed),
+            hostname()
+        );
+        let tmp = self.dir.join("tmp").join(&name);
+        let message = format!("{}\n{}\n", mail_headers(note), note.text.trim_end());
+        fs::write(&tmp, message).map_err(|e| with_path(e, &tmp))?;
+        let new = self.dir.join("new").join(&name);
+        fs::rename(&tmp, &new).map_err(|e| with_path(e, &new))
+    }
+}
+
+// POSTs {"title": ..., "text": ..., "at": ...} to the URL, any answer that
+// isn't 2xx is an error. Only plain http://, https would need a TLS library
+pub struct Webhook {
+    pub url: String,
+}
+
+impl Notifier for Webhook {
+    fn name(&self) -> String {
+        format!("webhook:{}", self.url)
+    }
+
+    fn send(&mut self, note: &Notification) -> io::Result<()> {
+        let body = Json::Object(vec![
+            (String::from("title"), Json::String(note.title.clone())),
+            (String::from("text"), Json::String(note.text.clone())),
+            (String::from("at"), Json::String(note.at.to_string())),
+        ])
+        .to_string();
+        let response = http::post(&self.url, "application/json", body.as_bytes())?;
+        if response.is_success() {
+            Ok(())
+        } else {
+            Err(io::Error::other(format!("the server answered {:?}", response.status_line)))
+        }
+    }
+}
+
+// keeps every notification it gets. Clones share the same list, so keep
+// one clone to look at and hand the other to a Notifiers
+#[derive(Clone, Default)]
+pub struct Memory {
+    notes: Rc<RefCell<Vec<Notification>>>,
+}
+
+impl Memory {
+    pub fn new() -> Memory {
+        Memory::default()
+    }
+
+    // everything received so far, the list is empty afterwards
+    pub fn taken(&self) -> Vec<Notification> {
+        self.notes.borrow_mut().drain(..).collect()
+    }
+}
+
+impl Notifier for Memory {
+    fn name(&self) -> String {
+        String::from("memory")
+    }
+
+    fn send(&mut self, note: &Notification) -> io::Result<()> {
+        self.notes.borrow_mut().push(note.clone());
+        Ok(())
+    }
+}
+
+// a sink from LEARNING_PROJECT_NOTIFY we couldn't understand. It fails every
+// time, so a typo shows up as an error next to the sinks that did work
+struct Broken {
+    spec: String,
+    message: String,
+}
+
+impl Notifier for Broken {
+    fn name(&self) -> String {
+        self.spec.clone()
+    }
+
+    fn send(&mut self, _note: &Notification) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::InvalidInput, self.message.clone()))
+    }
+}
+
+// one sink that failed
+#[derive(Debug)]
+pub struct NotifyError {
+    pub sink: String,
+    pub error: io::Error,
+}
+
+impl fmt::Display for NotifyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.sink, self.error)
+    }
+}
+
+// every sink that failed, the others did get the notification
+#[derive(Debug)]
+pub struct NotifyErrors(pub Vec<NotifyError>);
+
+impl fmt::Display for NotifyErrors {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, error) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{}", error)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for NotifyErrors {}
+
+// the sinks a notification goes to
+#[derive(Default)]
+pub struct Notifiers {
+    sinks: Vec<Box<dyn Notifier>>,
+}
+
+impl Notifiers {
+    pub fn new() -> Notifiers {
+        Notifiers::default()
+    }
+
+    pub fn add(mut self, sink: impl Notifier + 'static) -> Notifiers {
+        self.sinks.push(Box::new(sink));
+        self
+    }
+
+    // the sinks named in LEARNING_PROJECT_NOTIFY, or stdout when it isn't set
+    pub fn configured() -> Notifiers {
+        Notifiers::parse(&configured_specs().join(","))
+    }
+
+    // "stdout", "file:PATH", "mbox:PATH", "maildir:DIR" or "webhook:URL", separated by commas
+    pub fn parse(specs: &str) -> Notifiers {
+        let mut notifiers = Notifiers::new();
+        for spec in specs.split(',').map(str::trim).filter(|spec| !spec.is_empty()) {
+            // a bad spec still gets a sink, one that fails, so the mistake shows up in the errors
+            let sink = sink(spec).unwrap_or_else(|message| Box::new(Broken { spec: spec.to_string(), message }));
+            notifiers.sinks.push(sink);
+        }
+        notifiers
+    }
+
+    // sends to every sink, even after one of them fails
+    pub fn send(&mut self, note: &Notification) -> Result<(), NotifyErrors> {
+        let mut errors = Vec::new();
+        for sink in &mut self.sinks {
+            if let Err(error) = sink.send(note) {
+                errors.push(NotifyError { sink: sink.name(), error });
+            }
+        }
+        if errors.is_empty() { Ok(()) } else { Err(NotifyErrors(errors)) }
+    }
+}
+
+// the sink for one spec, or what is wrong with it
+fn sink(spec: &str) -> Result<Box<dyn Notifier>, String> {
+    let (kind, target) = spec.split_once(':').unwrap_or((spec, ""));
+    Ok(match (kind, target) {
+        ("stdout", "") => Box::new(Stdout),
+        ("file", path) if !path.is_empty() => Box::new(AppendFile { path: PathBuf::from(path) }),
+        ("mbox", path) if !path.is_empty() => Box::new(Mbox { path: PathBuf::from(path) }),
+        ("maildir", dir) if !dir.is_empty() => Box::new(Maildir { dir: PathBuf::from(dir) }),
+        ("webhook", url) if !url.is_empty() => Box::new(Webhook { url: url.to_string() }),
+        ("file" | "mbox" | "maildir" | "webhook", _) => return Err(format!("{}: needs something after the colon", kind)),
+        _ => return Err(String::from("unknown sink (try: stdout, file:PATH, mbox:PATH, maildir:DIR, webhook:URL)")),
+    })
+}
+
+// checks a spec without making the sink, for specs that are saved to use later
+pub fn check_spec(spec: &str) -> Result<(), String> {
+    sink(spec).map(|_| ())
+}
+
+// the specs in LEARNING_PROJECT_NOTIFY one by one, ["stdout"] when it isn't set.
+// The outbox keeps these with each notification, so a retry knows where it still has to go
+pub fn configured_specs() -> Vec<String> {
+    let specs = std::env::var(NOTIFY_ENV).unwrap_or_default();
+    let specs: Vec<String> = specs.split(',').map(str::trim).filter(|spec| !spec.is_empty()).map(String::from).collect();
+    if specs.is_empty() { vec![String::from("stdout")] } else { specs }
+}
+
+fn append(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let mut file = OpenOptions::new().append(true).create(true).open(path).map_err(|e| with_path(e, path))?;
+    file.write_all(bytes).map_err(|e| with_path(e, path))
+}
+
+// the headers of the mbox and Maildir messages, ending in a line break
+fn mail_headers(note: &Notification) -> String {
+    let subject = note.title.replace(['\r', '\n'], " ");
+    format!("From: {}\nSubject: {}\nDate: {}\n", MAIL_FROM, encode_words(&subject), note.at.rfc5322())
+}
+
+fn hostname() -> String {
+    fs::read_to_string("/proc/sys/kernel/hostname")
+        .map(|name| name.trim().replace(['/', ':'], "_"))
+        .ok()
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| String::from("localhost"))
+}
@@ -0,0 +1,514 @@
+// ============================================
+// The notification outbox
+// ============================================
+// Sending straight to the sinks loses a notification when a sink is down.
+// Now notify first writes it to outbox.jsonl in the data directory (one JSON
+// object per line) and only then tries the sinks. A sink that took it is
+// crossed off, the ones that failed are tried again later:
+//
+//   attempt 1 fails -> wait about 10s, attempt 2 fails -> about 20s, then 40s, ...
+//
+// The wait doubles every time (exponential backoff, at most an hour) and only
+// a random 50-100% of it is used (jitter), so a pile of notifications that
+// failed together doesn't come back all at the same moment.
+// After 5 failed attempts a notification is moved to outbox.dead.jsonl,
+// otherwise one that can never be delivered (a typo in a sink) would be retried forever.
+//
+// The file survives a restart, `learning_project outbox work` delivers whatever
+// is still waiting. LEARNING_PROJECT_OUTBOX_ATTEMPTS and LEARNING_PROJECT_OUTBOX_BACKOFF
+// (the first wait in seconds) change the defaults.
+//
+// Notifications only get in here past the limiter (limiter.rs), which drops
+// duplicates and holds back the ones over the rate limit.
+
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use crate::atomic_write::write_atomic;
+use crate::data_dir::{DataDir, with_path};
+use crate::datetime::Timestamp;
+use crate::json::Json;
+use crate::limiter::{Decision, LIMITER_FILE, Limiter, Limits};
+use crate::lock::{FileLock, Mode};
+use crate::notifier::{Notification, Notifiers, NotifyErrors, configured_specs};
+use crate::template::TemplateError;
+
+pub const OUTBOX_FILE: &str = "outbox.jsonl";
+pub const DEAD_FILE: &str = "outbox.dead.jsonl";
+pub const ATTEMPTS_ENV: &str = "LEARNING_PROJECT_OUTBOX_ATTEMPTS";
+pub const BACKOFF_ENV: &str = "LEARNING_PROJECT_OUTBOX_BACKOFF";
+const DEFAULT_ATTEMPTS: u32 = 5;
+const DEFAULT_BACKOFF: f64 = 10.0;
+const MAX_BACKOFF: f64 = 3600.0;
+// a record that is being delivered is left alone by other processes for this long.
+// if the process dies halfway, the record is picked up again after that
+const CLAIM_SECONDS: i64 = 60;
+
+// main sets this from --data-dir, Summary::notify has no DataDir to pass along
+static OUTBOX_PATH: OnceLock<PathBuf> = OnceLock::new();
+// record ids need to be unique even for two notifications in the same second
+static RECORDS: AtomicUsize = AtomicUsize::new(0);
+
+// one notification and the sinks that still have to get it
+#[derive(Debug, Clone)]
+pub struct Record {
+    pub id: String,
+    pub note: Notification,
+    pub sinks: Vec<String>,
+    pub attempts: u32,
+    pub next_attempt: Timestamp,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum OutboxError {
+    Io(io::Error),
+    Malformed { path: PathBuf, line: usize, message: String },
+    // the notification template (see item.rs) didn't work for this notification
+    Template(TemplateError),
+}
+
+impl fmt::Display for OutboxError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OutboxError::Io(e) => write!(f, "{}", e),
+            OutboxError::Malformed { path, line, message } => write!(f, "{} line {}: {}", path.display(), line, message),
+            OutboxError::Template(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for OutboxError {}
+
+impl From<io::Error> for OutboxError {
+    fn from(e: io::Error) -> Self {
+        OutboxError::Io(e)
+    }
+}
+
+impl From<TemplateError> for OutboxError {
+    fn from(e: TemplateError) -> Self {
+        OutboxError::Template(e)
+    }
+}
+
+// how often to try and how long to wait in between
+#[derive(Debug, Clone, Copy)]
+pub struct Retry {
+    pub max_attempts: u32,
+    // seconds to wait after the first failure
+    pub backoff: f64,
+}
+
+impl Retry {
+    pub fn configured() -> io::Result<Retry> {
+        let bad = |name: &str, value: &str, expected: &str| {
+            io::Error::new(io::ErrorKind::InvalidInput, format!("{} must be {}, got {:?}", name, expected, value))
+        };
+        let max_attempts = match std::env::var(ATTEMPTS_ENV) {
+            Ok(value) => match value.trim().parse::<u32>() {
+                Ok(n) if n > 0 => n,
+                _ => return Err(bad(ATTEMPTS_ENV, &value, "a whole number above 0")),
+            },
+            Err(_) => DEFAULT_ATTEMPTS,
+        };
+        let backoff = match std::env::var(BACKOFF_ENV) {
+            Ok(value) => match value.trim().parse::<f64>() {
+                Ok(seconds) if (0.0..=MAX_BACKOFF).contains(&seconds) => seconds,
+                _ => return Err(bad(BACKOFF_ENV, &value, "a number of seconds up to an hour")),
+            },
+            Err(_) => DEFAULT_BACKOFF,
+        };
+        Ok(Retry { max_attempts, backoff })
+    }
+
+    // the wait after `attempts` failed attempts: backoff, 2 x backoff, 4 x backoff, ...
+    // then a random 50-100% of that
+    pub fn delay(&self, attempts: u32) -> Duration {
+        let doubled = self.backoff * 2f64.powi(attempts.saturating_sub(1).min(30) as i32);
+        Duration::from_secs_f64(doubled.min(MAX_BACKOFF) * (0.5 + 0.5 * random_fraction()))
+    }
+}
+
+// one record that didn't reach every sink this time
+#[derive(Debug)]
+pub struct Failure {
+    pub id: String,
+    pub errors: NotifyErrors,
+    pub attempts: u32,
+    // None when we gave up and moved it to the dead letter file
+    pub retry_at: Option<Timestamp>,
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.retry_at {
+            Some(at) => write!(f, "{} (attempt {}, trying again at {})", self.errors, self.attempts, at),
+            None => write!(f, "{} (gave up after {} attempts, moved to {})", self.errors, self.attempts, DEAD_FILE),
+        }
+    }
+}
+
+// what one delivery round did
+#[derive(Debug, Default)]
+pub struct Report {
+    pub delivered: usize,
+    pub failed: Vec<Failure>,
+    // recipients the limiter kept this notification from, see limiter.rs
+    pub suppressed: usize,
+    pub deferred: usize,
+}
+
+impl Report {
+    // "1 duplicate suppressed, 2 deferred by the rate limit", None when the
+    // limiter let everything through
+    pub fn held_back(&self) -> Option<String> {
+        let mut parts = Vec::new();
+        if self.suppressed > 0 {
+            parts.push(format!("{} duplicate{} suppressed", self.suppressed, if self.suppressed == 1 { "" } else { "s" }));
+        }
+        if self.deferred > 0 {
+            parts.push(format!("{} deferred by the rate limit", self.deferred));
+        }
+        if parts.is_empty() { None } else { Some(parts.join(", ")) }
+    }
+
+    // adds up two rounds
+    pub fn add(&mut self, other: Report) {
+        self.delivered += other.delivered;
+        self.failed.extend(other.failed);
+        self.suppressed += other.suppressed;
+        self.deferred += other.deferred;
+    }
+}
+
+pub struct Outbox {
+    path: PathBuf,
+    dead: PathBuf,
+    retry: Retry,
+}
+
+impl Outbox {
+    pub fn open(path: &Path, retry: Retry) -> Outbox {
+        Outbox { path: path.to_path_buf(), dead: path.with_file_name(DEAD_FILE), retry }
+    }
+
+    // the outbox in the data directory, with the retry settings from the environment
+    pub fn configured() -> Result<Outbox, OutboxError> {
+        Ok(Outbox::open(&data_file(OUTBOX_FILE)?, Retry::configured()?))
+    }
+
+    // adds a notification for these sinks, due right away
+    pub fn enqueue(&self, note: Notification, sinks: Vec<String>) -> Result<String, OutboxError> {
+        let due = note.at;
+        self.enqueue_at(note, sinks, due)
+    }
+
+    // adds a notification that isn't tried before `due`
+    pub fn enqueue_at(&self, note: Notification, sinks: Vec<String>, due: Timestamp) -> Result<String, OutboxError> {
+        let _lock = FileLock::acquire(&self.path, Mode::Exclusive)?;
+        let id = format!("{}-{}-{}", note.at.0, std::process::id(), RECORDS.fetch_add(1, Ordering::Relaxed));
+        let mut records = read_records(&self.path)?;
+        records.push(Record {
+            id: id.clone(),
+            next_attempt: due,
+            note,
+            sinks,
+            attempts: 0,
+            last_error: None,
+        });
+        write_records(&self.path, &records)?;
+        Ok(id)
+    }
+
+    pub fn pending(&self) -> Result<Vec<Record>, OutboxError> {
+        let _lock = FileLock::acquire(&self.path, Mode::Shared)?;
+        read_records(&self.path)
+    }
+
+    pub fn dead(&self) -> Result<Vec<Record>, OutboxError> {
+        let _lock = FileLock::acquire(&self.path, Mode::Shared)?;
+        read_records(&self.dead)
+    }
+
+    // when the next record is due, None when the outbox is empty
+    pub fn next_due(&self) -> Result<Option<Timestamp>, OutboxError> {
+        Ok(self.pending()?.iter().map(|record| record.next_attempt).min())
+    }
+
+    // tries every record that is due. The file is only locked while records are
+    // picked and while the results are written back, not while a slow sink is
+    // being waited for, so notify in another process doesn't have to wait
+    pub fn deliver_due(&self) -> Result<Report, OutboxError> {
+        let now = Timestamp::now();
+        let claimed = {
+            let _lock = FileLock::acquire(&self.path, Mode::Exclusive)?;
+            let mut records = read_records(&self.path)?;
+            let mut claimed = Vec::new();
+            for record in records.iter_mut().filter(|record| record.next_attempt <= now) {
+                claimed.push(record.clone());
+                record.next_attempt = Timestamp(now.0 + CLAIM_SECONDS);
+            }
+            if !claimed.is_empty() {
+                write_records(&self.path, &records)?;
+            }
+            claimed
+        };
+        if claimed.is_empty() {
+            return Ok(Report::default());
+        }
+
+        // a sink that takes it is crossed off, so a retry only goes to the ones that failed
+        let mut results = Vec::new();
+        for mut record in claimed {
+            let mut errors = Vec::new();
+            record.sinks.retain(|spec| match Notifiers::parse(spec).send(&record.note) {
+                Ok(()) => false,
+                Err(NotifyErrors(failed)) => {
+                    errors.extend(failed);
+                    true
+                }
+            });
+            results.push((record, errors));
+        }
+
+        let _lock = FileLock::acquire(&self.path, Mode::Exclusive)?;
+        let mut records = read_records(&self.path)?;
+        let mut dead = Vec::new();
+        let mut report = Report::default();
+        let finished_at = Timestamp::now();
+        for (mut result, errors) in results {
+            let Some(index) = records.iter().position(|record| record.id == result.id) else {
+                continue;
+            };
+            if errors.is_empty() {
+                records.remove(index);
+                report.delivered += 1;
+                continue;
+            }
+            let errors = NotifyErrors(errors);
+            result.attempts += 1;
+            result.last_error = Some(errors.to_string());
+            let retry_at = if result.attempts >= self.retry.max_attempts {
+                records.remove(index);
+                dead.push(result.clone());
+                None
+            } else {
+                let wait = self.retry.delay(result.attempts).as_secs_f64().ceil() as i64;
+                result.next_attempt = Timestamp(finished_at.0 + wait);
+                records[index] = result.clone();
+                Some(result.next_attempt)
+            };
+            report.failed.push(Failure { id: result.id, errors, attempts: result.attempts, retry_at });
+        }
+        write_records(&self.path, &records)?;
+        if !dead.is_empty() {
+            let lines: String = dead.iter().map(|record| format!("{}\n", to_json(record))).collect();
+            let mut file = OpenOptions::new().append(true).create(true).open(&self.dead).map_err(|e| with_path(e, &self.dead))?;
+            file.write_all(lines.as_bytes()).map_err(|e| with_path(e, &self.dead))?;
+        }
+        Ok(report)
+    }
+}
+
+// main calls this once with the data directory it resolved
+pub fn use_data_dir(data: &DataDir) {
+    let _ = OUTBOX_PATH.set(data.file(OUTBOX_FILE));
+}
+
+// a file next to the outbox
+pub fn data_file(name: &str) -> io::Result<PathBuf> {
+    match OUTBOX_PATH.get() {
+        Some(path) => Ok(path.with_file_name(name)),
+        None => Ok(DataDir::resolve(None)?.file(name)),
+    }
+}
+
+// the limiter in the data directory, with the limits from the environment
+pub fn configured_limiter() -> Result<Limiter, OutboxError> {
+    Ok(Limiter::open(&data_file(LIMITER_FILE)?, Limits::configured()?))
+}
+
+// what Summary::notify does: ask the limiter about every configured sink, queue
+// the notification for the ones it lets through (the deferred ones for later),
+// then deliver whatever is due (this one and any older ones waiting for a retry)
+pub fn notify(note: Notification) -> Result<Report, OutboxError> {
+    notify_to(note, &configured_specs())
+}
+
+// the same for these sinks instead of the ones in LEARNING_PROJECT_NOTIFY
+pub fn notify_to(note: Notification, specs: &[String]) -> Result<Report, OutboxError> {
+    let outbox = Outbox::configured()?;
+    let decisions = configured_limiter()?.check(&note, specs)?;
+    let mut now = Vec::new();
+    // one record per time, the outbox has one next_attempt for all a record's sinks
+    let mut later: Vec<(Timestamp, Vec<String>)> = Vec::new();
+    let (mut suppressed, mut deferred) = (0, 0);
+    for (spec, decision) in decisions {
+        match decision {
+            Decision::Send => now.push(spec),
+            Decision::Defer(at) => {
+                deferred += 1;
+                match later.iter_mut().find(|(due, _)| *due == at) {
+                    Some((_, specs)) => specs.push(spec),
+                    None => later.push((at, vec![spec])),
+                }
+            }
+            Decision::Suppress => suppressed += 1,
+        }
+    }
+    if !now.is_empty() {
+        outbox.enqueue(note.clone(), now)?;
+    }
+    for (due, specs) in later {
+        outbox.enqueue_at(note.clone(), specs, due)?;
+    }
+    let mut report = outbox.deliver_due()?;
+    report.suppressed = suppressed;
+    report.deferred = deferred;
+    Ok(report)
+}
+
+fn to_json(record: &Record) -> Json {
+    let text = |value: &str| Json::String(value.to_string());
+    Json::Object(vec![
+        (String::from("id"), text(&record.id)),
+        (String::from("title"), text(&record.note.title)),
+        (String::from("text"), text(&record.note.text)),
+        (String::from("at"), text(&record.note.at.to_string())),
+        (String::from("sinks"), Json::Array(record.sinks.iter().map(|spec| text(spec)).collect())),
+        (String::from("attempts"), Json::Number(record.attempts as f64)),
+        (String::from("next_attempt"), text(&record.next_attempt.to_string())),
+        (String::from("last_error"), record.last_error.as_deref().map(text).unwrap_or(Json::Null)),
+    ])
+}
+
+fn from_json(value: &Json) -> Result<Record, String> {
+    let text = |field: &str| match value.get(field) {
+        Some(Json::String(s)) => Ok(s.clone()),
+        _ => Err(format!("{:?} should be a string", field)),
+    };
+    let time = |field: &str| Timestamp::parse_rfc3339(&text(field)?).ok_or_else(|| format!("{:?} is not a time", field));
+    let sinks = match value.get("sinks") {
+        Some(Json::Array(items)) => items
+            .iter()
+            .map(|item| match item {
+                Json::String(spec) => Ok(spec.clone()),
+                _ => Err(String::from("\"sinks\" should only hold strings")),
+            })
+            .collect::<Result<Vec<String>, String>>()?,
+        _ => return Err(String::from("\"sinks\" should be a list")),
+    };
+    let attempts = match value.get("attempts") {
+        Some(Json::Number(n)) if n.fract() == 0.0 && *n >= 0.0 => *n as u32,
+        _ => return Err(String::from("\"attempts\" should be a whole number")),
+    };
+    let last_error = match value.get("last_error") {
+        Some(Json::String(error)) => Some(error.clone()),
+        _ => None,
+    };
+    Ok(Record {
+        id: text("id")?,
+        note: Notification { title: text("title")?, text: text("text")?, at: time("at")? },
+        sinks,
+        attempts,
+        next_attempt: time("next_attempt")?,
+        last_error,
+    })
+}
+
+fn read_records(path: &Path) -> Result<Vec<Record>, OutboxError> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(with_path(e, path).into()),
+    };
+    let mut records = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let malformed = |message: String| OutboxError::Malformed { path: path.to_path_buf(), line: index + 1, message };
+        let value = Json::parse(line).map_err(|e| malformed(e.to_string()))?;
+        records.push(from_json(&value).map_err(malformed)?);
+    }
+    Ok(records)
+}
+
+fn write_records(path: &Path, records: &[Record]) -> io::Result<()> {
+    let lines: String = records.iter().map(|record| format!("{}\n", to_json(record))).collect();
+    write_atomic(path, lines.as_bytes())
+}
+
+// a number from 0 up to 1. RandomState is seeded differently every time it is
+// made, that is random enough to spread retries out and needs no crate
+fn random_fraction() -> f64 {
+    let bits = RandomState::new().build_hasher().finish() >> 11;
+    bits as f64 / (1u64 << 53) as f64
+}
+
+// `learning_project outbox list|dead|deliver|work`
+pub fn run(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let usage = "usage: outbox <list|dead|deliver|work|limits>";
+    let outbox = Outbox::configured()?;
+    match args.first().map(String::as_str) {
+        Some("list") => print_records(&outbox.pending()?),
+        Some("dead") => print_records(&outbox.dead()?),
+        Some("deliver") => print_report(&outbox.deliver_due()?),
+        // keeps delivering, sleeping until the next retry is due, until the outbox is empty
+        Some("work") => {
+            while let Some(due) = outbox.next_due()? {
+                let wait = due.0 - Timestamp::now().0;
+                if wait > 0 {
+                    println!("waiting {}s for the next retry", wait);
+                    std::thread::sleep(Duration::from_secs(wait as u64));
+                }
+                print_report(&outbox.deliver_due()?);
+            }
+            println!("outbox is empty");
+        }
+        Some("limits") => {
+            let state = configured_limiter()?.state()?;
+            println!("suppressed {}, deferred {}", state.suppressed, state.deferred);
+            for (key, bucket) in &state.buckets {
+                println!("{}: {:.1} tokens at {}", key, bucket.tokens, bucket.at);
+            }
+        }
+        _ => return Err(usage.into()),
+    }
+    Ok(())
+}
+
+fn print_records(records: &[Record]) {
+    if records.is_empty() {
+        println!("(none)");
+    }
+    for record in records {
+        println!(
+            "{}  {}: {}  to {}, {} attempts, next {}",
+            record.id,
+            record.note.title,
+            record.note.text,
+            record.sinks.join(","),
+            record.attempts,
+            record.next_attempt
+        );
+        if let Some(error) = &record.last_error {
+            println!("    last error: {}", error);
+        }
+    }
+}
+
+fn print_report(report: &Report) {
+    println!("delivered {}", report.delivered);
+    for failure in &report.failed {
+        println!("{}: {}", failure.id, failure);
+    }
+}
@@ -0,0 +1,339 @@
+// ============================================
+// Asking questions about a list of students
+// ============================================
+// A Query is a list of filters plus optional sorting and paging.
+// You can build one in code:
+//
+//     Query::new().active(true).age_between(18, 25).name_starts_with("J")
+//
+// or parse the same thing from text, so it can come from the command line:
+//
+//     active=true age=18..25 name^=J sort=-age limit=10 offset=0
+//
+// A value with spaces goes in double quotes: name="Mary Ann"
+//
+// Filters (all of them must match):
+//   name=John   exact name, ignoring case
+//   name^=J     name starts with, ignoring case
+//   name~=oh    name contains, ignoring case
+//   age=20  age=18..25  age>=18  age<=25  age>18  age<25
+//   active=true / active=false
+//   status=active|inactive|pending|withdrawn
+// Then: sort=name|age|status (a leading - sorts descending), limit=N, offset=N
+
+use std::fmt;
+
+use crate::status::Status;
+use crate::Student;
+
+#[derive(Debug, Clone)]
+enum Filter {
+    NameIs(String),
+    NameStartsWith(String),
+    NameContains(String),
+    AgeAtLeast(u8),
+    AgeAtMost(u8),
+    Active(bool),
+    Status(Status),
+}
+
+impl Filter {
+    fn matches(&self, student: &Student) -> bool {
+        let name = student.name.to_lowercase();
+        match self {
+            Filter::NameIs(wanted) => name == *wanted,
+            Filter::NameStartsWith(prefix) => name.starts_with(prefix.as_str()),
+            Filter::NameContains(part) => name.contains(part.as_str()),
+            Filter::AgeAtLeast(min) => student.age >= *min,
+            Filter::AgeAtMost(max) => student.age <= *max,
+            Filter::Active(active) => student.is_active() == *active,
+            Filter::Status(status) => student.status == *status,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortField {
+    Name,
+    Age,
+    Status,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Order {
+    Ascending,
+    Descending,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Query {
+    filters: Vec<Filter>,
+    sort: Option<(SortField, Order)>,
+    limit: Option<usize>,
+    offset: usize,
+}
+
+// count and average age of the students that matched (before paging)
+pub struct Stats {
+    pub count: usize,
+    pub average_age: Option<f64>,
+    pub youngest: Option<u8>,
+    pub oldest: Option<u8>,
+}
+
+#[derive(Debug)]
+pub struct QueryError {
+    pub term: String,
+    pub message: String,
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "in {:?}: {}", self.term, self.message)
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+impl Query {
+    pub fn new() -> Query {
+        Query::default()
+    }
+
+    pub fn name_is(mut self, name: &str) -> Query {
+        self.filters.push(Filter::NameIs(name.to_lowercase()));
+        self
+    }
+
+    pub fn name_starts_with(mut self, prefix: &str) -> Query {
+        self.filters.push(Filter::NameStartsWith(prefix.to_lowercase()));
+        self
+    }
+
+    pub fn name_contains(mut self, part: &str) -> Query {
+        self.filters.push(Filter::NameContains(part.to_lowercase()));
+        self
+    }
+
+    pub fn min_age(mut self, min: u8) -> Query {
+        self.filters.push(Filter::AgeAtLeast(min));
+        self
+    }
+
+    pub fn max_age(mut self, max: u8) -> Query {
+        self.filters.push(Filter::AgeAtMost(max));
+        self
+    }
+
+    // both ends included, so 18..25 means 18 up to and including 25
+    pub fn age_between(self, min: u8, max: u8) -> Query {
+        self.min_age(min).max_age(max)
+    }
+
+    pub fn active(mut self, active: bool) -> Query {
+        self.filters.push(Filter::Active(active));
+        self
+    }
+
+    pub fn status(mut self, status: Status) -> Query {
+        self.filters.push(Filter::Status(status));
+        self
+    }
+
+    pub fn sort_by(mut self, field: SortField, order: Order) -> Query {
+        self.sort = Some((field, order));
+        self
+    }
+
+    pub fn limit(mut self, limit: usize) -> Query {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn offset(mut self, offset: usize) -> Query {
+        self.offset = offset;
+        self
+    }
+
+    pub fn matches(&self, student: &Student) -> bool {
+        self.filters.iter().all(|filter| filter.matches(student))
+    }
+
+    // filter, sort, then page
+    pub fn run<'a>(&self, students: &'a [Student]) -> Vec<&'a Student> {
+        let mut found: Vec<&Student> = students.iter().filter(|s| self.matches(s)).collect();
+        if let Some((field, order)) = self.sort {
+            found.sort_by(|a, b| {
+                let ordering = match field {
+                    SortField::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+                    SortField::Age => a.age.cmp(&b.age),
+                    SortField::Status => a.status.cmp(&b.status),
+                };
+                // ties keep a stable order by name so paging doesn't jump around
+                let ordering = ordering.then_with(|| a.name.cmp(&b.name));
+                match order {
+                    Order::Ascending => ordering,
+                    Order::Descending => ordering.reverse(),
+                }
+            });
+        }
+        let limit = self.limit.unwrap_or(usize::MAX);
+        found.into_iter().skip(self.offset).take(limit).collect()
+    }
+
+    pub fn count(&self, students: &[Student]) -> usize {
+        students.iter().filter(|s| self.matches(s)).count()
+    }
+
+    pub fn stats(&self, students: &[Student]) -> Stats {
+        let ages: Vec<u8> = students
+            .iter()
+            .filter(|s| self.matches(s))
+            .map(|s| s.age)
+            .collect();
+        let total: u32 = ages.iter().map(|&age| age as u32).sum();
+        Stats {
+            count: ages.len(),
+            average_age: if ages.is_empty() { None } else { Some(total as f64 / ages.len() as f64) },
+            youngest: ages.iter().copied().min(),
+            oldest: ages.iter().copied().max(),
+        }
+    }
+
+    // parses the text syntax described at the top of this file
+    pub fn parse(text: &str) -> Result<Query, QueryError> {
+        let mut query = Query::new();
+        for (written, term) in split_terms(text)? {
+            query = query.apply_term(&written, &term)?;
+        }
+        Ok(query)
+    }
+
+    // `written` is the term as typed, quotes and all, so errors show what the user wrote
+    fn apply_term(self, written: &str, term: &str) -> Result<Query, QueryError> {
+        let error = |message: &str| QueryError { term: written.to_string(), message: message.to_string() };
+        let number = |text: &str| -> Result<u8, QueryError> {
+            text.parse().map_err(|_| error("expected a number from 0 to 255"))
+        };
+
+        // longest operators first so ">=" isn't read as ">"
+        let operators = ["^=", "~=", ">=", "<=", "=", ">", "<"];
+        let (key, op, value) = operators
+            .iter()
+            .filter_map(|op| term.find(op).map(|at| (at, *op)))
+            .min_by(|(a, op_a), (b, op_b)| a.cmp(b).then(op_b.len().cmp(&op_a.len())))
+            .map(|(at, op)| (&term[..at], op, &term[at + op.len()..]))
+            .ok_or_else(|| error("expected KEY=VALUE"))?;
+        if value.is_empty() {
+            return Err(error("missing value"));
+        }
+
+        match (key, op) {
+            ("name", "=") => Ok(self.name_is(value)),
+            ("name", "^=") => Ok(self.name_starts_with(value)),
+            ("name", "~=") => Ok(self.name_contains(value)),
+            ("age", "=") => match value.split_once("..") {
+                Some((min, max)) => {
+                    let (min, max) = (number(min)?, number(max)?);
+                    if min > max {
+                        return Err(error("range is backwards"));
+                    }
+                    Ok(self.age_between(min, max))
+                }
+                None => {
+                    let age = number(value)?;
+                    Ok(self.age_between(age, age))
+                }
+            },
+            ("age", ">=") => Ok(self.min_age(number(value)?)),
+            ("age", "<=") => Ok(self.max_age(number(value)?)),
+            ("age", ">") => {
+                let age = number(value)?;
+                let min = age.checked_add(1).ok_or_else(|| error("no age is above 255"))?;
+                Ok(self.min_age(min))
+            }
+            ("age", "<") => {
+                let age = number(value)?;
+                let max = age.checked_sub(1).ok_or_else(|| error("no age is below 0"))?;
+                Ok(self.max_age(max))
+            }
+            ("active", "=") => match value {
+                "true" => Ok(self.active(true)),
+                "false" => Ok(self.active(false)),
+                _ => Err(error("expected true or false")),
+            },
+            ("status", "=") => match Status::from_name(value) {
+                Some(status) => Ok(self.status(status)),
+                None => Err(error("expected active, inactive, pending or withdrawn")),
+            },
+            ("sort", "=") => {
+                let (order, field) = match value.strip_prefix('-') {
+                    Some(field) => (Order::Descending, field),
+                    None => (Order::Ascending, value),
+                };
+                let field = match field {
+                    "name" => SortField::Name,
+                    "age" => SortField::Age,
+                    "status" => SortField::Status,
+                    _ => return Err(error("can sort by name, age or status")),
+                };
+                Ok(self.sort_by(field, order))
+            }
+            ("limit", "=") => Ok(self.limit(value.parse().map_err(|_| error("expected a number"))?)),
+            ("offset", "=") => Ok(self.offset(value.parse().map_err(|_| error("expected a number"))?)),
+            _ => Err(error("unknown filter")),
+        }
+    }
+}
+
+// splits on whitespace, except inside "double quotes". In quotes \" is a quote and
+// \\ a backslash, any other backslash is kept as it is.
+// gives back each term as it was written and with the quotes taken out
+fn split_terms(text: &str) -> Result<Vec<(String, String)>, QueryError> {
+    let mut terms = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        let mut term = String::new();
+        let mut end = text.len();
+        let mut quoted = false;
+        while let Some(&(at, c)) = chars.peek() {
+            if c.is_whitespace() && !quoted {
+                end = at;
+                break;
+            }
+            chars.next();
+            match c {
+                '"' => quoted = !quoted,
+                '\\' if quoted => match chars.peek() {
+                    Some(&(_, next @ ('"' | '\\'))) => {
+                        chars.next();
+                        term.push(next);
+                    }
+                    _ => term.push(c),
+                },
+                _ => term.push(c),
+            }
+        }
+        let written = text[start..end].to_string();
+        if quoted {
+            return Err(QueryError { term: written, message: String::from("quote is never closed") });
+        }
+        terms.push((written, term));
+    }
+    Ok(terms)
+}
+
+// so a Stats can be printed straight away
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "count: {}", self.count)?;
+        if let (Some(average), Some(youngest), Some(oldest)) = (self.average_age, self.youngest, self.oldest) {
+            write!(f, ", average age: {:.1}, youngest: {}, oldest: {}", average, youngest, oldest)?;
+        }
+        Ok(())
+    }
+}
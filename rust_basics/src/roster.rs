@@ -0,0 +1,412 @@
+// ============================================
+// Roster: every student in one file
+// ============================================
+// save_student only keeps the last student, the roster keeps all of them.
+// The file is the same Name/Age/Status layout as student.txt,
+// with a blank line between students.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::atomic_write::write_atomic;
+use crate::csv;
+use crate::data_dir::{self, DataDir};
+use crate::fuzzy::{self, Hit};
+use crate::json;
+use crate::lock::{FileLock, Mode};
+use crate::query::Query;
+use crate::schema;
+use crate::status::TransitionError;
+use crate::{format_student, parse_record, LoadError, Student, StudentErrors};
+
+pub const ROSTER_FILE: &str = "roster.txt";
+
+#[derive(Debug)]
+pub enum RosterError {
+    Io(io::Error),
+    Load(LoadError),
+    DuplicateName(String),
+    // the closest name in the roster, if one is close enough to be a typo
+    NotFound { name: String, suggestion: Option<String> },
+    Student(StudentErrors),
+    Transition(TransitionError),
+    Invalid(String),
+}
+
+impl fmt::Display for RosterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RosterError::Io(e) => write!(f, "roster file error: {}", e),
+            RosterError::Load(e) => write!(f, "roster file is malformed: {}", e),
+            RosterError::DuplicateName(name) => write!(f, "a student named {:?} already exists", name),
+            RosterError::NotFound { name, suggestion: None } => write!(f, "no student named {:?}", name),
+            RosterError::NotFound { name, suggestion: Some(close) } => {
+                write!(f, "no student named {:?}, did you mean {:?}?", name, close)
+            }
+            RosterError::Student(errors) => write!(f, "invalid student: {}", errors),
+            RosterError::Transition(e) => write!(f, "{}", e),
+            RosterError::Invalid(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+impl std::error::Error for RosterError {}
+
+impl From<io::Error> for RosterError {
+    fn from(e: io::Error) -> RosterError {
+        RosterError::Io(e)
+    }
+}
+
+impl From<StudentErrors> for RosterError {
+    fn from(e: StudentErrors) -> RosterError {
+        RosterError::Student(e)
+    }
+}
+
+impl From<TransitionError> for RosterError {
+    fn from(e: TransitionError) -> RosterError {
+        RosterError::Transition(e)
+    }
+}
+
+impl From<LoadError> for RosterError {
+    fn from(e: LoadError) -> RosterError {
+        RosterError::Load(e)
+    }
+}
+
+// the fields we are allowed to change on an existing student, None means "leave it alone".
+// status is not here, it changes through change_status so the history is kept
+#[derive(Default)]
+pub struct StudentUpdate {
+    pub name: Option<String>,
+    pub age: Option<u8>,
+}
+
+pub struct Roster {
+    path: PathBuf,
+    students: Vec<Student>,
+}
+
+impl Roster {
+    // a missing file is just an empty roster, anything else has to parse
+    pub fn open(path: impl AsRef<Path>) -> Result<Roster, RosterError> {
+        let path = path.as_ref().to_path_buf();
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(RosterError::Io(data_dir::with_path(e, &path))),
+        };
+        Roster::parse(path, &content)
+    }
+
+    // the roster in `content`, as if it was read from `path`
+    pub fn parse(path: impl AsRef<Path>, content: &str) -> Result<Roster, RosterError> {
+        // older files are upgraded to the current layout first, see schema.rs
+        let content = schema::upgrade(content)?.body;
+        let mut roster = Roster { path: path.as_ref().to_path_buf(), students: Vec::new() };
+        for record in split_records(&content) {
+            let student = parse_record(record.into_iter())?;
+            if roster.position(&student.name).is_some() {
+                return Err(RosterError::DuplicateName(student.name));
+            }
+            roster.students.push(student);
+        }
+        Ok(roster)
+    }
+
+    // an older file on disk is left for migrate, so it gets its backup
+    pub fn save(&self) -> Result<(), RosterError> {
+        schema::check_on_disk(&self.path)?;
+        write_atomic(&self.path, self.to_text().as_bytes())?;
+        Ok(())
+    }
+
+    // the version line, then one record per student with a blank line between them
+    pub fn to_text(&self) -> String {
+        let records: Vec<String> = self.students.iter().map(format_student).collect();
+        let mut content = schema::header();
+        content.push_str(&records.join("\n\n"));
+        if !records.is_empty() {
+            content.push('\n');
+        }
+        content
+    }
+
+    // the file save writes to
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // saves to another file, and keeps using that file from now on
+    pub fn save_as(&mut self, path: impl AsRef<Path>) -> Result<(), RosterError> {
+        self.path = path.as_ref().to_path_buf();
+        self.save()
+    }
+
+    pub fn add(&mut self, name: String, age: u8) -> Result<&Student, RosterError> {
+        if self.position(&name).is_some() {
+            return Err(RosterError::DuplicateName(name));
+        }
+        let student = Student::create_student(name, age)?;
+        self.insert(student)
+    }
+
+    // for students that were already validated somewhere else, like a CSV import
+    pub fn insert(&mut self, student: Student) -> Result<&Student, RosterError> {
+        if self.position(&student.name).is_some() {
+            return Err(RosterError::DuplicateName(student.name));
+        }
+        self.students.push(student);
+        Ok(&self.students[self.students.len() - 1])
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Student> {
+        self.position(name).map(|index| &self.students[index])
+    }
+
+    pub fn update(&mut self, name: &str, update: StudentUpdate) -> Result<&Student, RosterError> {
+        let index = self.position(name).ok_or_else(|| self.not_found(name))?;
+
+        let new_name = update.name.unwrap_or_else(|| self.students[index].name.clone());
+        if let Some(other) = self.position(&new_name)
+            && other != index
+        {
+            return Err(RosterError::DuplicateName(new_name));
+        }
+
+        // run the changed student through create_student again so the age rule still holds
+        let age = update.age.unwrap_or(self.students[index].age);
+        let mut student = Student::create_student(new_name, age)?;
+        student.status = self.students[index].status;
+        student.history = std::mem::take(&mut self.students[index].history);
+        self.students[index] = student;
+        Ok(&self.students[index])
+    }
+
+    // runs one of the status methods (activate, suspend, ...) on the named student
+    pub fn change_status(
+        &mut self,
+        name: &str,
+        change: impl FnOnce(&mut Student) -> Result<(), TransitionError>,
+    ) -> Result<&Student, RosterError> {
+        let index = self.position(name).ok_or_else(|| self.not_found(name))?;
+        change(&mut self.students[index])?;
+        Ok(&self.students[index])
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<Student, RosterError> {
+        let index = self.position(name).ok_or_else(|| self.not_found(name))?;
+        Ok(self.students.remove(index))
+    }
+
+    pub fn list(&self) -> &[Student] {
+        &self.students
+    }
+
+    // students whose name is close to `query`, typos and accents included, best match first
+    pub fn search(&self, query: &str) -> Vec<Hit<'_, Student>> {
+        fuzzy::search(&self.students, query, |student| &student.name)
+    }
+
+    // the error for a name we don't have, with a suggestion when it looks like a typo
+    pub fn not_found(&self, name: &str) -> RosterError {
+        RosterError::NotFound {
+            name: name.to_string(),
+            suggestion: fuzzy::best(&self.students, name, |student| &student.name).map(|hit| hit.item.name.clone()),
+        }
+    }
+
+    // names are unique ignoring case, so "John" and "john" are the same student
+    fn position(&self, name: &str) -> Option<usize> {
+        let wanted = name.to_lowercase();
+        self.students
+            .iter()
+            .position(|student| student.name.to_lowercase() == wanted)
+    }
+}
+
+// splits the file on blank lines, keeping the real line numbers for error messages
+fn split_records(content: &str) -> Vec<Vec<(usize, &str)>> {
+    let mut records = Vec::new();
+    let mut current = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            if !current.is_empty() {
+                records.push(current);
+                current = Vec::new();
+            }
+        } else {
+            current.push((index + 1, line));
+        }
+    }
+    if !current.is_empty() {
+        records.push(current);
+    }
+    records
+}
+
+fn print_student(student: &Student) {
+    println!("{} ({}) {}", student.name, student.age, student.status);
+}
+
+fn parse_age(text: &str) -> Result<u8, RosterError> {
+    text.parse()
+        .map_err(|_| RosterError::Invalid(format!("age must be a number from 0 to 255, got {:?}", text)))
+}
+
+const USAGE: &str = "usage: roster <add NAME AGE [--pending] | list | show NAME | history NAME | update NAME [--name NEW] [--age AGE] | activate|suspend|approve|withdraw NAME [REASON...] | remove NAME | import-csv FILE | export-csv FILE [--bom] | import-json FILE | export-json FILE | query [--count|--stats] FILTER... | search NAME>";
+
+// `learning_project roster ...`
+pub fn run(data: &DataDir, args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let words: Vec<&str> = args.iter().map(String::as_str).collect();
+    // commands that only read can share the file, the rest hold it from open to save
+    let writes = !matches!(
+        words.first(),
+        Some(&("list" | "show" | "history" | "query" | "search" | "export-csv" | "export-json"))
+    );
+    let mode = if writes { Mode::Exclusive } else { Mode::Shared };
+    let path = data.file(ROSTER_FILE);
+    let _lock = FileLock::acquire(&path, mode)?;
+    // save would refuse an old file anyway, this says so before anything is printed
+    if writes {
+        schema::check_on_disk(&path)?;
+    }
+    let mut roster = Roster::open(&path)?;
+
+    match words.as_slice() {
+        ["add", name, age] => {
+            let student = roster.add(name.to_string(), parse_age(age)?)?;
+            print_student(student);
+            roster.save()?;
+        }
+        ["add", name, age, "--pending"] => {
+            let student = Student::create_pending(name.to_string(), parse_age(age)?)?;
+            print_student(roster.insert(student)?);
+            roster.save()?;
+        }
+        ["list"] => {
+            for student in roster.list() {
+                print_student(student);
+            }
+        }
+        ["show", name] => match roster.get(name) {
+            Some(student) => print_student(student),
+            None => return Err(roster.not_found(name).into()),
+        },
+        ["update", name, flags @ ..] => {
+            let mut update = StudentUpdate::default();
+            let mut rest = flags.iter();
+            while let Some(flag) = rest.next() {
+                let value = rest
+                    .next()
+                    .ok_or_else(|| RosterError::Invalid(format!("{} needs a value", flag)))?;
+                match *flag {
+                    "--name" => update.name = Some(value.to_string()),
+                    "--age" => update.age = Some(parse_age(value)?),
+                    other => return Err(RosterError::Invalid(format!("unknown flag {}", other)).into()),
+                }
+            }
+            let student = roster.update(name, update)?;
+            print_student(student);
+            roster.save()?;
+        }
+        ["history", name] => {
+            let student = roster.get(name).ok_or_else(|| roster.not_found(name))?;
+            for step in &student.history {
+                println!("{} {} -> {}: {}", step.at, step.from, step.to, step.reason);
+            }
+            println!("now {}", student.status);
+        }
+        [action @ ("activate" | "suspend" | "approve" | "withdraw"), name, reason @ ..] => {
+            let reason = reason.join(" ");
+            let student = roster.change_status(name, |student| match *action {
+                "activate" => student.activate(&reason),
+                "suspend" => student.suspend(&reason),
+                "approve" => student.approve_pending(&reason),
+                _ => student.withdraw(&reason),
+            })?;
+            print_student(student);
+            roster.save()?;
+        }
+        ["remove", name] => {
+            let student = roster.remove(name)?;
+            println!("removed {}", student.name);
+            roster.save()?;
+        }
+        ["import-csv", file] => {
+            let report = csv::import_students(&data_dir::read_to_string(file.as_ref())?)?;
+            let mut added = 0;
+            let mut rejected: Vec<(usize, String)> = report
+                .rejected
+                .iter()
+                .map(|(line, reason)| (*line, reason.to_string()))
+                .collect();
+            for (line, student) in report.accepted {
+                match roster.insert(student) {
+                    Ok(_) => added += 1,
+                    Err(e) => rejected.push((line, e.to_string())),
+                }
+            }
+            rejected.sort_by_key(|(line, _)| *line);
+            for (line, reason) in rejected {
+                println!("line {}: rejected: {}", line, reason);
+            }
+            println!("imported {} students", added);
+            roster.save()?;
+        }
+        ["export-csv", file, flags @ ..] => {
+            let with_bom = match flags {
+                [] => false,
+                ["--bom"] => true,
+                _ => return Err(USAGE.into()),
+            };
+            write_atomic(file, csv::export_students(roster.list(), with_bom).as_bytes())?;
+            println!("exported {} students to {}", roster.list().len(), file);
+        }
+        ["import-json", file] => {
+            // the whole file has to be valid, one bad student rejects the import
+            let students = json::students_from_json(&data_dir::read_to_string(file.as_ref())?)?;
+            let count = students.len();
+            for student in students {
+                roster.insert(student)?;
+            }
+            println!("imported {} students", count);
+            roster.save()?;
+        }
+        ["export-json", file] => {
+            write_atomic(file, json::students_to_json(roster.list()).as_bytes())?;
+            println!("exported {} students to {}", roster.list().len(), file);
+        }
+        ["query", rest @ ..] => {
+            let (mode, terms) = match rest {
+                ["--count", terms @ ..] | ["--stats", terms @ ..] => (Some(rest[0]), terms),
+                terms => (None, terms),
+            };
+            let query = Query::parse(&terms.join(" "))?;
+            match mode {
+                Some("--count") => println!("{}", query.count(roster.list())),
+                Some(_) => println!("{}", query.stats(roster.list())),
+                None => {
+                    for student in query.run(roster.list()) {
+                        print_student(student);
+                    }
+                }
+            }
+        }
+        ["search", query @ ..] if !query.is_empty() => {
+            let hits = roster.search(&query.join(" "));
+            if hits.is_empty() {
+                println!("no student with a name like {:?}", query.join(" "));
+            }
+            for hit in hits {
+                println!("{:.2} {} ({}) {}", hit.score, hit.item.name, hit.item.age, hit.item.status);
+            }
+        }
+        _ => return Err(USAGE.into()),
+    }
+    Ok(())
+}
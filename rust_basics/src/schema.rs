@@ -0,0 +1,205 @@
+// ============================================
+// Student file versions and migrations
+// ============================================
+// student.txt and roster.txt now start with a version line:
+//
+//   Version:2
+//   Name:john
+//   Age:25
+//   Status:active
+//
+// Version 1 is the original layout with no version line and an
+// is_active line ("is_activetrue", the colon was missing) instead of Status.
+// A file without a version line is version 1.
+//
+// When we load an old file we run it through every migration from its version
+// up to CURRENT_VERSION, in memory. `learning_project migrate` writes the
+// upgraded file to disk and keeps a copy of the original next to it.
+// Nothing else writes over an old file, a save tells you to migrate first.
+//
+// Migrations keep one output line per input line, so errors still point at
+// the right line of the file on disk.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::atomic_write::write_atomic;
+use crate::data_dir::{self, DataDir};
+use crate::datetime::Timestamp;
+use crate::lock::{FileLock, Mode};
+use crate::roster::{Roster, ROSTER_FILE};
+use crate::LoadError;
+
+pub const CURRENT_VERSION: u32 = 2;
+const VERSION_PREFIX: &str = "Version:";
+
+// MIGRATIONS[0] upgrades version 1 to 2, MIGRATIONS[1] would upgrade 2 to 3, and so on
+type Migration = fn(&str) -> Result<String, LoadError>;
+const MIGRATIONS: [Migration; 1] = [is_active_to_status];
+
+pub fn header() -> String {
+    format!("{}{}\n", VERSION_PREFIX, CURRENT_VERSION)
+}
+
+// the version a file was written with, and its body upgraded to the current layout
+pub struct Upgraded {
+    pub version: u32,
+    pub body: String,
+}
+
+pub fn upgrade(content: &str) -> Result<Upgraded, LoadError> {
+    let (version, body) = split_version(content)?;
+    let mut body = body;
+    for migration in &MIGRATIONS[(version - 1) as usize..] {
+        body = migration(&body)?;
+    }
+    Ok(Upgraded { version, body })
+}
+
+// save_student and Roster::save call this before they write. Writing over an
+// older file would throw it away without the backup migrate makes
+pub fn check_on_disk(path: &Path) -> io::Result<()> {
+    let content = match data_dir::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    match split_version(&content) {
+        Ok((CURRENT_VERSION, _)) => Ok(()),
+        Ok((version, _)) => Err(io::Error::other(format!(
+            "{} is version {}, run `learning_project migrate` first (it keeps a copy of the old file)",
+            path.display(),
+            version
+        ))),
+        Err(e) => Err(io::Error::other(format!("{}: {}", path.display(), e))),
+    }
+}
+
+// finds the Version line, the body keeps a blank line in its place so line numbers don't move
+fn split_version(content: &str) -> Result<(u32, String), LoadError> {
+    let first = content
+        .lines()
+        .enumerate()
+        .find(|(_, line)| !line.trim().is_empty());
+    let Some((index, line)) = first else {
+        return Ok((CURRENT_VERSION, String::new()));
+    };
+    let Some(value) = line.trim().strip_prefix(VERSION_PREFIX) else {
+        return Ok((1, content.to_string()));
+    };
+
+    let malformed = |reason: String| LoadError::Malformed { line: index + 1, reason };
+    let version: u32 = value
+        .trim()
+        .parse()
+        .map_err(|_| malformed(format!("Version must be a number, got {:?}", value)))?;
+    if version == 0 {
+        return Err(malformed(String::from("there is no version 0")));
+    }
+    if version > CURRENT_VERSION {
+        return Err(malformed(format!(
+            "file is version {} but this program only understands up to version {}",
+            version, CURRENT_VERSION
+        )));
+    }
+
+    let body: Vec<&str> = content
+        .lines()
+        .enumerate()
+        .map(|(i, line)| if i == index { "" } else { line })
+        .collect();
+    Ok((version, body.join("\n")))
+}
+
+// version 1 -> 2: `is_active true/false` becomes `Status:active/inactive`
+fn is_active_to_status(body: &str) -> Result<String, LoadError> {
+    let mut out = Vec::new();
+    for (index, line) in body.lines().enumerate() {
+        let Some(value) = line.trim_end_matches('\r').strip_prefix("is_active") else {
+            out.push(line.to_string());
+            continue;
+        };
+        // save_student used to write "is_activetrue" with no colon, so the colon is optional
+        let value = value.strip_prefix(':').unwrap_or(value);
+        let status = match value.trim() {
+            "true" => "active",
+            "false" => "inactive",
+            other => {
+                return Err(LoadError::Malformed {
+                    line: index + 1,
+                    reason: format!("is_active must be true or false, got {:?}", other),
+                });
+            }
+        };
+        out.push(format!("Status:{}", status));
+    }
+    Ok(out.join("\n"))
+}
+
+// ============================================
+// `learning_project migrate`
+// ============================================
+
+// upgrades one file on disk, returns what happened for printing
+// `convert` loads the old content and returns the new file, it runs before anything is written
+fn migrate_file(
+    path: &Path,
+    convert: impl Fn(&str) -> Result<String, Box<dyn std::error::Error>>,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let _lock = FileLock::acquire(path, Mode::Exclusive)?;
+    let content = match data_dir::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(format!("{}: no file, nothing to do", path.display()));
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let upgraded = upgrade(&content).map_err(|e| format!("{}: {}", path.display(), e))?;
+    if upgraded.version == CURRENT_VERSION {
+        return Ok(format!("{}: already version {}", path.display(), CURRENT_VERSION));
+    }
+
+    // a file that doesn't load is left alone, with or without a backup
+    let new_content = convert(&content).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+    let backup = backup_path(path, upgraded.version);
+    fs::copy(path, &backup).map_err(|e| data_dir::with_path(e, &backup))?;
+    write_atomic(path, new_content.as_bytes())?;
+    Ok(format!(
+        "{}: version {} -> {}, original kept in {}",
+        path.display(),
+        upgraded.version,
+        CURRENT_VERSION,
+        backup.display()
+    ))
+}
+
+// student.txt.v1.bak, or with a timestamp if that backup already exists
+fn backup_path(path: &Path, version: u32) -> PathBuf {
+    let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    let plain = path.with_file_name(format!("{}.v{}.bak", name, version));
+    if !plain.exists() {
+        return plain;
+    }
+    path.with_file_name(format!("{}.v{}.{}.bak", name, version, Timestamp::now().0))
+}
+
+pub fn run(data: &DataDir, args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    if !args.is_empty() {
+        return Err("usage: migrate (upgrades student.txt and roster.txt in the data directory)".into());
+    }
+    let student = migrate_file(&data.file("student.txt"), |content| {
+        let student = crate::parse_student(content)?;
+        Ok(format!("{}{}", header(), crate::format_student(&student)))
+    })?;
+    println!("{}", student);
+
+    let roster_path = data.file(ROSTER_FILE);
+    let roster = migrate_file(&roster_path, |content| {
+        let roster = Roster::parse(&roster_path, content)?;
+        Ok(roster.to_text())
+    })?;
+    println!("{}", roster);
+    Ok(())
+}
@@ -0,0 +1,301 @@
+// ============================================
+// Interactive roster shell
+// ============================================
+// `learning_project shell` gives a prompt where you manage the roster:
+//
+//   > add "Ekom Otu" 22
+//   > list
+//   > save
+//
+// `learning_project shell --script FILE` runs the same commands from a file
+// (one per line, # starts a comment) and stops at the first error, for batch jobs.
+//
+// The shell keeps the roster file locked while it runs (see lock.rs), so a
+// `learning_project roster add` in another terminal can't sneak in between
+// our load and save. That command waits and then names the shell's PID.
+//
+// We don't have a readline library, so the terminal does the line editing.
+// If backspaces or arrow keys still end up in the line (some terminals and
+// pipes send them raw) we apply/strip them before reading the command.
+
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+
+use crate::data_dir::{self, DataDir};
+use crate::lock::{FileLock, Mode};
+use crate::roster::{Roster, StudentUpdate, ROSTER_FILE};
+use crate::Student;
+
+const HELP: &str = "\
+commands:
+  add NAME AGE              add an active student
+  list                      show every student
+  show NAME                 show one student and their history
+  edit NAME [name=NEW] [age=AGE]
+  deactivate NAME [REASON]  suspend an active student
+  delete NAME               remove a student
+  save [FILE]               write the roster (default roster.txt in the data directory)
+  load [FILE]               read a roster, dropping unsaved changes
+  help                      this text
+  quit                      leave (also Ctrl-D)
+names with spaces go in double quotes: add \"Ekom Otu\" 22";
+
+struct Shell {
+    roster: Roster,
+    // exclusive lock on the file the roster saves to
+    lock: FileLock,
+    // where `load` with no file reads from
+    default_file: PathBuf,
+    // true when there are changes that haven't been saved yet
+    dirty: bool,
+}
+
+// what the caller should do after a command
+enum Flow {
+    Continue,
+    Quit,
+}
+
+pub fn run(data: &DataDir, args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let default_file = data.file(ROSTER_FILE);
+    let lock = FileLock::acquire(&default_file, Mode::Exclusive)?;
+    let mut shell = Shell { roster: Roster::open(&default_file)?, lock, default_file, dirty: false };
+    let words: Vec<&str> = args.iter().map(String::as_str).collect();
+    match words.as_slice() {
+        [] => shell.interactive(),
+        ["--script", file] => shell.script(file),
+        _ => Err("usage: shell [--script FILE]".into()),
+    }
+}
+
+impl Shell {
+    fn interactive(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let stdin = io::stdin();
+        // only show the prompt to a person, not when input is piped in
+        let prompt = stdin.is_terminal();
+        if prompt {
+            println!("roster shell, type help for commands");
+        }
+        let mut input = stdin.lock();
+        let mut line = String::new();
+
+        loop {
+            if prompt {
+                print!("> ");
+                io::stdout().flush()?;
+            }
+            line.clear();
+            // read_line gives 0 bytes at end of input, that's Ctrl-D on a terminal
+            if input.read_line(&mut line)? == 0 {
+                if prompt {
+                    println!();
+                }
+                break;
+            }
+            match self.execute(&clean_line(&line)) {
+                Ok(Flow::Continue) => {}
+                Ok(Flow::Quit) => break,
+                // a typo shouldn't end the session, just say what went wrong
+                Err(e) => println!("error: {}", e),
+            }
+        }
+
+        if self.dirty {
+            println!("unsaved changes were not written, use save next time");
+        }
+        Ok(())
+    }
+
+    fn script(&mut self, file: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let content = data_dir::read_to_string(file.as_ref())?;
+        for (index, line) in content.lines().enumerate() {
+            match self.execute(line) {
+                Ok(Flow::Continue) => {}
+                Ok(Flow::Quit) => break,
+                Err(e) => return Err(format!("{} line {}: {}", file, index + 1, e).into()),
+            }
+        }
+        if self.dirty {
+            println!("script ended with unsaved changes");
+        }
+        Ok(())
+    }
+
+    fn execute(&mut self, line: &str) -> Result<Flow, Box<dyn std::error::Error>> {
+        let words = split_words(line)?;
+        let words: Vec<&str> = words.iter().map(String::as_str).collect();
+
+        match words.as_slice() {
+            [] => {}
+            ["help"] => println!("{}", HELP),
+            ["quit"] | ["exit"] => return Ok(Flow::Quit),
+            ["add", name, age] => {
+                let student = self.roster.add(name.to_string(), parse_age(age)?)?;
+                print_student(student);
+                self.dirty = true;
+            }
+            ["list"] => {
+                if self.roster.list().is_empty() {
+                    println!("no students yet");
+                }
+                for student in self.roster.list() {
+                    print_student(student);
+                }
+            }
+            ["show", name] => {
+                let student = self.roster.get(name).ok_or_else(|| self.roster.not_found(name))?;
+                print_student(student);
+                for step in &student.history {
+                    println!("  {} {} -> {}: {}", step.at, step.from, step.to, step.reason);
+                }
+            }
+            ["edit", name, changes @ ..] if !changes.is_empty() => {
+                let mut update = StudentUpdate::default();
+                for change in changes {
+                    match change.split_once('=') {
+                        Some(("name", value)) => update.name = Some(value.to_string()),
+                        Some(("age", value)) => update.age = Some(parse_age(value)?),
+                        _ => return Err(format!("expected name=NEW or age=AGE, got {:?}", change).into()),
+                    }
+                }
+                print_student(self.roster.update(name, update)?);
+                self.dirty = true;
+            }
+            ["deactivate", name, reason @ ..] => {
+                let reason = reason.join(" ");
+                print_student(self.roster.change_status(name, |student| student.suspend(&reason))?);
+                self.dirty = true;
+            }
+            ["delete", name] => {
+                let student = self.roster.remove(name)?;
+                println!("deleted {}", student.name);
+                self.dirty = true;
+            }
+            ["save"] => {
+                self.roster.save()?;
+                println!("saved {} students", self.roster.list().len());
+                self.dirty = false;
+            }
+            ["save", file] => {
+                self.relock(file.as_ref())?;
+                self.roster.save_as(file)?;
+                println!("saved {} students to {}", self.roster.list().len(), file);
+                self.dirty = false;
+            }
+            ["load"] => self.load(&self.default_file.clone())?,
+            ["load", file] => self.load(file.as_ref())?,
+            [command, ..] => return Err(format!("unknown command or wrong arguments for {:?}, try help", command).into()),
+        }
+        Ok(Flow::Continue)
+    }
+
+    fn load(&mut self, file: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        // Roster::open treats a missing file as empty, but here it is almost always a typo
+        if !file.exists() {
+            return Err(format!("{} does not exist", file.display()).into());
+        }
+        self.relock(file)?;
+        self.roster = Roster::open(file)?;
+        self.dirty = false;
+        println!("loaded {} students from {}", self.roster.list().len(), file.display());
+        Ok(())
+    }
+
+    // moves our lock to another file, locking the same file twice would wait on ourselves
+    fn relock(&mut self, file: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        if !same_file(file, self.roster.path()) {
+            self.lock = FileLock::acquire(file, Mode::Exclusive)?;
+        }
+        Ok(())
+    }
+}
+
+// "roster.txt" and "./roster.txt" are the same file, compare the real directories
+fn same_file(a: &Path, b: &Path) -> bool {
+    let real = |path: &Path| {
+        let dir = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        std::fs::canonicalize(dir).ok().map(|dir| dir.join(path.file_name().unwrap_or_default()))
+    };
+    match (real(a), real(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+fn print_student(student: &Student) {
+    println!("{} ({}) {}", student.name, student.age, student.status);
+}
+
+fn parse_age(text: &str) -> Result<u8, String> {
+    text.parse().map_err(|_| format!("age must be a number from 0 to 255, got {:?}", text))
+}
+
+// applies backspace/delete and drops escape sequences (arrow keys send ESC [ A and so on)
+fn clean_line(raw: &str) -> String {
+    let mut out = String::new();
+    let mut chars = raw.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\u{08}' | '\u{7f}' => {
+                out.pop();
+            }
+            '\u{1b}' => {
+                // ESC [ ... ends with a letter or ~, ESC O x is one more character
+                match chars.next() {
+                    Some('[') => {
+                        for c in chars.by_ref() {
+                            if c.is_ascii_alphabetic() || c == '~' {
+                                break;
+                            }
+                        }
+                    }
+                    Some('O') => {
+                        chars.next();
+                    }
+                    _ => {}
+                }
+            }
+            c if c.is_control() && c != '\t' => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+// splits on spaces but keeps "quoted words" together, # starts a comment
+fn split_words(line: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quoted = false;
+
+    for c in line.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                in_word = true;
+            }
+            '#' if !quoted && !in_word => break,
+            c if c.is_whitespace() && !quoted => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            c => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if quoted {
+        return Err(String::from("a quote is never closed"));
+    }
+    if in_word {
+        words.push(word);
+    }
+    Ok(words)
+}
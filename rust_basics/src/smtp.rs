@@ -0,0 +1,287 @@
+// ============================================
+// Sending mail over SMTP (RFC 5321)
+// ============================================
+// SMTP is a conversation in lines of text. We send a command, the server
+// answers with a 3 digit code and some text:
+//
+//   S: 220 mail.example.com ready
+//   C: EHLO localhost
+//   S: 250-mail.example.com
+//   S: 250 AUTH PLAIN            ("250-" means more lines follow)
+//   C: MAIL FROM:<ekom@example.com>
+//   S: 250 ok
+//   C: RCPT TO:<ann@example.com>  (once per recipient, each can be refused)
+//   S: 250 ok
+//   C: DATA
+//   S: 354 go ahead
+//   C: ...the message..., then a line with just "."
+//   S: 250 queued
+//   C: QUIT
+//
+// 2xx and 3xx codes mean go on, 4xx means try later, 5xx means no.
+// A server can refuse some recipients and still take the message for the rest,
+// so send() reports every recipient on its own.
+
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+use crate::address::EmailAddress;
+use crate::base64;
+use crate::message::{MessageBuilder, MessageError};
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+// where to send, and who to log in as (if the server wants a login)
+pub struct SmtpConfig {
+    pub server: String,
+    pub helo_name: String,
+    pub login: Option<(String, String)>,
+}
+
+impl SmtpConfig {
+    // server is host:port, e.g. "localhost:25"
+    pub fn new(server: &str) -> SmtpConfig {
+        SmtpConfig { server: server.to_string(), helo_name: String::from("localhost"), login: None }
+    }
+
+    // log in with AUTH PLAIN before sending
+    pub fn login(mut self, user: &str, password: &str) -> SmtpConfig {
+        self.login = Some((user.to_string(), password.to_string()));
+        self
+    }
+}
+
+// one answer from the server, it can be several lines long
+#[derive(Debug, Clone)]
+pub struct Reply {
+    pub code: u16,
+    pub lines: Vec<String>,
+}
+
+impl Reply {
+    fn is_ok(&self) -> bool {
+        (200..400).contains(&self.code)
+    }
+}
+
+impl fmt::Display for Reply {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.code, self.lines.join(" "))
+    }
+}
+
+// a recipient the server refused
+#[derive(Debug)]
+pub struct RecipientError {
+    pub address: EmailAddress,
+    pub reply: Reply,
+}
+
+impl fmt::Display for RecipientError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.address, self.reply)
+    }
+}
+
+// what happened to each recipient of a message that was sent
+#[derive(Debug)]
+pub struct Delivery {
+    pub accepted: Vec<EmailAddress>,
+    pub rejected: Vec<RecipientError>,
+}
+
+#[derive(Debug)]
+pub enum SmtpError {
+    Io(io::Error),
+    // the server said something that isn't an SMTP reply
+    Malformed(String),
+    // the server answered a command with an error code
+    Refused { command: &'static str, reply: Reply },
+    // we have a login but the server doesn't offer AUTH PLAIN
+    AuthUnsupported,
+    // every recipient was refused, so nothing was sent
+    NoRecipients(Vec<RecipientError>),
+    // the message couldn't be put together, nothing was sent
+    Message(MessageError),
+}
+
+impl fmt::Display for SmtpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SmtpError::Io(e) => write!(f, "connection to the mail server failed: {}", e),
+            SmtpError::Malformed(line) => write!(f, "mail server sent something that isn't SMTP: {:?}", line),
+            SmtpError::Refused { command, reply } => write!(f, "mail server refused {}: {}", command, reply),
+            SmtpError::AuthUnsupported => write!(f, "mail server doesn't support AUTH PLAIN, can't log in"),
+            SmtpError::NoRecipients(rejected) => {
+                write!(f, "every recipient was refused")?;
+                for recipient in rejected {
+                    write!(f, "; {}", recipient)?;
+                }
+                Ok(())
+            }
+            SmtpError::Message(e) => write!(f, "can't build the message: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SmtpError {}
+
+impl From<io::Error> for SmtpError {
+    fn from(e: io::Error) -> SmtpError {
+        SmtpError::Io(e)
+    }
+}
+
+impl From<MessageError> for SmtpError {
+    fn from(e: MessageError) -> SmtpError {
+        SmtpError::Message(e)
+    }
+}
+
+// builds the message and sends it to everyone on it, Bcc included
+pub fn send_message(config: &SmtpConfig, message: &MessageBuilder) -> Result<Delivery, SmtpError> {
+    let bytes = message.build()?;
+    send(config, message.sender(), &message.recipients(), &bytes)
+}
+
+// sends one message (headers and body, already formatted) to all of `to`
+pub fn send(config: &SmtpConfig, from: &EmailAddress, to: &[EmailAddress], message: &[u8]) -> Result<Delivery, SmtpError> {
+    let stream = TcpStream::connect(&config.server)?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    let mut session = Session { reader: BufReader::new(stream.try_clone()?), writer: stream };
+
+    session.expect("the connection", None)?;
+    // EHLO is the modern greeting and lists what the server supports,
+    // very old servers only know HELO
+    let features = match session.expect("EHLO", Some(&format!("EHLO {}", config.helo_name))) {
+        Ok(reply) => reply.lines,
+        Err(SmtpError::Refused { .. }) => {
+            session.expect("HELO", Some(&format!("HELO {}", config.helo_name)))?;
+            Vec::new()
+        }
+        Err(e) => return Err(e),
+    };
+
+    if let Some((user, password)) = &config.login {
+        if !offers_auth_plain(&features) {
+            return Err(SmtpError::AuthUnsupported);
+        }
+        // AUTH PLAIN is "\0user\0password" in base64, only use it over a connection you trust
+        let token = base64::encode(format!("\0{}\0{}", user, password).as_bytes());
+        session.expect("AUTH PLAIN", Some(&format!("AUTH PLAIN {}", token)))?;
+    }
+
+    session.expect("MAIL FROM", Some(&format!("MAIL FROM:<{}>", from.addr_spec())))?;
+    let mut delivery = Delivery { accepted: Vec::new(), rejected: Vec::new() };
+    for address in to {
+        let reply = session.command(&format!("RCPT TO:<{}>", address.addr_spec()))?;
+        if reply.is_ok() {
+            delivery.accepted.push(address.clone());
+        } else {
+            delivery.rejected.push(RecipientError { address: address.clone(), reply });
+        }
+    }
+    if delivery.accepted.is_empty() {
+        session.quit();
+        return Err(SmtpError::NoRecipients(delivery.rejected));
+    }
+
+    session.expect("DATA", Some("DATA"))?;
+    session.writer.write_all(&dot_stuff(message))?;
+    session.expect("the message", None)?;
+    session.quit();
+    Ok(delivery)
+}
+
+struct Session {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+impl Session {
+    // sends a line and reads the reply, whatever the code is
+    fn command(&mut self, line: &str) -> Result<Reply, SmtpError> {
+        self.writer.write_all(format!("{}\r\n", line).as_bytes())?;
+        self.read_reply()
+    }
+
+    // like command, but an error code is an error (None only reads a reply)
+    fn expect(&mut self, what: &'static str, line: Option<&str>) -> Result<Reply, SmtpError> {
+        let reply = match line {
+            Some(line) => self.command(line)?,
+            None => self.read_reply()?,
+        };
+        if !reply.is_ok() {
+            return Err(SmtpError::Refused { command: what, reply });
+        }
+        Ok(reply)
+    }
+
+    // we're done either way, so a server that hangs up without answering QUIT is fine
+    fn quit(&mut self) {
+        let _ = self.command("QUIT");
+    }
+
+    fn read_reply(&mut self) -> Result<Reply, SmtpError> {
+        let mut lines = Vec::new();
+        loop {
+            let mut line = String::new();
+            if self.reader.read_line(&mut line)? == 0 {
+                return Err(SmtpError::Io(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "server closed the connection",
+                )));
+            }
+            let line = line.trim_end_matches(['\r', '\n']);
+            // "250-more" or "250 last" or just "250"
+            let code = line.get(..3).and_then(|code| code.parse::<u16>().ok());
+            let (Some(code), separator) = (code, line.as_bytes().get(3)) else {
+                return Err(SmtpError::Malformed(line.to_string()));
+            };
+            lines.push(line.get(4..).unwrap_or("").to_string());
+            match separator {
+                Some(b'-') => continue,
+                Some(b' ') | None => return Ok(Reply { code, lines }),
+                Some(_) => return Err(SmtpError::Malformed(line.to_string())),
+            }
+        }
+    }
+}
+
+// the EHLO reply has one feature per line, e.g. "AUTH LOGIN PLAIN"
+// (some old servers write "AUTH=LOGIN PLAIN")
+fn offers_auth_plain(features: &[String]) -> bool {
+    features.iter().any(|line| {
+        let line = line.to_uppercase();
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("AUTH") => words.any(|word| word == "PLAIN"),
+            Some(first) if first.starts_with("AUTH=") => {
+                first == "AUTH=PLAIN" || words.any(|word| word == "PLAIN")
+            }
+            _ => false,
+        }
+    })
+}
+
+// lines end in \r\n on the wire, and a line starting with "." gets a second one,
+// so a "." in the message can't end it early. Then the final "." line.
+fn dot_stuff(message: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(message.len() + 16);
+    let message = message.strip_suffix(b"\n").unwrap_or(message);
+    if !message.is_empty() {
+        for line in message.split(|&b| b == b'\n') {
+            let line = line.strip_suffix(b"\r").unwrap_or(line);
+            if line.starts_with(b".") {
+                out.push(b'.');
+            }
+            out.extend_from_slice(line);
+            out.extend_from_slice(b"\r\n");
+        }
+    }
+    out.extend_from_slice(b".\r\n");
+    out
+}
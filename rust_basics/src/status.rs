@@ -0,0 +1,124 @@
+// ============================================
+// Student status lifecycle
+// ============================================
+// This is the only Status enum, myown.rs brings this file in instead of having
+// its own copy, so it only uses std and datetime.rs. Withdrawn is for students who left.
+// A student moves between them only through these methods:
+//
+//   Pending  --approve_pending--> Active
+//   Active   --suspend----------> Inactive
+//   Inactive --activate---------> Active
+//   anything but Withdrawn --withdraw--> Withdrawn (there is no way back)
+//
+// Every move is written to the student's history with a time and a reason.
+
+use std::fmt;
+
+use crate::datetime::Timestamp;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Status {
+    Active,
+    Inactive,
+    Pending,
+    Withdrawn,
+}
+
+impl Status {
+    pub fn name(self) -> &'static str {
+        match self {
+            Status::Active => "active",
+            Status::Inactive => "inactive",
+            Status::Pending => "pending",
+            Status::Withdrawn => "withdrawn",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Status> {
+        match name {
+            "active" => Some(Status::Active),
+            "inactive" => Some(Status::Inactive),
+            "pending" => Some(Status::Pending),
+            "withdrawn" => Some(Status::Withdrawn),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+// one line of a student's history
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transition {
+    pub at: Timestamp,
+    pub from: Status,
+    pub to: Status,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TransitionError {
+    // e.g. approving a student that isn't pending
+    Illegal { action: &'static str, from: Status },
+    // reasons are stored one per line, so they can't contain line breaks
+    BadReason,
+}
+
+impl fmt::Display for TransitionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TransitionError::Illegal { action, from } => {
+                write!(f, "cannot {} a student who is {}", action, from)
+            }
+            TransitionError::BadReason => write!(f, "reason must not contain control characters"),
+        }
+    }
+}
+
+impl std::error::Error for TransitionError {}
+
+// each move checks where the status is now and gives back the Transition for the
+// history. Student (learning_project.rs) applies it, so the rules stay in this file
+impl Status {
+    pub fn activate(self, reason: &str) -> Result<Transition, TransitionError> {
+        self.step("activate", &[Status::Inactive], Status::Active, reason)
+    }
+
+    pub fn suspend(self, reason: &str) -> Result<Transition, TransitionError> {
+        self.step("suspend", &[Status::Active], Status::Inactive, reason)
+    }
+
+    pub fn approve_pending(self, reason: &str) -> Result<Transition, TransitionError> {
+        self.step("approve", &[Status::Pending], Status::Active, reason)
+    }
+
+    pub fn withdraw(self, reason: &str) -> Result<Transition, TransitionError> {
+        let allowed = [Status::Active, Status::Inactive, Status::Pending];
+        self.step("withdraw", &allowed, Status::Withdrawn, reason)
+    }
+
+    fn step(
+        self,
+        action: &'static str,
+        allowed: &[Status],
+        to: Status,
+        reason: &str,
+    ) -> Result<Transition, TransitionError> {
+        if !allowed.contains(&self) {
+            return Err(TransitionError::Illegal { action, from: self });
+        }
+        if reason.contains(char::is_control) {
+            return Err(TransitionError::BadReason);
+        }
+        Ok(Transition {
+            at: Timestamp::now(),
+            from: self,
+            to,
+            reason: reason.trim().to_string(),
+        })
+    }
+}
@@ -0,0 +1,368 @@
+// ============================================
+// Subscriptions: who wants to hear about what
+// ============================================
+// notify sends everything to the sinks in LEARNING_PROJECT_NOTIFY. Publishing
+// an item instead sends it only to the recipients that subscribed to it.
+// A recipient is a sink spec (mbox:/home/ann/news.mbox, webhook:http://...)
+// and a topic is one of
+//
+//   author:The Times         articles by that author (NewsArticle.author)
+//   user:ekom                tweets by @ekom (Tweet.username)
+//   from:ekom@example.com    emails from that address (Email.from)
+//   keyword:rust             anything with that word or phrase in its summary
+//
+// Names and keywords don't care about upper/lower case, keywords only match
+// whole words ("rust" matches "#rust" but not "trust").
+//
+// Every subscription gets a token. It goes at the end of each notification,
+// so the recipient can unsubscribe with it without knowing anything else:
+//   learning_project subscription remove 4f0c...
+//
+// Subscriptions are kept in subscriptions.jsonl in the data directory, one
+// JSON object per line.
+
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::fs;
+use std::hash::{BuildHasher, Hasher};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::address::EmailAddress;
+use crate::atomic_write::write_atomic;
+use crate::data_dir::{self, with_path};
+use crate::datetime::Timestamp;
+use crate::item::{self, Item};
+use crate::json::{self, Json};
+use crate::lesson_7_traits::Summary;
+use crate::lock::{FileLock, Mode};
+use crate::notifier::check_spec;
+use crate::outbox::{self, OutboxError, Report};
+use crate::template;
+
+pub const SUBSCRIPTIONS_FILE: &str = "subscriptions.jsonl";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Topic {
+    Author(String),
+    Username(String),
+    // the address as addr_spec() writes it, so it compares with Email's source()
+    Sender(String),
+    Keyword(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct Subscription {
+    pub token: String,
+    pub recipient: String,
+    pub topic: Topic,
+    pub since: Timestamp,
+}
+
+#[derive(Debug)]
+pub enum SubscriptionError {
+    Io(io::Error),
+    Malformed { path: PathBuf, line: usize, message: String },
+    Topic(String),
+    Recipient { spec: String, message: String },
+    Outbox(OutboxError),
+}
+
+impl fmt::Display for SubscriptionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SubscriptionError::Io(e) => write!(f, "{}", e),
+            SubscriptionError::Malformed { path, line, message } => write!(f, "{} line {}: {}", path.display(), line, message),
+            SubscriptionError::Topic(message) => write!(f, "bad topic: {}", message),
+            SubscriptionError::Recipient { spec, message } => write!(f, "bad recipient {:?}: {}", spec, message),
+            SubscriptionError::Outbox(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for SubscriptionError {}
+
+impl From<io::Error> for SubscriptionError {
+    fn from(e: io::Error) -> Self {
+        SubscriptionError::Io(e)
+    }
+}
+
+impl From<OutboxError> for SubscriptionError {
+    fn from(e: OutboxError) -> Self {
+        SubscriptionError::Outbox(e)
+    }
+}
+
+impl Topic {
+    // "author:The Times", "user:ekom" (or "user:@ekom"), "from:ekom@example.com", "keyword:rust"
+    pub fn parse(text: &str) -> Result<Topic, SubscriptionError> {
+        let bad = |message: String| SubscriptionError::Topic(message);
+        let Some((kind, value)) = text.split_once(':') else {
+            return Err(bad(format!("{:?} should look like author:NAME, user:NAME, from:ADDRESS or keyword:WORDS", text)));
+        };
+        let value = value.trim();
+        if value.is_empty() {
+            return Err(bad(format!("{}: needs something after the colon", kind)));
+        }
+        match kind {
+            "author" => Ok(Topic::Author(value.to_string())),
+            "user" => {
+                let name = value.strip_prefix('@').unwrap_or(value);
+                if name.is_empty() || name.contains(char::is_whitespace) {
+                    return Err(bad(format!("user:{} must be one word", name)));
+                }
+                Ok(Topic::Username(name.to_string()))
+            }
+            "from" => {
+                let address = EmailAddress::parse(value).map_err(|e| bad(format!("from:{}: {}", value, e)))?;
+                Ok(Topic::Sender(address.addr_spec()))
+            }
+            "keyword" => {
+                if words(value).is_empty() {
+                    return Err(bad(format!("keyword:{} has no words in it", value)));
+                }
+                Ok(Topic::Keyword(value.to_string()))
+            }
+            other => Err(bad(format!("unknown kind {:?} (try: author, user, from, keyword)", other))),
+        }
+    }
+
+    // does the item belong to this topic? Tagged items (digest.rs) pass kind
+    // and source through, so they match like the item inside
+    pub fn matches<S: Summary + ?Sized>(&self, item: &S) -> bool {
+        match self {
+            Topic::Author(name) => item.kind() == "article" && item.source().to_lowercase() == name.to_lowercase(),
+            Topic::Username(name) => item.kind() == "tweet" && item.source().eq_ignore_ascii_case(&format!("@{}", name)),
+            Topic::Sender(address) => item.kind() == "email" && item.source().eq_ignore_ascii_case(address),
+            Topic::Keyword(phrase) => {
+                let phrase = words(phrase);
+                words(&item.summarize()).windows(phrase.len()).any(|window| window == phrase.as_slice())
+            }
+        }
+    }
+}
+
+impl fmt::Display for Topic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Topic::Author(name) => write!(f, "author:{}", name),
+            Topic::Username(name) => write!(f, "user:{}", name),
+            Topic::Sender(address) => write!(f, "from:{}", address),
+            Topic::Keyword(phrase) => write!(f, "keyword:{}", phrase),
+        }
+    }
+}
+
+// one recipient of an item and every one of their subscriptions it matched
+#[derive(Debug, Clone)]
+pub struct Route {
+    pub recipient: String,
+    pub subscriptions: Vec<Subscription>,
+}
+
+pub struct Subscriptions {
+    path: PathBuf,
+}
+
+impl Subscriptions {
+    pub fn open(path: &Path) -> Subscriptions {
+        Subscriptions { path: path.to_path_buf() }
+    }
+
+    // the file in the data directory
+    pub fn configured() -> Result<Subscriptions, SubscriptionError> {
+        Ok(Subscriptions::open(&outbox::data_file(SUBSCRIPTIONS_FILE)?))
+    }
+
+    // subscribing twice to the same thing gives back the first subscription
+    pub fn subscribe(&self, recipient: &str, topic: Topic) -> Result<Subscription, SubscriptionError> {
+        let recipient = recipient.trim();
+        check_spec(recipient).map_err(|message| SubscriptionError::Recipient { spec: recipient.to_string(), message })?;
+        let _lock = FileLock::acquire(&self.path, Mode::Exclusive)?;
+        let mut subscriptions = self.read()?;
+        if let Some(existing) = subscriptions.iter().find(|s| s.recipient == recipient && s.topic == topic) {
+            return Ok(existing.clone());
+        }
+        let subscription = Subscription { token: new_token(), recipient: recipient.to_string(), topic, since: Timestamp::now() };
+        subscriptions.push(subscription.clone());
+        self.write(&subscriptions)?;
+        Ok(subscription)
+    }
+
+    // the subscription that was removed, None if no subscription has that token
+    pub fn unsubscribe(&self, token: &str) -> Result<Option<Subscription>, SubscriptionError> {
+        let _lock = FileLock::acquire(&self.path, Mode::Exclusive)?;
+        let mut subscriptions = self.read()?;
+        let Some(index) = subscriptions.iter().position(|s| s.token == token.trim()) else {
+            return Ok(None);
+        };
+        let removed = subscriptions.remove(index);
+        self.write(&subscriptions)?;
+        Ok(Some(removed))
+    }
+
+    pub fn list(&self) -> Result<Vec<Subscription>, SubscriptionError> {
+        let _lock = FileLock::acquire(&self.path, Mode::Shared)?;
+        self.read()
+    }
+
+    // who gets the item: each recipient once, with all of their subscriptions
+    // that matched it (each one's topic and token goes in the notification)
+    pub fn route<S: Summary + ?Sized>(&self, item: &S) -> Result<Vec<Route>, SubscriptionError> {
+        let mut routes: Vec<Route> = Vec::new();
+        for subscription in self.list()?.into_iter().filter(|s| s.topic.matches(item)) {
+            match routes.iter_mut().find(|route| route.recipient == subscription.recipient) {
+                Some(route) => route.subscriptions.push(subscription),
+                None => routes.push(Route { recipient: subscription.recipient.clone(), subscriptions: vec![subscription] }),
+            }
+        }
+        Ok(routes)
+    }
+
+    fn read(&self) -> Result<Vec<Subscription>, SubscriptionError> {
+        let content = match fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(with_path(e, &self.path).into()),
+        };
+        let mut subscriptions = Vec::new();
+        for (index, line) in content.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let malformed = |message: String| SubscriptionError::Malformed { path: self.path.clone(), line: index + 1, message };
+            let value = Json::parse(line).map_err(|e| malformed(e.to_string()))?;
+            subscriptions.push(from_json(&value).map_err(malformed)?);
+        }
+        Ok(subscriptions)
+    }
+
+    fn write(&self, subscriptions: &[Subscription]) -> io::Result<()> {
+        let lines: String = subscriptions.iter().map(|s| format!("{}\n", to_json(s))).collect();
+        write_atomic(&self.path, lines.as_bytes())
+    }
+}
+
+// what Item::publish does (item.rs): the item goes through the outbox to the
+// recipients route() found for it, one notification each, written by the
+// notification template with an unsubscribe line for every subscription that
+// matched. No routes means nothing is sent (an empty report).
+pub fn publish<S: Item + ?Sized>(item: &S, routes: &[Route]) -> Result<Report, SubscriptionError> {
+    let title = format!("new {} from {}", item.kind(), item.source());
+    let mut report = Report::default();
+    for route in routes {
+        let mut context = template::context(item);
+        if let Json::Object(fields) = &mut context {
+            let topics = route
+                .subscriptions
+                .iter()
+                .map(|subscription| {
+                    Json::Object(vec![
+                        (String::from("topic"), Json::String(subscription.topic.to_string())),
+                        (String::from("token"), Json::String(subscription.token.clone())),
+                    ])
+                })
+                .collect();
+            fields.push((String::from("subscriptions"), Json::Array(topics)));
+        }
+        let note = item::notification(&title, context).map_err(OutboxError::from)?;
+        report.add(outbox::notify_to(note, std::slice::from_ref(&route.recipient))?);
+    }
+    Ok(report)
+}
+
+// "keyword:Rust traits!" -> ["rust", "traits"]
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric()).filter(|word| !word.is_empty()).map(str::to_lowercase).collect()
+}
+
+// 32 hex digits nobody can guess. RandomState is seeded from the operating
+// system's random numbers, the time and a counter make two tokens differ
+// even from the same seed
+fn new_token() -> String {
+    static TOKENS: AtomicU64 = AtomicU64::new(0);
+    let count = TOKENS.fetch_add(1, Ordering::Relaxed);
+    let half = |salt: u64| {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u64(salt);
+        hasher.write_u64(count);
+        hasher.write_i64(Timestamp::now().0);
+        hasher.write_u32(std::process::id());
+        hasher.finish()
+    };
+    format!("{:016x}{:016x}", half(1), half(2))
+}
+
+fn to_json(subscription: &Subscription) -> Json {
+    let text = |value: &str| Json::String(value.to_string());
+    Json::Object(vec![
+        (String::from("token"), text(&subscription.token)),
+        (String::from("recipient"), text(&subscription.recipient)),
+        (String::from("topic"), text(&subscription.topic.to_string())),
+        (String::from("since"), text(&subscription.since.to_string())),
+    ])
+}
+
+fn from_json(value: &Json) -> Result<Subscription, String> {
+    let text = |field: &str| match value.get(field) {
+        Some(Json::String(s)) => Ok(s.clone()),
+        _ => Err(format!("{:?} should be a string", field)),
+    };
+    Ok(Subscription {
+        token: text("token")?,
+        recipient: text("recipient")?,
+        topic: Topic::parse(&text("topic")?).map_err(|e| e.to_string())?,
+        since: Timestamp::parse_rfc3339(&text("since")?).ok_or("\"since\" is not a time")?,
+    })
+}
+
+// `learning_project subscription add RECIPIENT TOPIC | remove TOKEN | list | publish FILE`
+pub fn run(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let usage = "usage: subscription <add RECIPIENT TOPIC | remove TOKEN | list | publish FILE>";
+    let subscriptions = Subscriptions::configured()?;
+    let words: Vec<&str> = args.iter().map(String::as_str).collect();
+    match words.as_slice() {
+        ["add", recipient, topic @ ..] if !topic.is_empty() => {
+            // "author:The Times" may come in as two arguments without quotes
+            let subscription = subscriptions.subscribe(recipient, Topic::parse(&topic.join(" "))?)?;
+            println!("{} subscribed to {}", subscription.recipient, subscription.topic);
+            println!("unsubscribe token: {}", subscription.token);
+        }
+        ["remove", token] => match subscriptions.unsubscribe(token)? {
+            Some(removed) => println!("{} unsubscribed from {}", removed.recipient, removed.topic),
+            None => return Err(format!("no subscription has the token {:?}", token).into()),
+        },
+        ["list"] => {
+            let all = subscriptions.list()?;
+            if all.is_empty() {
+                println!("(none)");
+            }
+            for subscription in all {
+                println!("{}  {}  {}  since {}", subscription.token, subscription.recipient, subscription.topic, subscription.since);
+            }
+        }
+        // the items of a digest file (see run_digest), each to its own subscribers
+        ["publish", file] => {
+            let items = json::digest_items_from_json(&data_dir::read_to_string(file.as_ref())?)?;
+            let mut report = Report::default();
+            for item in &items {
+                // routed once, the same list is printed and sent to
+                let routes = subscriptions.route(item.as_ref())?;
+                let recipients: Vec<&str> = routes.iter().map(|route| route.recipient.as_str()).collect();
+                println!("{} -> {}", item.summarize(), if recipients.is_empty() { String::from("nobody") } else { recipients.join(", ") });
+                report.add(item.publish(&routes)?);
+            }
+            if let Some(held_back) = report.held_back() {
+                eprintln!("{}", held_back);
+            }
+            if !report.failed.is_empty() {
+                let failures: Vec<String> = report.failed.iter().map(|failure| failure.to_string()).collect();
+                return Err(failures.join("; ").into());
+            }
+        }
+        _ => return Err(usage.into()),
+    }
+    Ok(())
+}
@@ -0,0 +1,433 @@
+// ============================================
+// Task list on top of task.txt
+// ============================================
+// load_task only prints task.txt. Here every line is a Task we can manage:
+//
+//   [ ] 1 (high) Write the report due:2026-11-01 #work #urgent
+//   [~] 2 (low) Read the Rust book #learning
+//   [x] 3 (medium) Install rustup
+//
+// [ ] is todo, [~] is doing, [x] is done. The priority, due date and tags are
+// optional, due: and #tags go at the end of the line. A plain line without
+// the [ ] marker (like an old hand written task.txt) becomes a new todo task.
+// A title word that looks like a tag or a due date is saved with a backslash
+// in front, so "Fix issue #42" comes back as a title and not as a tag:
+//
+//   [ ] 4 (medium) Fix issue \#42 #work
+
+use std::fmt;
+use std::io;
+
+use crate::atomic_write::write_atomic;
+use crate::data_dir::{self, DataDir};
+use crate::datetime;
+use crate::lock::{FileLock, Mode};
+
+pub const TASK_FILE: &str = "task.txt";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TaskStatus {
+    Todo,
+    Doing,
+    Done,
+}
+
+impl TaskStatus {
+    fn marker(self) -> &'static str {
+        match self {
+            TaskStatus::Todo => "[ ]",
+            TaskStatus::Doing => "[~]",
+            TaskStatus::Done => "[x]",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<TaskStatus> {
+        match name {
+            "todo" => Some(TaskStatus::Todo),
+            "doing" => Some(TaskStatus::Doing),
+            "done" => Some(TaskStatus::Done),
+            _ => None,
+        }
+    }
+}
+
+// Low < Medium < High, so sorting by priority puts High last (we reverse it when listing)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    Low,
+    Medium,
+    High,
+}
+
+impl Priority {
+    pub fn name(self) -> &'static str {
+        match self {
+            Priority::Low => "low",
+            Priority::Medium => "medium",
+            Priority::High => "high",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Priority> {
+        match name {
+            "low" => Some(Priority::Low),
+            "medium" => Some(Priority::Medium),
+            "high" => Some(Priority::High),
+            _ => None,
+        }
+    }
+}
+
+// a calendar day, fields in this order so the derived Ord sorts by date
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Date {
+    year: i64,
+    month: u32,
+    day: u32,
+}
+
+impl Date {
+    fn parse(text: &str) -> Option<Date> {
+        let (year, month, day) = datetime::parse_date(text)?;
+        Some(Date { year, month, day })
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Task {
+    pub id: u32,
+    pub title: String,
+    pub status: TaskStatus,
+    pub priority: Priority,
+    pub due: Option<Date>,
+    pub tags: Vec<String>,
+}
+
+// words that would be read back as a #tag or a due: date, after any backslashes
+fn looks_like_metadata(word: &str) -> bool {
+    let bare = word.trim_start_matches('\\');
+    bare.starts_with('#') || bare.starts_with("due:")
+}
+
+// every such word gets one more backslash, even one that already has some,
+// so reading can always take exactly one off again
+fn escape_title(title: &str) -> String {
+    let words: Vec<String> = title
+        .split(' ')
+        .map(|word| if looks_like_metadata(word) { format!("\\{}", word) } else { word.to_string() })
+        .collect();
+    words.join(" ")
+}
+
+fn unescape_word(word: &str) -> &str {
+    match word.strip_prefix('\\') {
+        Some(rest) if looks_like_metadata(rest) => rest,
+        _ => word,
+    }
+}
+
+impl fmt::Display for Task {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {} ({}) {}", self.status.marker(), self.id, self.priority.name(), escape_title(&self.title))?;
+        if let Some(due) = self.due {
+            write!(f, " due:{}", due)?;
+        }
+        for tag in &self.tags {
+            write!(f, " #{}", tag)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum TaskError {
+    Io(io::Error),
+    Parse { line: usize, reason: String },
+    NotFound(u32),
+    Invalid(String),
+}
+
+impl fmt::Display for TaskError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TaskError::Io(e) => write!(f, "task file error: {}", e),
+            TaskError::Parse { line, reason } => write!(f, "{} line {}: {}", TASK_FILE, line, reason),
+            TaskError::NotFound(id) => write!(f, "no task with id {}", id),
+            TaskError::Invalid(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+impl std::error::Error for TaskError {}
+
+impl From<io::Error> for TaskError {
+    fn from(e: io::Error) -> TaskError {
+        TaskError::Io(e)
+    }
+}
+
+pub fn parse_tasks(content: &str) -> Result<Vec<Task>, TaskError> {
+    let mut tasks: Vec<Task> = Vec::new();
+    // plain lines get their ids after all the numbered ones are known
+    let mut plain: Vec<String> = Vec::new();
+
+    for (index, raw) in content.lines().enumerate() {
+        let line = index + 1;
+        let text = raw.trim();
+        if text.is_empty() {
+            continue;
+        }
+        let error = |reason: String| TaskError::Parse { line, reason };
+
+        let status = match text.get(..3) {
+            Some("[ ]") => TaskStatus::Todo,
+            Some("[~]") => TaskStatus::Doing,
+            Some("[x]") | Some("[X]") => TaskStatus::Done,
+            _ => {
+                plain.push(text.to_string());
+                continue;
+            }
+        };
+        let rest = text[3..].trim_start();
+        let (id, rest) = rest.split_once(' ').unwrap_or((rest, ""));
+        let id: u32 = id.parse().map_err(|_| error(format!("expected a task id, got {:?}", id)))?;
+        if tasks.iter().any(|task| task.id == id) {
+            return Err(error(format!("task id {} is used twice", id)));
+        }
+
+        let mut rest = rest.trim_start();
+        let mut priority = Priority::Medium;
+        if let Some(after) = rest.strip_prefix('(') {
+            let (name, after) = after
+                .split_once(')')
+                .ok_or_else(|| error(String::from("priority is missing its ')'")))?;
+            priority = Priority::from_name(name)
+                .ok_or_else(|| error(format!("priority must be low, medium or high, got {:?}", name)))?;
+            rest = after.trim_start();
+        }
+
+        // due: and #tags are read from the end of the line backwards
+        let mut words: Vec<&str> = rest.split_whitespace().collect();
+        let mut due = None;
+        let mut tags = Vec::new();
+        while let Some(word) = words.last() {
+            if let Some(tag) = word.strip_prefix('#').filter(|tag| !tag.is_empty()) {
+                tags.insert(0, tag.to_string());
+            } else if let Some(date) = word.strip_prefix("due:") {
+                if due.is_some() {
+                    return Err(error(String::from("due date appears more than once")));
+                }
+                due = Some(Date::parse(date).ok_or_else(|| error(format!("due date must be YYYY-MM-DD, got {:?}", date)))?);
+            } else {
+                break;
+            }
+            words.pop();
+        }
+        if words.is_empty() {
+            return Err(error(String::from("task has no title")));
+        }
+
+        let title: Vec<&str> = words.into_iter().map(unescape_word).collect();
+        tasks.push(Task { id, title: title.join(" "), status, priority, due, tags });
+    }
+
+    for title in plain {
+        let id = next_id(&tasks);
+        tasks.push(Task {
+            id,
+            title,
+            status: TaskStatus::Todo,
+            priority: Priority::Medium,
+            due: None,
+            tags: Vec::new(),
+        });
+    }
+    Ok(tasks)
+}
+
+fn next_id(tasks: &[Task]) -> u32 {
+    tasks.iter().map(|task| task.id).max().unwrap_or(0) + 1
+}
+
+pub struct TaskList {
+    tasks: Vec<Task>,
+}
+
+impl TaskList {
+    pub fn load(data: &DataDir) -> Result<TaskList, TaskError> {
+        let path = data.file(TASK_FILE);
+        let content = match data_dir::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(TaskError::Io(e)),
+        };
+        Ok(TaskList { tasks: parse_tasks(&content)? })
+    }
+
+    pub fn save(&self, data: &DataDir) -> Result<(), TaskError> {
+        let mut content = String::new();
+        for task in &self.tasks {
+            content.push_str(&task.to_string());
+            content.push('\n');
+        }
+        write_atomic(data.file(TASK_FILE), content.as_bytes())?;
+        Ok(())
+    }
+
+    pub fn add(&mut self, title: &str, priority: Priority, due: Option<Date>, tags: Vec<String>) -> Result<&Task, TaskError> {
+        if title.contains(char::is_control) {
+            return Err(TaskError::Invalid(String::from("title must be a single line")));
+        }
+        // the file keeps one space between words, so the title does too
+        let title: Vec<&str> = title.split_whitespace().collect();
+        if title.is_empty() {
+            return Err(TaskError::Invalid(String::from("a task needs a title")));
+        }
+        let id = next_id(&self.tasks);
+        self.tasks.push(Task {
+            id,
+            title: title.join(" "),
+            status: TaskStatus::Todo,
+            priority,
+            due,
+            tags,
+        });
+        Ok(&self.tasks[self.tasks.len() - 1])
+    }
+
+    fn find(&mut self, id: u32) -> Result<&mut Task, TaskError> {
+        self.tasks
+            .iter_mut()
+            .find(|task| task.id == id)
+            .ok_or(TaskError::NotFound(id))
+    }
+
+    pub fn set_status(&mut self, id: u32, status: TaskStatus) -> Result<&Task, TaskError> {
+        let task = self.find(id)?;
+        task.status = status;
+        Ok(task)
+    }
+
+    pub fn complete(&mut self, id: u32) -> Result<&Task, TaskError> {
+        self.set_status(id, TaskStatus::Done)
+    }
+
+    pub fn reprioritize(&mut self, id: u32, priority: Priority) -> Result<&Task, TaskError> {
+        let task = self.find(id)?;
+        task.priority = priority;
+        Ok(task)
+    }
+
+    // open tasks first, then the most important, then the soonest due
+    pub fn sorted(&self) -> Vec<&Task> {
+        let mut tasks: Vec<&Task> = self.tasks.iter().collect();
+        tasks.sort_by(|a, b| {
+            (a.status == TaskStatus::Done)
+                .cmp(&(b.status == TaskStatus::Done))
+                .then(b.priority.cmp(&a.priority))
+                .then(match (a.due, b.due) {
+                    (Some(x), Some(y)) => x.cmp(&y),
+                    (Some(_), None) => std::cmp::Ordering::Less,
+                    (None, Some(_)) => std::cmp::Ordering::Greater,
+                    (None, None) => std::cmp::Ordering::Equal,
+                })
+                .then(a.id.cmp(&b.id))
+        });
+        tasks
+    }
+}
+
+fn parse_id(text: &str) -> Result<u32, TaskError> {
+    text.parse().map_err(|_| TaskError::Invalid(format!("expected a task id, got {:?}", text)))
+}
+
+fn parse_priority(text: &str) -> Result<Priority, TaskError> {
+    Priority::from_name(text).ok_or_else(|| TaskError::Invalid(format!("priority must be low, medium or high, got {:?}", text)))
+}
+
+const USAGE: &str = "usage: task <add TITLE... [--priority low|medium|high] [--due YYYY-MM-DD] [--tag TAG]... | start ID | done ID | priority ID LEVEL | list [--all] [--status todo|doing|done] [--tag TAG]>";
+
+// `learning_project task ...`
+pub fn run(data: &DataDir, args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let words: Vec<&str> = args.iter().map(String::as_str).collect();
+    // list only reads, everything else holds task.txt from load to save
+    let mode = if words.first() == Some(&"list") { Mode::Shared } else { Mode::Exclusive };
+    let _lock = FileLock::acquire(data.file(TASK_FILE), mode)?;
+    let mut list = TaskList::load(data)?;
+
+    match words.as_slice() {
+        ["add", rest @ ..] => {
+            let mut title = Vec::new();
+            let mut priority = Priority::Medium;
+            let mut due = None;
+            let mut tags = Vec::new();
+            let mut rest = rest.iter();
+            while let Some(word) = rest.next() {
+                let mut value = || rest.next().ok_or_else(|| TaskError::Invalid(format!("{} needs a value", word)));
+                match *word {
+                    "--priority" => priority = parse_priority(value()?)?,
+                    "--due" => {
+                        let text = value()?;
+                        due = Some(Date::parse(text).ok_or_else(|| TaskError::Invalid(format!("due date must be YYYY-MM-DD, got {:?}", text)))?);
+                    }
+                    "--tag" => {
+                        let tag = value()?.trim_start_matches('#');
+                        if tag.is_empty() || tag.contains(char::is_whitespace) {
+                            return Err(TaskError::Invalid(format!("tag must be one word, got {:?}", tag)).into());
+                        }
+                        tags.push(tag.to_string());
+                    }
+                    word => title.push(word),
+                }
+            }
+            println!("{}", list.add(&title.join(" "), priority, due, tags)?);
+            list.save(data)?;
+        }
+        ["start", id] => {
+            println!("{}", list.set_status(parse_id(id)?, TaskStatus::Doing)?);
+            list.save(data)?;
+        }
+        ["done", id] => {
+            println!("{}", list.complete(parse_id(id)?)?);
+            list.save(data)?;
+        }
+        ["priority", id, level] => {
+            println!("{}", list.reprioritize(parse_id(id)?, parse_priority(level)?)?);
+            list.save(data)?;
+        }
+        ["list", flags @ ..] => {
+            let mut show_done = false;
+            let mut status = None;
+            let mut tag = None;
+            let mut flags = flags.iter();
+            while let Some(flag) = flags.next() {
+                match *flag {
+                    "--all" => show_done = true,
+                    "--status" => {
+                        let name = flags.next().ok_or(USAGE)?;
+                        status = Some(TaskStatus::from_name(name).ok_or_else(|| TaskError::Invalid(format!("unknown status {:?}", name)))?);
+                    }
+                    "--tag" => tag = Some(flags.next().ok_or(USAGE)?.trim_start_matches('#')),
+                    _ => return Err(USAGE.into()),
+                }
+            }
+            for task in list.sorted() {
+                let wanted = match status {
+                    Some(status) => task.status == status,
+                    None => show_done || task.status != TaskStatus::Done,
+                };
+                if wanted && tag.is_none_or(|tag| task.tags.iter().any(|t| t == tag)) {
+                    println!("{}", task);
+                }
+            }
+        }
+        _ => return Err(USAGE.into()),
+    }
+    Ok(())
+}
@@ -0,0 +1,607 @@
+// ============================================
+// Templates for summaries and notifications
+// ============================================
+// A template is text with holes in it. The holes are filled from JSON data
+// (a Json value, see json.rs), so a template file can change how things look
+// without touching the Rust code:
+//
+//   {{ headline | upper }} by {{ author }}
+//   {% if link %}read it at {{ link }}{% else %}no link yet{% endif %}
+//   {% for tag in hashtags %}#{{ tag }}{% if not loop.last %}, {% endif %}{% endfor %}
+//   {# a comment, it isn't printed #}
+//
+// {{ a.b }} is field b inside a. A field that isn't there is empty (and false
+// in an if). Inside a for, loop.index (from 1), loop.first and loop.last are set.
+//
+// Filters change a value, one after the other:
+//   upper, lower, trim, length
+//   truncate(20)       at most 20 columns, with … (see unicode.rs)
+//   date("%e %b %Y")   a time as 18 Oct 2026, plain `date` is 2026-10-18
+//   default("none")    for a field that is empty or missing
+//   join(", ")         a list as text
+//   escape, raw        always / never HTML escape. Escaping happens after
+//                      all the other filters, so truncate can't cut an &amp; in half
+//
+// Templates in .html files HTML escape every {{ }} unless it ends in | raw.
+// A {% %} or {# #} tag alone on its line takes the whole line with it, so
+// blocks don't leave empty lines behind.
+//
+// Errors say the line and column, both for mistakes in the template and for
+// data that doesn't fit it (like date on something that isn't a time).
+
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+use crate::data_dir;
+use crate::datetime::Timestamp;
+use crate::digest::html_escape;
+use crate::json::Json;
+use crate::item::Item;
+use crate::unicode;
+
+const FILTERS: &str = "upper, lower, trim, length, truncate, date, default, join, escape or raw";
+
+#[derive(Debug)]
+pub enum TemplateError {
+    Io(io::Error),
+    // a mistake in the template itself
+    Syntax { name: String, line: usize, column: usize, message: String },
+    // the template is fine but the data doesn't fit it
+    Render { name: String, line: usize, column: usize, message: String },
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TemplateError::Io(e) => write!(f, "{}", e),
+            TemplateError::Syntax { name, line, column, message } | TemplateError::Render { name, line, column, message } => {
+                write!(f, "{}: line {}, column {}: {}", name, line, column, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+impl From<io::Error> for TemplateError {
+    fn from(e: io::Error) -> Self {
+        TemplateError::Io(e)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Template {
+    // the file name, for errors
+    name: String,
+    escape_html: bool,
+    nodes: Vec<Node>,
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Text(String),
+    Value(Expr),
+    If { condition: Expr, negated: bool, then: Vec<Node>, otherwise: Vec<Node> },
+    For { name: String, list: Expr, body: Vec<Node>, empty: Vec<Node> },
+}
+
+// a.b.c | filter | filter
+#[derive(Debug, Clone)]
+struct Expr {
+    path: Vec<String>,
+    filters: Vec<Filter>,
+    line: usize,
+    column: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Filter {
+    kind: FilterKind,
+    line: usize,
+    column: usize,
+}
+
+#[derive(Debug, Clone)]
+enum FilterKind {
+    Upper,
+    Lower,
+    Trim,
+    Length,
+    Truncate(usize),
+    Date(String),
+    Default(String),
+    Join(String),
+    Escape,
+    Raw,
+}
+
+// what can go in the ( ) of a filter
+enum Argument {
+    Text(String),
+    Number(usize),
+}
+
+impl Template {
+    // `name` is what errors call it, a name ending in .html turns on escaping
+    pub fn parse(name: &str, text: &str) -> Result<Template, TemplateError> {
+        let lower = name.to_ascii_lowercase();
+        let mut parser = Parser { name: name.to_string(), chars: text.chars().collect(), pos: 0, trim_line: false };
+        let (nodes, _) = parser.nodes(&[], None)?;
+        Ok(Template { name: name.to_string(), escape_html: lower.ends_with(".html") || lower.ends_with(".htm"), nodes })
+    }
+
+    pub fn load(path: &Path) -> Result<Template, TemplateError> {
+        let text = data_dir::read_to_string(path)?;
+        Template::parse(&path.display().to_string(), &text)
+    }
+
+    pub fn render(&self, data: &Json) -> Result<String, TemplateError> {
+        let mut out = String::new();
+        self.render_nodes(&self.nodes, data, &mut Vec::new(), &mut out)?;
+        Ok(out)
+    }
+
+    // `locals` are the for loop variables, the innermost loop last
+    fn render_nodes(&self, nodes: &[Node], data: &Json, locals: &mut Vec<(String, Json)>, out: &mut String) -> Result<(), TemplateError> {
+        for node in nodes {
+            match node {
+                Node::Text(text) => out.push_str(text),
+                Node::Value(expr) => {
+                    let (value, escape) = self.eval(expr, data, locals)?;
+                    let text = self.text(&value, expr.line, expr.column)?;
+                    if escape.unwrap_or(self.escape_html) {
+                        out.push_str(&html_escape(&text));
+                    } else {
+                        out.push_str(&text);
+                    }
+                }
+                Node::If { condition, negated, then, otherwise } => {
+                    let (value, _) = self.eval(condition, data, locals)?;
+                    let branch = if truthy(&value) != *negated { then } else { otherwise };
+                    self.render_nodes(branch, data, locals, out)?;
+                }
+                Node::For { name, list, body, empty } => {
+                    let items = match self.eval(list, data, locals)?.0 {
+                        Json::Array(items) => items,
+                        Json::Null => Vec::new(),
+                        _ => return Err(self.render_error(list.line, list.column, format!("{} is not a list", list.path.join(".")))),
+                    };
+                    if items.is_empty() {
+                        self.render_nodes(empty, data, locals, out)?;
+                    }
+                    let count = items.len();
+                    for (i, item) in items.into_iter().enumerate() {
+                        let state = Json::Object(vec![
+                            (String::from("index"), Json::Number((i + 1) as f64)),
+                            (String::from("first"), Json::Bool(i == 0)),
+                            (String::from("last"), Json::Bool(i + 1 == count)),
+                        ]);
+                        locals.push((name.clone(), item));
+                        locals.push((String::from("loop"), state));
+                        let result = self.render_nodes(body, data, locals, out);
+                        locals.truncate(locals.len() - 2);
+                        result?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // the value, and whether an escape or raw filter said to HTML escape it
+    // (None is up to the template, escaped in .html files)
+    fn eval(&self, expr: &Expr, data: &Json, locals: &[(String, Json)]) -> Result<(Json, Option<bool>), TemplateError> {
+        let first = &expr.path[0];
+        let mut value = match locals.iter().rev().find(|(name, _)| name == first) {
+            Some((_, value)) => value.clone(),
+            None => data.get(first).cloned().unwrap_or(Json::Null),
+        };
+        for key in &expr.path[1..] {
+            value = match &value {
+                Json::Object(_) => value.get(key).cloned().unwrap_or(Json::Null),
+                Json::Array(items) => key.parse::<usize>().ok().and_then(|i| items.get(i)).cloned().unwrap_or(Json::Null),
+                _ => Json::Null,
+            };
+        }
+        let mut escape = None;
+        for filter in &expr.filters {
+            match filter.kind {
+                FilterKind::Escape => escape = Some(true),
+                FilterKind::Raw => escape = Some(false),
+                _ => value = self.apply(filter, value)?,
+            }
+        }
+        Ok((value, escape))
+    }
+
+    fn apply(&self, filter: &Filter, value: Json) -> Result<Json, TemplateError> {
+        let text = |value: &Json| self.text(value, filter.line, filter.column);
+        Ok(match &filter.kind {
+            FilterKind::Upper => Json::String(text(&value)?.to_uppercase()),
+            FilterKind::Lower => Json::String(text(&value)?.to_lowercase()),
+            FilterKind::Trim => Json::String(text(&value)?.trim().to_string()),
+            FilterKind::Length => Json::Number(match &value {
+                Json::Null => 0,
+                Json::Array(items) => items.len(),
+                Json::Object(fields) => fields.len(),
+                other => unicode::graphemes(&text(other)?).len(),
+            } as f64),
+            FilterKind::Truncate(columns) => Json::String(unicode::truncate(&text(&value)?, *columns)),
+            FilterKind::Date(pattern) => {
+                let time = match &value {
+                    // a missing time stays missing, so default() can fill it in
+                    Json::Null => return Ok(Json::Null),
+                    Json::String(s) => Timestamp::parse_rfc3339(s).or_else(|| Timestamp::parse_rfc2822(s)),
+                    // seconds since 1970
+                    Json::Number(n) if n.fract() == 0.0 => Some(Timestamp(*n as i64)),
+                    _ => None,
+                };
+                let time = time.ok_or_else(|| self.render_error(filter.line, filter.column, format!("{} is not a time", value)))?;
+                Json::String(time.format(pattern))
+            }
+            FilterKind::Default(fallback) => match &value {
+                Json::Null => Json::String(fallback.clone()),
+                Json::String(s) if s.is_empty() => Json::String(fallback.clone()),
+                _ => value,
+            },
+            FilterKind::Join(separator) => match &value {
+                Json::Null => Json::String(String::new()),
+                Json::Array(items) => {
+                    let parts: Result<Vec<String>, TemplateError> = items.iter().map(text).collect();
+                    Json::String(parts?.join(separator))
+                }
+                _ => return Err(self.render_error(filter.line, filter.column, "join needs a list")),
+            },
+            // eval handles these two, the escaping is done when the value is printed
+            FilterKind::Escape | FilterKind::Raw => value,
+        })
+    }
+
+    // how a value looks in the output
+    fn text(&self, value: &Json, line: usize, column: usize) -> Result<String, TemplateError> {
+        match value {
+            Json::Null => Ok(String::new()),
+            Json::String(s) => Ok(s.clone()),
+            Json::Bool(_) | Json::Number(_) => Ok(value.to_string()),
+            Json::Array(_) => Err(self.render_error(line, column, "can't print a list, use join or a for loop")),
+            Json::Object(_) => Err(self.render_error(line, column, "can't print an object, pick one of its fields with .")),
+        }
+    }
+
+    fn render_error(&self, line: usize, column: usize, message: impl Into<String>) -> TemplateError {
+        TemplateError::Render { name: self.name.clone(), line, column, message: message.into() }
+    }
+}
+
+// the data a template gets for one item: the item's own fields (fields() in
+// item.rs) and summary, kind, source, priority and published,
+// which every item has
+pub fn context<S: Item + ?Sized>(item: &S) -> Json {
+    let mut fields = item.fields();
+    fields.push((String::from("summary"), Json::String(item.summarize())));
+    fields.push((String::from("kind"), Json::String(item.kind().to_string())));
+    fields.push((String::from("source"), Json::String(item.source())));
+    fields.push((String::from("priority"), Json::String(item.priority().name().to_string())));
+    let published = item.published().map(|time| Json::String(time.to_string())).unwrap_or(Json::Null);
+    fields.push((String::from("published"), published));
+    Json::Object(fields)
+}
+
+// empty things are false, like in most template languages
+fn truthy(value: &Json) -> bool {
+    match value {
+        Json::Null => false,
+        Json::Bool(b) => *b,
+        Json::Number(n) => *n != 0.0,
+        Json::String(s) => !s.is_empty(),
+        Json::Array(items) => !items.is_empty(),
+        Json::Object(fields) => !fields.is_empty(),
+    }
+}
+
+struct Parser {
+    name: String,
+    chars: Vec<char>,
+    pos: usize,
+    // the tag being read is alone on its line, so the line goes too
+    trim_line: bool,
+}
+
+impl Parser {
+    fn error<T>(&self, at: usize, message: impl Into<String>) -> Result<T, TemplateError> {
+        let (line, column) = self.position(at);
+        Err(TemplateError::Syntax { name: self.name.clone(), line, column, message: message.into() })
+    }
+
+    fn position(&self, at: usize) -> (usize, usize) {
+        let before = &self.chars[..at.min(self.chars.len())];
+        let line = before.iter().filter(|&&c| c == '\n').count() + 1;
+        let column = before.iter().rev().take_while(|&&c| c != '\n').count() + 1;
+        (line, column)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn starts_with(&self, text: &str) -> bool {
+        text.chars().enumerate().all(|(i, c)| self.chars.get(self.pos + i) == Some(&c))
+    }
+
+    fn find(&self, text: &str, from: usize) -> Option<usize> {
+        let text: Vec<char> = text.chars().collect();
+        (from..self.chars.len()).find(|&i| self.chars[i..].starts_with(&text))
+    }
+
+    fn skip_spaces(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    // the nodes up to one of the `ends` tags (say {% endif %}), which is read
+    // up to its name and given back. `opener` is the {% if %} or {% for %}
+    // we are inside, for the error when the end never comes.
+    fn nodes(&mut self, ends: &[&str], opener: Option<(usize, &str)>) -> Result<(Vec<Node>, Option<String>), TemplateError> {
+        let mut nodes = Vec::new();
+        let mut text = String::new();
+        let flush = |text: &mut String, nodes: &mut Vec<Node>| {
+            if !text.is_empty() {
+                nodes.push(Node::Text(std::mem::take(text)));
+            }
+        };
+        loop {
+            let start = self.pos;
+            if self.pos >= self.chars.len() {
+                if let Some((at, tag)) = opener {
+                    return self.error(at, format!("{{% {} %}} is never closed, expected {{% {} %}}", tag, ends[ends.len() - 1]));
+                }
+                flush(&mut text, &mut nodes);
+                return Ok((nodes, None));
+            }
+            if self.starts_with("{{") {
+                flush(&mut text, &mut nodes);
+                self.pos += 2;
+                self.skip_spaces();
+                let expr = self.expr()?;
+                self.skip_spaces();
+                if !self.starts_with("}}") {
+                    return self.error(self.pos, "expected }}");
+                }
+                self.pos += 2;
+                nodes.push(Node::Value(expr));
+            } else if self.starts_with("{#") {
+                self.standalone(&mut text, "#}");
+                let Some(end) = self.find("#}", self.pos) else {
+                    return self.error(start, "comment is never closed, expected #}");
+                };
+                self.pos = end + 2;
+                self.end_line();
+            } else if self.starts_with("{%") {
+                self.standalone(&mut text, "%}");
+                self.pos += 2;
+                self.skip_spaces();
+                let keyword = self.word()?;
+                flush(&mut text, &mut nodes);
+                if ends.contains(&keyword.as_str()) {
+                    return Ok((nodes, Some(keyword)));
+                }
+                match keyword.as_str() {
+                    "if" => nodes.push(self.if_block(start)?),
+                    "for" => nodes.push(self.for_block(start)?),
+                    "else" | "endif" | "endfor" => match opener {
+                        Some((_, tag)) => {
+                            let expected = ends[ends.len() - 1];
+                            return self.error(start, format!("{{% {} %}} can't close {{% {} %}}, expected {{% {} %}}", keyword, tag, expected));
+                        }
+                        None => return self.error(start, format!("{{% {} %}} without an {{% if %}} or {{% for %}} to go with it", keyword)),
+                    },
+                    other => return self.error(start, format!("unknown tag {{% {} %}}, expected if, for, else, endif or endfor", other)),
+                }
+            } else {
+                text.push(self.chars[self.pos]);
+                self.pos += 1;
+            }
+        }
+    }
+
+    // {% if [not] expr %} ... [{% else %} ...] {% endif %}, the "if" is read already
+    fn if_block(&mut self, start: usize) -> Result<Node, TemplateError> {
+        self.skip_spaces();
+        let negated = self.starts_with("not ");
+        if negated {
+            self.pos += 4;
+            self.skip_spaces();
+        }
+        let condition = self.expr()?;
+        self.tag_end()?;
+        let (then, end) = self.nodes(&["else", "endif"], Some((start, "if")))?;
+        self.tag_end()?;
+        let mut otherwise = Vec::new();
+        if end.as_deref() == Some("else") {
+            otherwise = self.nodes(&["endif"], Some((start, "if")))?.0;
+            self.tag_end()?;
+        }
+        Ok(Node::If { condition, negated, then, otherwise })
+    }
+
+    // {% for name in expr %} ... [{% else %} when the list is empty] {% endfor %}
+    fn for_block(&mut self, start: usize) -> Result<Node, TemplateError> {
+        self.skip_spaces();
+        let name = self.word()?;
+        self.skip_spaces();
+        let at = self.pos;
+        if self.word().ok().as_deref() != Some("in") {
+            return self.error(at, format!("expected {{% for {} in LIST %}}", name));
+        }
+        self.skip_spaces();
+        let list = self.expr()?;
+        self.tag_end()?;
+        let (body, end) = self.nodes(&["else", "endfor"], Some((start, "for")))?;
+        self.tag_end()?;
+        let mut empty = Vec::new();
+        if end.as_deref() == Some("else") {
+            empty = self.nodes(&["endfor"], Some((start, "for")))?.0;
+            self.tag_end()?;
+        }
+        Ok(Node::For { name, list, body, empty })
+    }
+
+    fn tag_end(&mut self) -> Result<(), TemplateError> {
+        self.skip_spaces();
+        if !self.starts_with("%}") {
+            return self.error(self.pos, "expected %}");
+        }
+        self.pos += 2;
+        self.end_line();
+        Ok(())
+    }
+
+    // is the tag starting here alone on its line? Then the spaces before it
+    // (already in `text`) go now and the line break after it in end_line()
+    fn standalone(&mut self, text: &mut String, close: &str) {
+        let line_start = self.chars[..self.pos].iter().rposition(|&c| c == '\n').map_or(0, |i| i + 1);
+        let blank = |c: &char| *c == ' ' || *c == '\t' || *c == '\r';
+        let blank_before = self.chars[line_start..self.pos].iter().all(blank);
+        let blank_after = self.find(close, self.pos).is_some_and(|end| {
+            self.chars[end + close.len()..].iter().take_while(|&&c| c != '\n').all(blank)
+        });
+        self.trim_line = blank_before && blank_after;
+        if self.trim_line {
+            text.truncate(text.trim_end_matches([' ', '\t']).len());
+        }
+    }
+
+    fn end_line(&mut self) {
+        if !std::mem::take(&mut self.trim_line) {
+            return;
+        }
+        while matches!(self.peek(), Some(' ' | '\t' | '\r')) {
+            self.pos += 1;
+        }
+        if self.peek() == Some('\n') {
+            self.pos += 1;
+        }
+    }
+
+    fn word(&mut self) -> Result<String, TemplateError> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_alphanumeric() || c == '_') {
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return self.error(start, "expected a name");
+        }
+        Ok(self.chars[start..self.pos].iter().collect())
+    }
+
+    fn expr(&mut self) -> Result<Expr, TemplateError> {
+        let (line, column) = self.position(self.pos);
+        let mut path = vec![self.word()?];
+        while self.peek() == Some('.') {
+            self.pos += 1;
+            path.push(self.word()?);
+        }
+        let mut filters = Vec::new();
+        loop {
+            self.skip_spaces();
+            if self.peek() != Some('|') {
+                break;
+            }
+            self.pos += 1;
+            self.skip_spaces();
+            filters.push(self.filter()?);
+        }
+        Ok(Expr { path, filters, line, column })
+    }
+
+    fn filter(&mut self) -> Result<Filter, TemplateError> {
+        let start = self.pos;
+        let (line, column) = self.position(start);
+        let name = self.word()?;
+        let mut arguments = Vec::new();
+        if self.peek() == Some('(') {
+            self.pos += 1;
+            loop {
+                self.skip_spaces();
+                if self.peek() == Some(')') {
+                    self.pos += 1;
+                    break;
+                }
+                arguments.push(self.argument()?);
+                self.skip_spaces();
+                match self.peek() {
+                    Some(',') => self.pos += 1,
+                    Some(')') => {
+                        self.pos += 1;
+                        break;
+                    }
+                    _ => return self.error(self.pos, "expected , or )"),
+                }
+            }
+        }
+        let kind = match (name.as_str(), arguments.as_slice()) {
+            ("upper", []) => FilterKind::Upper,
+            ("lower", []) => FilterKind::Lower,
+            ("trim", []) => FilterKind::Trim,
+            ("length", []) => FilterKind::Length,
+            ("truncate", [Argument::Number(columns)]) => FilterKind::Truncate(*columns),
+            ("date", []) => FilterKind::Date(String::from("%Y-%m-%d")),
+            ("date", [Argument::Text(pattern)]) => FilterKind::Date(pattern.clone()),
+            ("default", [Argument::Text(fallback)]) => FilterKind::Default(fallback.clone()),
+            ("join", []) => FilterKind::Join(String::from(", ")),
+            ("join", [Argument::Text(separator)]) => FilterKind::Join(separator.clone()),
+            ("escape", []) => FilterKind::Escape,
+            ("raw", []) => FilterKind::Raw,
+            (name, _) => {
+                let usage = match name {
+                    "upper" | "lower" | "trim" | "length" | "escape" | "raw" => name.to_string(),
+                    "truncate" => String::from("truncate(COLUMNS)"),
+                    "date" => String::from("date or date(\"%e %b %Y\")"),
+                    "default" => String::from("default(\"TEXT\")"),
+                    "join" => String::from("join or join(\", \")"),
+                    _ => return self.error(start, format!("unknown filter {}, try {}", name, FILTERS)),
+                };
+                return self.error(start, format!("{} is written {}", name, usage));
+            }
+        };
+        Ok(Filter { kind, line, column })
+    }
+
+    // "text" with \" and \\ in it, or a whole number
+    fn argument(&mut self) -> Result<Argument, TemplateError> {
+        let start = self.pos;
+        match self.peek() {
+            Some('"') => {
+                self.pos += 1;
+                let mut text = String::new();
+                loop {
+                    match self.peek() {
+                        Some('"') => {
+                            self.pos += 1;
+                            return Ok(Argument::Text(text));
+                        }
+                        Some('\\') if matches!(self.chars.get(self.pos + 1), Some('"' | '\\')) => {
+                            text.push(self.chars[self.pos + 1]);
+                            self.pos += 2;
+                        }
+                        Some(c) => {
+                            text.push(c);
+                            self.pos += 1;
+                        }
+                        None => return self.error(start, "text is never closed, expected \""),
+                    }
+                }
+            }
+            Some(c) if c.is_ascii_digit() => {
+                while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                    self.pos += 1;
+                }
+                let digits: String = self.chars[start..self.pos].iter().collect();
+                digits.parse().map(Argument::Number).or_else(|_| self.error(start, "number is too big"))
+            }
+            _ => self.error(start, "expected \"text\" or a number"),
+        }
+    }
+}
@@ -0,0 +1,186 @@
+// ============================================
+// Characters the way people see them
+// ============================================
+// A Rust char is one Unicode code point, but what a person calls one
+// character (a "grapheme cluster") can be several of them:
+//   é      = e + U+0301 (combining accent)
+//   👍🏽     = 👍 + U+1F3FD (skin tone)
+//   👨‍👩‍👧    = 👨 + ZWJ + 👩 + ZWJ + 👧
+//   🇳🇬     = two regional indicator letters, N and G
+// And on a terminal some take two columns: 🦀, 漢, 한.
+//
+// So `s.len()` counts bytes, `s.chars().count()` counts code points, and
+// neither is "how long does this look". Cutting at a byte can even panic
+// in the middle of a 🦀 (4 bytes).
+//
+// These are the main rules from Unicode (UAX #29 for clusters, East Asian
+// Width for columns) with small tables, not the full Unicode data, so some
+// rare scripts will be counted a bit off.
+
+const ZWJ: char = '\u{200d}';
+const ELLIPSIS: char = '…';
+
+// splits text into grapheme clusters
+pub fn graphemes(text: &str) -> Vec<&str> {
+    let mut clusters = Vec::new();
+    let mut start = 0;
+    let mut previous: Option<char> = None;
+    // regional indicators pair up, a third one starts a new flag
+    let mut indicators = 0;
+    for (at, c) in text.char_indices() {
+        let joins = match previous {
+            None => false,
+            Some('\r') => c == '\n',
+            Some(p) if p.is_control() => false,
+            Some(p) => {
+                if c.is_control() {
+                    false
+                } else if is_extend(c) || c == ZWJ {
+                    true
+                } else if p == ZWJ {
+                    // a ZWJ glues two pictures into one: 👨‍👩‍👧
+                    is_pictographic(c)
+                } else if is_regional_indicator(p) && is_regional_indicator(c) {
+                    indicators % 2 == 1
+                } else {
+                    hangul_joins(p, c)
+                }
+            }
+        };
+        if !joins && at > start {
+            clusters.push(&text[start..at]);
+            start = at;
+        }
+        indicators = if is_regional_indicator(c) { if joins { indicators + 1 } else { 1 } } else { 0 };
+        previous = Some(c);
+    }
+    if start < text.len() {
+        clusters.push(&text[start..]);
+    }
+    clusters
+}
+
+// how many columns a terminal uses for the text
+pub fn width(text: &str) -> usize {
+    graphemes(text).into_iter().map(cluster_width).sum()
+}
+
+// cuts the text to at most `columns` columns, with … at the end if
+// anything was cut. Never splits a cluster.
+pub fn truncate(text: &str, columns: usize) -> String {
+    if width(text) <= columns {
+        return text.to_string();
+    }
+    if columns == 0 {
+        return String::new();
+    }
+    let mut cut = String::new();
+    let mut used = 0;
+    // keep one column for the …
+    for cluster in graphemes(text) {
+        let w = cluster_width(cluster);
+        if used + w > columns - 1 {
+            break;
+        }
+        cut.push_str(cluster);
+        used += w;
+    }
+    // "some words …" looks odd, "some words…" doesn't
+    let mut cut = cut.trim_end().to_string();
+    cut.push(ELLIPSIS);
+    cut
+}
+
+fn cluster_width(cluster: &str) -> usize {
+    let mut chars = cluster.chars();
+    let Some(first) = chars.next() else {
+        return 0;
+    };
+    // U+FE0F asks for the emoji picture, which is two columns wide: ❤️
+    if is_regional_indicator(first) || cluster.contains('\u{fe0f}') {
+        return 2;
+    }
+    char_width(first)
+}
+
+fn char_width(c: char) -> usize {
+    if c.is_control() || is_extend(c) || c == ZWJ {
+        0
+    } else if is_wide(c) {
+        2
+    } else {
+        1
+    }
+}
+
+// marks that sit on the character before them: accents, vowel signs,
+// variation selectors, skin tones
+fn is_extend(c: char) -> bool {
+    matches!(c,
+        '\u{0300}'..='\u{036f}'   // accents
+        | '\u{0483}'..='\u{0489}' // Cyrillic
+        | '\u{0591}'..='\u{05bd}' | '\u{05bf}' | '\u{05c1}'..='\u{05c2}' | '\u{05c4}'..='\u{05c5}' | '\u{05c7}' // Hebrew
+        | '\u{0610}'..='\u{061a}' | '\u{064b}'..='\u{065f}' | '\u{0670}' | '\u{06d6}'..='\u{06dc}' | '\u{06df}'..='\u{06e4}'
+        | '\u{06e7}'..='\u{06e8}' | '\u{06ea}'..='\u{06ed}' // Arabic
+        | '\u{0900}'..='\u{0903}' | '\u{093a}'..='\u{094f}' | '\u{0951}'..='\u{0957}' | '\u{0962}'..='\u{0963}' // Devanagari
+        | '\u{0981}'..='\u{0983}' | '\u{09bc}'..='\u{09d7}' // Bengali
+        | '\u{0e31}' | '\u{0e34}'..='\u{0e3a}' | '\u{0e47}'..='\u{0e4e}' // Thai
+        | '\u{1ab0}'..='\u{1aff}' | '\u{1dc0}'..='\u{1dff}' | '\u{200c}' | '\u{20d0}'..='\u{20ff}'
+        | '\u{fe00}'..='\u{fe0f}' // variation selectors
+        | '\u{fe20}'..='\u{fe2f}'
+        | '\u{1f3fb}'..='\u{1f3ff}' // skin tones
+        | '\u{e0020}'..='\u{e007f}' // tags, for flags like 🏴󠁧󠁢󠁳󠁣󠁴󠁿
+        | '\u{e0100}'..='\u{e01ef}'
+    )
+}
+
+fn is_regional_indicator(c: char) -> bool {
+    matches!(c, '\u{1f1e6}'..='\u{1f1ff}')
+}
+
+// pictures that can be joined with a ZWJ
+fn is_pictographic(c: char) -> bool {
+    matches!(c, '\u{00a9}' | '\u{00ae}' | '\u{203c}' | '\u{2049}' | '\u{2122}' | '\u{2139}' | '\u{2194}'..='\u{21aa}'
+        | '\u{231a}'..='\u{23ff}' | '\u{24c2}' | '\u{25aa}'..='\u{27bf}' | '\u{2934}'..='\u{2935}' | '\u{2b05}'..='\u{2b55}'
+        | '\u{3030}' | '\u{303d}' | '\u{3297}' | '\u{3299}' | '\u{1f000}'..='\u{1faff}')
+}
+
+// two columns on a terminal: CJK, Hangul, fullwidth forms and most emoji
+fn is_wide(c: char) -> bool {
+    matches!(c,
+        '\u{1100}'..='\u{115f}'   // Hangul leading jamo
+        | '\u{231a}'..='\u{231b}' | '\u{23e9}'..='\u{23ec}' | '\u{23f0}' | '\u{23f3}'
+        | '\u{25fd}'..='\u{25fe}' | '\u{2614}'..='\u{2615}' | '\u{2648}'..='\u{2653}' | '\u{267f}' | '\u{2693}' | '\u{26a1}'
+        | '\u{26aa}'..='\u{26ab}' | '\u{26bd}'..='\u{26be}' | '\u{26c4}'..='\u{26c5}' | '\u{26ce}' | '\u{26d4}' | '\u{26ea}'
+        | '\u{26f2}'..='\u{26f3}' | '\u{26f5}' | '\u{26fa}' | '\u{26fd}' | '\u{2705}' | '\u{270a}'..='\u{270b}' | '\u{2728}'
+        | '\u{274c}' | '\u{274e}' | '\u{2753}'..='\u{2755}' | '\u{2757}' | '\u{2795}'..='\u{2797}' | '\u{27b0}' | '\u{27bf}'
+        | '\u{2b1b}'..='\u{2b1c}' | '\u{2b50}' | '\u{2b55}' // emoji in the symbol blocks
+        | '\u{2e80}'..='\u{303e}' | '\u{3041}'..='\u{33ff}' // CJK punctuation, kana
+        | '\u{3400}'..='\u{4dbf}' | '\u{4e00}'..='\u{9fff}' // CJK ideographs
+        | '\u{a000}'..='\u{a4cf}' | '\u{a960}'..='\u{a97f}' | '\u{ac00}'..='\u{d7a3}' // Yi, Hangul syllables
+        | '\u{f900}'..='\u{faff}' | '\u{fe10}'..='\u{fe19}' | '\u{fe30}'..='\u{fe6f}'
+        | '\u{ff00}'..='\u{ff60}' | '\u{ffe0}'..='\u{ffe6}' // fullwidth forms
+        | '\u{1f004}' | '\u{1f0cf}' | '\u{1f18e}' | '\u{1f191}'..='\u{1f19a}' | '\u{1f200}'..='\u{1f251}'
+        | '\u{1f300}'..='\u{1f320}' | '\u{1f32d}'..='\u{1f335}' | '\u{1f337}'..='\u{1f37c}' | '\u{1f37e}'..='\u{1f393}'
+        | '\u{1f3a0}'..='\u{1f3ca}' | '\u{1f3cf}'..='\u{1f3d3}' | '\u{1f3e0}'..='\u{1f3f0}' | '\u{1f3f4}' | '\u{1f3f8}'..='\u{1f43e}'
+        | '\u{1f440}' | '\u{1f442}'..='\u{1f4fc}' | '\u{1f4ff}'..='\u{1f53d}' | '\u{1f54b}'..='\u{1f54e}' | '\u{1f550}'..='\u{1f567}'
+        | '\u{1f57a}' | '\u{1f595}'..='\u{1f596}' | '\u{1f5a4}' | '\u{1f5fb}'..='\u{1f64f}' | '\u{1f680}'..='\u{1f6c5}'
+        | '\u{1f6cc}' | '\u{1f6d0}'..='\u{1f6d2}' | '\u{1f6d5}'..='\u{1f6d7}' | '\u{1f6eb}'..='\u{1f6ec}' | '\u{1f6f4}'..='\u{1f6fc}'
+        | '\u{1f7e0}'..='\u{1f7eb}' | '\u{1f90c}'..='\u{1f93a}' | '\u{1f93c}'..='\u{1f945}' | '\u{1f947}'..='\u{1f9ff}'
+        | '\u{1fa70}'..='\u{1faff}' // emoji
+        | '\u{20000}'..='\u{3fffd}' // more CJK ideographs
+    )
+}
+
+// old-style Hangul written as separate jamo (ᄒ + ᅡ + ᆫ = 한)
+fn hangul_joins(previous: char, c: char) -> bool {
+    let leading = |c: char| matches!(c, '\u{1100}'..='\u{115f}' | '\u{a960}'..='\u{a97c}');
+    let vowel = |c: char| matches!(c, '\u{1160}'..='\u{11a7}' | '\u{d7b0}'..='\u{d7c6}');
+    let trailing = |c: char| matches!(c, '\u{11a8}'..='\u{11ff}' | '\u{d7cb}'..='\u{d7fb}');
+    let syllable = |c: char| matches!(c, '\u{ac00}'..='\u{d7a3}');
+    // a syllable with no final consonant is one where code - AC00 is a multiple of 28
+    let open_syllable = |c: char| syllable(c) && (c as u32 - 0xac00).is_multiple_of(28);
+    (leading(previous) && (leading(c) || vowel(c) || syllable(c)))
+        || ((vowel(previous) || open_syllable(previous)) && (vowel(c) || trailing(c)))
+        || ((trailing(previous) || syllable(previous)) && trailing(c))
+}
@@ -0,0 +1,317 @@
+// ============================================
+// A small XML reader
+// ============================================
+// Enough XML for RSS and Atom feeds: elements, attributes, text, entities
+// (&amp; &#233; ...) and CDATA sections (<![CDATA[ raw <b>text</b> ]]>).
+// Comments, <?xml ...?> and <!DOCTYPE ...> are skipped.
+// Like the JSON reader, errors say the line and column.
+//
+// Names are kept as written, prefix and all ("dc:creator"). Feeds only use
+// a few well-known prefixes, so we don't look namespaces up.
+
+use std::fmt;
+
+// deeper nesting than this is almost certainly garbage (or an attack)
+const MAX_DEPTH: usize = 128;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Node {
+    Element(Element),
+    Text(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Element {
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<Node>,
+}
+
+#[derive(Debug)]
+pub struct XmlError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for XmlError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl std::error::Error for XmlError {}
+
+impl Element {
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str())
+    }
+
+    // the child elements called `name`
+    pub fn children_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> + 'a {
+        self.children.iter().filter_map(move |node| match node {
+            Node::Element(element) if element.name == name => Some(element),
+            _ => None,
+        })
+    }
+
+    pub fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find_map(|node| match node {
+            Node::Element(element) if element.name == name => Some(element),
+            _ => None,
+        })
+    }
+
+    // all the text inside, child elements included
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        for node in &self.children {
+            match node {
+                Node::Text(t) => text.push_str(t),
+                Node::Element(element) => text.push_str(&element.text()),
+            }
+        }
+        text
+    }
+}
+
+// the one element at the top of the document
+pub fn parse(text: &str) -> Result<Element, XmlError> {
+    let mut parser = Parser { chars: text.trim_start_matches('\u{feff}').chars().collect(), pos: 0 };
+    parser.skip_misc()?;
+    if parser.peek() != Some('<') {
+        return Err(parser.error("expected an element"));
+    }
+    let root = parser.element(0)?;
+    parser.skip_misc()?;
+    if parser.pos < parser.chars.len() {
+        return Err(parser.error("unexpected text after the root element"));
+    }
+    Ok(root)
+}
+
+// the character for &name; XML only has five, the others are HTML ones
+// that turn up in feeds anyway
+pub fn entity(name: &str) -> Option<char> {
+    if let Some(number) = name.strip_prefix('#') {
+        let code = match number.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => number.parse().ok()?,
+        };
+        return char::from_u32(code);
+    }
+    Some(match name {
+        "lt" => '<',
+        "gt" => '>',
+        "amp" => '&',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => '\u{a0}',
+        "ndash" => '–',
+        "mdash" => '—',
+        "hellip" => '…',
+        "lsquo" => '‘',
+        "rsquo" => '’',
+        "ldquo" => '“',
+        "rdquo" => '”',
+        "copy" => '©',
+        _ => return None,
+    })
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn error(&self, message: &str) -> XmlError {
+        let before = &self.chars[..self.pos.min(self.chars.len())];
+        let line = before.iter().filter(|&&c| c == '\n').count() + 1;
+        let column = before.iter().rev().take_while(|&&c| c != '\n').count() + 1;
+        XmlError { line, column, message: message.to_string() }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn starts_with(&self, text: &str) -> bool {
+        text.chars().enumerate().all(|(i, c)| self.chars.get(self.pos + i) == Some(&c))
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    // moves past `end`, or fails with `message` if it never comes
+    fn skip_past(&mut self, end: &str, message: &str) -> Result<(), XmlError> {
+        while self.pos < self.chars.len() {
+            if self.starts_with(end) {
+                self.pos += end.chars().count();
+                return Ok(());
+            }
+            self.pos += 1;
+        }
+        Err(self.error(message))
+    }
+
+    // whitespace, comments, <?...?> and <!DOCTYPE ...> around the root element
+    fn skip_misc(&mut self) -> Result<(), XmlError> {
+        loop {
+            self.skip_whitespace();
+            if self.starts_with("<!--") {
+                self.skip_past("-->", "comment is never closed")?;
+            } else if self.starts_with("<?") {
+                self.skip_past("?>", "<? is never closed")?;
+            } else if self.starts_with("<!DOCTYPE") {
+                // a DOCTYPE can have [...] inside with more > in it
+                let mut depth = 0;
+                loop {
+                    match self.peek() {
+                        Some('[') => depth += 1,
+                        Some(']') => depth -= 1,
+                        Some('>') if depth == 0 => break,
+                        Some(_) => {}
+                        None => return Err(self.error("DOCTYPE is never closed")),
+                    }
+                    self.pos += 1;
+                }
+                self.pos += 1;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn name(&mut self) -> Result<String, XmlError> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_alphanumeric() || matches!(c, ':' | '_' | '-' | '.')) {
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return Err(self.error("expected a name"));
+        }
+        Ok(self.chars[start..self.pos].iter().collect())
+    }
+
+    fn element(&mut self, depth: usize) -> Result<Element, XmlError> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("nested too deeply"));
+        }
+        self.pos += 1; // the <
+        let name = self.name()?;
+        let mut element = Element { name, attributes: Vec::new(), children: Vec::new() };
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some('/') if self.starts_with("/>") => {
+                    self.pos += 2;
+                    return Ok(element);
+                }
+                Some('>') => {
+                    self.pos += 1;
+                    break;
+                }
+                Some(_) => {
+                    let key = self.name()?;
+                    self.skip_whitespace();
+                    if self.peek() != Some('=') {
+                        return Err(self.error(&format!("attribute {} needs a value", key)));
+                    }
+                    self.pos += 1;
+                    self.skip_whitespace();
+                    let value = self.attribute_value()?;
+                    element.attributes.push((key, value));
+                }
+                None => return Err(self.error(&format!("<{}> is never closed", element.name))),
+            }
+        }
+        self.content(&mut element, depth)?;
+        Ok(element)
+    }
+
+    fn attribute_value(&mut self) -> Result<String, XmlError> {
+        let quote = match self.peek() {
+            Some(quote @ ('"' | '\'')) => quote,
+            _ => return Err(self.error("attribute value needs quotes")),
+        };
+        self.pos += 1;
+        let mut value = String::new();
+        loop {
+            match self.peek() {
+                Some(c) if c == quote => {
+                    self.pos += 1;
+                    return Ok(value);
+                }
+                Some('&') => value.push(self.entity()?),
+                Some('<') => return Err(self.error("'<' can't be in an attribute value")),
+                Some(c) => {
+                    value.push(c);
+                    self.pos += 1;
+                }
+                None => return Err(self.error("attribute value is never closed")),
+            }
+        }
+    }
+
+    // everything up to the matching </name>
+    fn content(&mut self, element: &mut Element, depth: usize) -> Result<(), XmlError> {
+        let mut text = String::new();
+        loop {
+            if self.starts_with("</") {
+                self.pos += 2;
+                let name = self.name()?;
+                if name != element.name {
+                    return Err(self.error(&format!("expected </{}>, found </{}>", element.name, name)));
+                }
+                self.skip_whitespace();
+                if self.peek() != Some('>') {
+                    return Err(self.error("expected '>'"));
+                }
+                self.pos += 1;
+                break;
+            } else if self.starts_with("<![CDATA[") {
+                // CDATA is taken exactly as it is, no entities and no tags
+                self.pos += "<![CDATA[".len();
+                let start = self.pos;
+                self.skip_past("]]>", "CDATA section is never closed")?;
+                text.extend(&self.chars[start..self.pos - 3]);
+            } else if self.starts_with("<!--") {
+                self.skip_past("-->", "comment is never closed")?;
+            } else if self.starts_with("<?") {
+                self.skip_past("?>", "<? is never closed")?;
+            } else if self.peek() == Some('<') {
+                if !text.is_empty() {
+                    element.children.push(Node::Text(std::mem::take(&mut text)));
+                }
+                let child = self.element(depth + 1)?;
+                element.children.push(Node::Element(child));
+            } else if self.peek() == Some('&') {
+                text.push(self.entity()?);
+            } else if let Some(c) = self.peek() {
+                text.push(c);
+                self.pos += 1;
+            } else {
+                return Err(self.error(&format!("<{}> is never closed", element.name)));
+            }
+        }
+        if !text.is_empty() {
+            element.children.push(Node::Text(text));
+        }
+        Ok(())
+    }
+
+    fn entity(&mut self) -> Result<char, XmlError> {
+        let start = self.pos;
+        let Some(length) = self.chars[start..].iter().take(12).position(|&c| c == ';') else {
+            return Err(self.error("'&' must start an entity like &amp;"));
+        };
+        let name: String = self.chars[start + 1..start + length].iter().collect();
+        let c = entity(&name).ok_or_else(|| self.error(&format!("unknown entity &{};", name)))?;
+        self.pos = start + length + 1;
+        Ok(c)
+    }
+}
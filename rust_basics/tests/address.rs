@@ -0,0 +1,80 @@
+// Checks email address parsing through `learning_project email build` (valid
+// addresses end up in the From header) and `json email` (bad ones are refused).
+
+use std::fs;
+use std::process::{Command, Output};
+
+fn build_from(from: &str) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_learning_project"))
+        .args(["email", "build", "--from", from, "--to", "ann@example.com", "--subject", "hi", "hello"])
+        .output()
+        .unwrap()
+}
+
+fn from_header(from: &str) -> String {
+    let output = build_from(from);
+    assert!(output.status.success(), "{:?}: {}", from, String::from_utf8_lossy(&output.stderr));
+    let message = String::from_utf8(output.stdout).unwrap();
+    message.lines().find_map(|line| line.strip_prefix("From: ")).unwrap().trim_end().to_string()
+}
+
+fn error(from: &str) -> String {
+    let output = build_from(from);
+    assert!(!output.status.success(), "{:?} should be refused", from);
+    String::from_utf8_lossy(&output.stderr).trim_end().to_string()
+}
+
+#[test]
+fn valid_addresses_are_accepted() {
+    assert_eq!(from_header("ekom@example.com"), "<ekom@example.com>");
+    assert_eq!(from_header("  Ekom Otu <ekom@example.com> "), "\"Ekom Otu\" <ekom@example.com>");
+    assert_eq!(from_header("\"Otu, Ekom\" <ekom@example.com>"), "\"Otu, Ekom\" <ekom@example.com>");
+    assert_eq!(from_header("\"ekom otu\"@example.com"), "<\"ekom otu\"@example.com>");
+    assert_eq!(from_header("first.last+tag@sub.example.com"), "<first.last+tag@sub.example.com>");
+    // names outside ASCII are encoded words, domains outside ASCII are punycode
+    assert_eq!(from_header("Zoë <zoe@example.com>"), "=?utf-8?B?Wm/Dqw==?= <zoe@example.com>");
+    assert_eq!(from_header("ekom@München.de"), "<ekom@xn--mnchen-3ya.de>");
+}
+
+#[test]
+fn errors_point_at_the_bad_character() {
+    let cases = [
+        ("ekom otu", "column 5: unexpected ' ' in the part before @"),
+        ("", "column 1: address is empty"),
+        ("@example.com", "column 1: nothing before the @"),
+        ("ekom", "column 5: missing @ and domain"),
+        ("ekom@", "column 6: nothing after the @"),
+        (".ekom@example.com", "column 1: the part before @ can't start with a dot"),
+        ("ek..om@example.com", "column 4: two dots in a row"),
+        ("ekom@example..com", "column 14: empty part in the domain"),
+        ("ekom@exa_mple.com", "column 9: '_' can't be in a domain"),
+        ("ekom@-example.com", "column 6: domain part \"-example\" can't start or end with '-'"),
+        ("\"ekom@example.com", "column 1: quote is never closed"),
+        ("Otu, Ekom <ekom@example.com>", "column 4: ',' can't be in a name without quotes"),
+        ("Ekom <ekom@example.com", "column 23: missing '>' after the address"),
+        ("Ekom <ekom@example.com> x", "column 25: unexpected text after the address"),
+        ("jürgen@example.com", "column 2: unexpected 'ü' in the part before @"),
+    ];
+    for (from, expected) in cases {
+        let message = error(from);
+        assert!(
+            message.starts_with(&format!("error: --from {:?}: {}", from, expected)),
+            "{:?} gave {:?}",
+            from,
+            message
+        );
+    }
+}
+
+#[test]
+fn json_emails_need_a_valid_address() {
+    let dir = std::env::temp_dir().join(format!("lp-address-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("email.json");
+    fs::write(&file, r#"{"from": "ekom otu", "subject": "learning rust"}"#).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_learning_project")).arg("json").arg("email").arg(&file).output().unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("field \"from\": column 5: unexpected ' ' in the part before @"));
+}
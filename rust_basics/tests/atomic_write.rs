@@ -0,0 +1,74 @@
+// Runs `learning_project student save` with the fault hook switched on for each step
+// of the atomic write and checks that student.txt is never left half written.
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+const OLD_RECORD: &str = "Version:2\nName:john\nAge:25\nStatus:active";
+const NEW_RECORD: &str = "Version:2\nName:jane\nAge:30\nStatus:active";
+
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("lp-atomic-{}-{}", std::process::id(), name));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+// everything in the directory except student.txt, the lock file stays on purpose
+fn leftovers(dir: &PathBuf) -> Vec<String> {
+    fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .filter(|name| name != "student.txt" && name != ".student.txt.lock")
+        .collect()
+}
+
+fn save_jane(dir: &PathBuf, fault: Option<&str>) -> bool {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_learning_project"));
+    command.arg("--data-dir").arg(dir).args(["student", "save", "jane", "30"]);
+    if let Some(step) = fault {
+        command.env("LEARNING_PROJECT_FAULT", step);
+    }
+    command.output().unwrap().status.success()
+}
+
+#[test]
+fn save_replaces_the_file_when_nothing_fails() {
+    let dir = scratch_dir("ok");
+    fs::write(dir.join("student.txt"), OLD_RECORD).unwrap();
+
+    assert!(save_jane(&dir, None));
+    assert_eq!(fs::read_to_string(dir.join("student.txt")).unwrap(), NEW_RECORD);
+    assert_eq!(leftovers(&dir), Vec::<String>::new(), "temp file left behind");
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn old_file_survives_a_failure_before_the_rename() {
+    for step in ["create", "write", "fsync", "rename"] {
+        let dir = scratch_dir(step);
+        fs::write(dir.join("student.txt"), OLD_RECORD).unwrap();
+
+        assert!(!save_jane(&dir, Some(step)), "save should fail at {}", step);
+        assert_eq!(
+            fs::read_to_string(dir.join("student.txt")).unwrap(),
+            OLD_RECORD,
+            "student.txt changed after a fault at {}",
+            step
+        );
+        assert_eq!(leftovers(&dir), Vec::<String>::new(), "temp file left behind at {}", step);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
+
+#[test]
+fn failure_after_the_rename_leaves_the_complete_new_file() {
+    let dir = scratch_dir("dir-fsync");
+    fs::write(dir.join("student.txt"), OLD_RECORD).unwrap();
+
+    // the rename already happened, so the error is reported but the file is whole
+    assert!(!save_jane(&dir, Some("dir-fsync")));
+    assert_eq!(fs::read_to_string(dir.join("student.txt")).unwrap(), NEW_RECORD);
+    fs::remove_dir_all(&dir).unwrap();
+}
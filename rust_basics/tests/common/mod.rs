@@ -0,0 +1,39 @@
+// Helpers the integration tests share: a fresh data directory for each test
+// and a way to run learning_project against it.
+// Not every test file uses all of them, hence the allow.
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+// an empty directory under the system temp dir, the pid keeps parallel runs apart
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("lp-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+// learning_project with its data directory set, ready for more arguments
+pub fn program(dir: &Path) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_learning_project"));
+    command.arg("--data-dir").arg(dir);
+    command
+}
+
+pub fn run(dir: &Path, env: &[(&str, &str)], args: &[&str]) -> Output {
+    program(dir).envs(env.iter().copied()).args(args).output().unwrap()
+}
+
+// what a successful run printed
+pub fn stdout(output: Output) -> String {
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+// what a failed run printed to stderr
+pub fn stderr(output: Output) -> String {
+    assert!(!output.status.success(), "should have failed: {}", String::from_utf8_lossy(&output.stdout));
+    String::from_utf8(output.stderr).unwrap()
+}
@@ -0,0 +1,85 @@
+// Imports CSV files with `roster import-csv` and checks what got in, what was
+// turned away (with its line number) and what `roster export-csv` writes back.
+
+mod common;
+
+use common::{run, stderr, stdout, temp_dir};
+use std::fs;
+use std::path::Path;
+
+fn import(dir: &Path, csv: &str) -> String {
+    let file = dir.join("in.csv");
+    fs::write(&file, csv).unwrap();
+    stdout(run(dir, &[], &["roster", "import-csv", file.to_str().unwrap()]))
+}
+
+#[test]
+fn quoted_fields_and_crlf_survive_a_round_trip() {
+    let dir = temp_dir("csv-round-trip");
+    let csv = "\u{feff}name,age,status\r\n\"Mary \"\"Ann\"\" Jones\",20,active\r\n\"Smith, Bob\",21,pending\r\ncarol,22,inactive\r\n";
+    let imported = import(&dir, csv);
+    let list = stdout(run(&dir, &[], &["roster", "list"]));
+    let out = dir.join("out.csv");
+    stdout(run(&dir, &[], &["roster", "export-csv", out.to_str().unwrap(), "--bom"]));
+    let exported = fs::read_to_string(&out).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(imported, "imported 3 students\n");
+    assert!(list.contains("Mary \"Ann\" Jones (20) active\n"), "{}", list);
+    assert!(list.contains("Smith, Bob (21) pending\n"), "{}", list);
+    assert_eq!(exported, csv);
+}
+
+#[test]
+fn a_bad_row_is_reported_and_the_rest_imported() {
+    let dir = temp_dir("csv-bad-rows");
+    let csv = concat!(
+        "name,age,status\n",
+        // a quoted line break is part of the field, the next row still starts on line 4
+        "\"Bob\nSmith\",21,active\n",
+        "\"Broken,22,active\n",
+        "ann,23,active\n",
+        "\"dave\"x,24,active\n",
+        "eve,25\n",
+        "fay,26,inactive",
+    );
+    let out = import(&dir, csv);
+    let list = stdout(run(&dir, &[], &["roster", "list"]));
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(
+        out,
+        "line 2: rejected: name has a control character at position 3\n\
+         line 4: rejected: quoted field is never closed\n\
+         line 6: rejected: quote in the middle of an unquoted field\n\
+         line 7: rejected: expected 3 fields, found 2\n\
+         imported 2 students\n"
+    );
+    assert_eq!(list, "ann (23) active\nfay (26) inactive\n");
+}
+
+#[test]
+fn an_unclosed_quote_further_down_only_costs_its_own_row() {
+    let dir = temp_dir("csv-unclosed");
+    // the quote opened on line 2 looks closed by the one on line 4
+    let out = import(&dir, "name,age\n\"ann,20\nbob,21\n\"carl\",22\n");
+    let list = stdout(run(&dir, &[], &["roster", "list"]));
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(out, "line 2: rejected: quoted field is never closed\nimported 2 students\n");
+    assert_eq!(list, "bob (21) active\ncarl (22) active\n");
+}
+
+#[test]
+fn a_bad_header_stops_the_import() {
+    let dir = temp_dir("csv-header");
+    let file = dir.join("in.csv");
+    fs::write(&file, "\n\"name,age\n").unwrap();
+    let broken = stderr(run(&dir, &[], &["roster", "import-csv", file.to_str().unwrap()]));
+    fs::write(&file, "first,second\nann,20\n").unwrap();
+    let wrong = stderr(run(&dir, &[], &["roster", "import-csv", file.to_str().unwrap()]));
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(broken.contains("line 2: quoted field is never closed"), "{}", broken);
+    assert!(wrong.contains("expected header \"name,age,status\", found \"first,second\""), "{}", wrong);
+}
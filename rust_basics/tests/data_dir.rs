@@ -0,0 +1,84 @@
+// Runs learning_project with and without --data-dir, LEARNING_PROJECT_DATA_DIR,
+// XDG_DATA_HOME and HOME, and checks where student.txt ends up.
+
+mod common;
+
+use common::{stderr, stdout, temp_dir};
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+
+// none of the settings from the environment running the tests, only `env`
+fn bare(env: &[(&str, &Path)], args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_learning_project"))
+        .env_remove("LEARNING_PROJECT_DATA_DIR")
+        .env_remove("XDG_DATA_HOME")
+        .env_remove("HOME")
+        .envs(env.iter().copied())
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+// `flag` goes before the command, like --data-dir has to
+fn save(env: &[(&str, &Path)], flag: &[&str]) -> String {
+    let mut args = flag.to_vec();
+    args.extend_from_slice(&["student", "save", "ann", "20"]);
+    stdout(bare(env, &args))
+}
+
+#[test]
+fn the_flag_wins_then_the_variable_then_xdg() {
+    let dir = temp_dir("data-dir-order");
+    let (flag, var, xdg, home) = (dir.join("flag"), dir.join("var"), dir.join("xdg"), dir.join("home"));
+    let everything = [("LEARNING_PROJECT_DATA_DIR", var.as_path()), ("XDG_DATA_HOME", &xdg), ("HOME", &home)];
+
+    save(&everything, &["--data-dir", flag.to_str().unwrap()]);
+    let flag_used = flag.join("student.txt").exists() && !var.exists() && !xdg.exists();
+    save(&everything, &[]);
+    let var_used = var.join("student.txt").exists() && !xdg.exists();
+    save(&everything[1..], &[]);
+    let xdg_used = xdg.join("learning_project/student.txt").exists() && !home.exists();
+    // an empty variable counts as not set
+    save(&[("LEARNING_PROJECT_DATA_DIR", Path::new("")), ("HOME", &home)], &[]);
+    let home_used = home.join(".local/share/learning_project/student.txt").exists();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(flag_used, "--data-dir was not used");
+    assert!(var_used, "LEARNING_PROJECT_DATA_DIR was not used");
+    assert!(xdg_used, "XDG_DATA_HOME was not used");
+    assert!(home_used, "~/.local/share was not used");
+}
+
+#[test]
+fn a_relative_xdg_data_home_is_ignored() {
+    let dir = temp_dir("data-dir-relative");
+    let home = dir.join("home");
+    save(&[("XDG_DATA_HOME", Path::new("relative")), ("HOME", &home)], &[]);
+    let used = home.join(".local/share/learning_project/student.txt").exists();
+    fs::remove_dir_all(&dir).unwrap();
+    assert!(used);
+}
+
+#[test]
+fn missing_directories_are_created_and_errors_name_the_path() {
+    let dir = temp_dir("data-dir-errors");
+    let nested = dir.join("a/b/c");
+    save(&[], &["--data-dir", nested.to_str().unwrap()]);
+    let created = nested.join("student.txt").exists();
+    // a file where a directory should be
+    fs::write(dir.join("file"), "").unwrap();
+    let blocked = dir.join("file/sub");
+    let not_a_dir = stderr(bare(&[], &["--data-dir", blocked.to_str().unwrap(), "student", "show"]));
+    let no_value = stderr(bare(&[], &["--data-dir"]));
+    // after the command it is one of the command's arguments
+    let after = stderr(bare(&[("HOME", &dir)], &["student", "show", "--data-dir", nested.to_str().unwrap()]));
+    let no_home = stderr(bare(&[], &["student", "show"]));
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(created);
+    assert!(not_a_dir.starts_with(&format!("error: {}: ", blocked.display())), "{}", not_a_dir);
+    assert_eq!(no_value, "error: --data-dir needs a directory\n");
+    assert_eq!(after, "error: usage: student <save NAME AGE | show>\n");
+    assert_eq!(no_home, "error: HOME is not set, use --data-dir or LEARNING_PROJECT_DATA_DIR\n");
+}
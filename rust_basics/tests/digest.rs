@@ -0,0 +1,76 @@
+// Runs `learning_project digest` on a JSON file with tweets, articles and an email.
+
+use std::fs;
+use std::process::{Command, Output};
+
+const ITEMS: &str = r#"[
+    {"type": "tweet", "item": {"username": "ekom", "content": "traits are *cool*"}, "published": "2026-10-18T07:00:00Z"},
+    {"type": "article", "item": {"headline": "Rust <takes> over", "author": "The Times"}, "priority": "high", "published": "2026-10-17T07:00:00Z"},
+    {"type": "email", "item": {"from": "ekom@example.com", "subject": "learning rust"}, "priority": "low"},
+    {"type": "tweet", "item": {"username": "ann", "content": "hello"}, "published": "2026-10-18T09:00:00Z", "priority": "high"}
+]"#;
+
+fn digest(name: &str, items: &str, args: &[&str]) -> Output {
+    let dir = std::env::temp_dir().join(format!("lp-digest-{}-{}", name, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("items.json");
+    fs::write(&file, items).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_learning_project"))
+        .arg("--data-dir")
+        .arg(&dir)
+        .arg("digest")
+        .arg(&file)
+        .args(args)
+        .output()
+        .unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    output
+}
+
+fn rendered(name: &str, args: &[&str]) -> String {
+    let output = digest(name, ITEMS, args);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn markdown_groups_by_kind_and_puts_high_priority_first() {
+    assert_eq!(
+        rendered("markdown", &["--title", "Roundup"]),
+        "# Roundup\n\
+         \n## Articles\n\
+         \n- **high** Rust \\<takes\\> over by The Times (2026-10-17T07:00:00Z)\n\
+         \n## Emails\n\
+         \n- **low** from:ekom@example.com,subject:learning rust\n\
+         \n## Tweets\n\
+         \n- **high** @ann: hello (2026-10-18T09:00:00Z)\n\
+         - @ekom: traits are \\*cool\\* (2026-10-18T07:00:00Z)\n"
+    );
+}
+
+#[test]
+fn html_by_recency_without_groups_is_escaped() {
+    let html = rendered("html", &["--format", "html", "--group", "none", "--order", "recent"]);
+    let items: Vec<&str> = html.lines().filter(|line| line.starts_with("<li>")).collect();
+    assert_eq!(items.len(), 4);
+    // newest first, the email has no time so it comes last
+    assert!(items[0].contains("@ann: hello"));
+    assert!(items[1].contains("@ekom"));
+    assert!(items[2].contains("Rust &lt;takes&gt; over"));
+    assert!(items[3].starts_with("<li><strong>low</strong> from:ekom@example.com,subject:learning rust</li>"));
+    assert!(!html.contains("<h2>"));
+}
+
+#[test]
+fn text_groups_by_source() {
+    let text = rendered("text", &["--format", "text", "--group", "source"]);
+    assert!(text.starts_with("Daily digest\n============\n\n@ann\n----\n* @ann: hello [high] (2026-10-18T09:00:00Z)\n"), "{}", text);
+    assert!(text.contains("\nThe Times\n---------\n* Rust <takes> over by The Times [high]"), "{}", text);
+}
+
+#[test]
+fn a_bad_item_is_reported() {
+    let output = digest("bad", r#"[{"type": "tweet", "item": {"username": "ekom", "content": "hi"}, "priority": "urgent"}]"#, &[]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("field \"priority\": must be low, medium or high"));
+}
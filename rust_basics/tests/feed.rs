@@ -0,0 +1,162 @@
+// Runs `learning_project feed` on an RSS file and on an Atom feed served by
+// a tiny HTTP server in a thread of the test.
+
+mod common;
+
+use common::{program, stdout, temp_dir};
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::path::Path;
+use std::process::Output;
+use std::thread::{self, JoinHandle};
+
+const RSS: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!-- written by hand -->
+<rss version="2.0" xmlns:dc="http://purl.org/dc/elements/1.1/">
+<channel>
+  <title>The Times</title>
+  <item>
+    <title>Rust &amp; friends &lt;3</title>
+    <link>http://example.com/rust</link>
+    <guid isPermaLink="false">rust-1</guid>
+    <pubDate>Sun, 18 Oct 2026 09:51:11 +0200</pubDate>
+    <description><![CDATA[<p>Traits are <b>cool</b> &amp; fun&nbsp;too</p>]]></description>
+    <dc:creator>Ann Lee</dc:creator>
+  </item>
+  <item>
+    <title>Caf&#233; opens</title>
+    <link>http://example.com/cafe</link>
+    <pubDate>17 Oct 26 07:00 EST</pubDate>
+    <author>bob@example.com (Bob Otu)</author>
+  </item>
+</channel>
+</rss>
+"#;
+
+const ATOM: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>Rust Blog</title>
+  <author><name>The Rust Team</name></author>
+  <entry>
+    <title type="html">Rust 2.0 &lt;em&gt;is out&lt;/em&gt;</title>
+    <id>tag:blog.example.com,2026:rust-2</id>
+    <link rel="alternate" href="http://blog.example.com/rust-2"/>
+    <link rel="self" href="http://blog.example.com/rust-2.atom"/>
+    <updated>2026-10-18T10:00:00+02:00</updated>
+    <summary>Big news.</summary>
+  </entry>
+  <entry>
+    <title>Older post</title>
+    <id>tag:blog.example.com,2026:old</id>
+    <published>2026-10-01T08:00:00Z</published>
+  </entry>
+</feed>
+"#;
+
+// answers `requests` GETs with `body`, the handle gives back the request lines
+fn serve(body: &'static str, requests: usize) -> (String, JoinHandle<Vec<String>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/feed.atom", listener.local_addr().unwrap());
+    let handle = thread::spawn(move || {
+        let mut request_lines = Vec::new();
+        for _ in 0..requests {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" {
+                    break;
+                }
+            }
+            // sent in chunks, the way a lot of servers do
+            let mut writer = stream;
+            let (first, second) = body.split_at(body.len() / 2);
+            let answer = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/atom+xml\r\nTransfer-Encoding: chunked\r\n\r\n{:x}\r\n{}\r\n{:x}\r\n{}\r\n0\r\n\r\n",
+                first.len(),
+                first,
+                second.len(),
+                second
+            );
+            writer.write_all(answer.as_bytes()).unwrap();
+            request_lines.push(request_line.trim_end().to_string());
+        }
+        request_lines
+    });
+    (url, handle)
+}
+
+fn feed(dir: &Path, args: &[&str]) -> Output {
+    program(dir).arg("feed").args(args).output().unwrap()
+}
+
+#[test]
+fn rss_is_decoded_and_only_stored_once() {
+    let dir = temp_dir("feed-rss");
+    let file = dir.join("times.xml");
+    fs::write(&file, RSS).unwrap();
+    let file = file.to_str().unwrap();
+
+    assert!(stdout(feed(&dir, &["ingest", file])).ends_with("2 articles\nadded 2, 0 already known\n"));
+    assert!(stdout(feed(&dir, &["ingest", file])).ends_with("added 0, 2 already known\n"));
+
+    // newest first, times in UTC
+    assert_eq!(
+        stdout(feed(&dir, &["list"])),
+        "2026-10-18T07:51:11Z  Rust & friends <3 by Ann Lee\n    http://example.com/rust\n\
+         2026-10-17T12:00:00Z  Café opens by Bob Otu\n    http://example.com/cafe\n"
+    );
+    let stored = fs::read_to_string(dir.join("articles.jsonl")).unwrap();
+    assert!(stored.contains(r#""summary":"Traits are cool & fun too""#), "{}", stored);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn atom_over_http_is_fetched_and_deduplicated() {
+    let dir = temp_dir("feed-atom");
+    let (url, server) = serve(ATOM, 2);
+
+    assert!(stdout(feed(&dir, &["ingest", &url])).ends_with("added 2, 0 already known\n"));
+    assert!(stdout(feed(&dir, &["ingest", &url])).ends_with("added 0, 2 already known\n"));
+    assert_eq!(server.join().unwrap(), vec!["GET /feed.atom HTTP/1.1"; 2]);
+
+    let list = stdout(feed(&dir, &["list", "--limit", "1"]));
+    assert_eq!(list, "2026-10-18T08:00:00Z  Rust 2.0 is out by The Rust Team\n    http://blog.example.com/rust-2\n");
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn bad_xml_says_where() {
+    let dir = temp_dir("feed-bad");
+    let file = dir.join("bad.xml");
+    fs::write(&file, "<rss><channel>\n<item><title>x</b></item></channel></rss>").unwrap();
+
+    let output = feed(&dir, &["ingest", file.to_str().unwrap()]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("bad XML, line 2, column 18: expected </title>, found </b>"), "{}", stderr);
+    assert!(!dir.join("articles.jsonl").exists());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn a_date_out_of_range_is_left_out() {
+    let dir = temp_dir("feed-year");
+    let file = dir.join("odd.xml");
+    let items: String = ["Sun, 18 Oct 99999999999999 07:51:11 +0000", "99999-10-18T07:51:11Z", "0000-01-01"]
+        .iter()
+        .enumerate()
+        .map(|(n, date)| format!("<item><title>odd {}</title><pubDate>{}</pubDate></item>", n, date))
+        .collect();
+    fs::write(&file, format!("<rss><channel><title>Odd</title>{}</channel></rss>", items)).unwrap();
+
+    // the articles still go in, just without a date
+    assert!(stdout(feed(&dir, &["ingest", file.to_str().unwrap()])).ends_with("added 3, 0 already known\n"));
+    let list = stdout(feed(&dir, &["list"]));
+    assert_eq!(list.lines().filter(|line| line.starts_with("undated  odd ")).count(), 3, "{}", list);
+    fs::remove_dir_all(&dir).unwrap();
+}
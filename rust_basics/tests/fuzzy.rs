@@ -0,0 +1,72 @@
+// Searches a roster with `roster search` and checks that typos, case and
+// accents still find the student, best match first with its score.
+
+mod common;
+
+use common::{run, stderr, stdout, temp_dir};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn roster(dir: &Path, args: &[&str]) -> std::process::Output {
+    let mut all = vec!["roster"];
+    all.extend_from_slice(args);
+    run(dir, &[], &all)
+}
+
+fn class(name: &str) -> PathBuf {
+    let dir = temp_dir(name);
+    for name in ["Charlie", "Charlotte", "José", "Zoë", "Ekom Otu", "Bob", "Rob"] {
+        stdout(roster(&dir, &["add", name, "20"]));
+    }
+    dir
+}
+
+#[test]
+fn typos_case_and_accents_still_match() {
+    let dir = class("fuzzy-match");
+    let found: Vec<String> = ["Charlei", "jose", "ZOE", "otu", "xyz"]
+        .iter()
+        .map(|query| stdout(roster(&dir, &["search", query])))
+        .collect();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(
+        found,
+        [
+            "0.86 Charlie (20) active\n",
+            "1.00 José (20) active\n",
+            "1.00 Zoë (20) active\n",
+            "1.00 Ekom Otu (20) active\n",
+            "no student with a name like \"xyz\"\n",
+        ]
+    );
+}
+
+#[test]
+fn the_best_match_comes_first() {
+    let dir = class("fuzzy-rank");
+    let bob = stdout(roster(&dir, &["search", "bob"]));
+    let charl = stdout(roster(&dir, &["search", "Charlot"]));
+    fs::remove_dir_all(&dir).unwrap();
+
+    // one letter off in a three letter name still counts, but below the exact match
+    assert_eq!(bob, "1.00 Bob (20) active\n0.67 Rob (20) active\n");
+    let names: Vec<&str> = charl.lines().map(|line| line.split(' ').nth(1).unwrap()).collect();
+    assert_eq!(names.first(), Some(&"Charlotte"), "{}", charl);
+    let scores: Vec<f64> = charl.lines().map(|line| line.split(' ').next().unwrap().parse().unwrap()).collect();
+    assert!(scores.windows(2).all(|pair| pair[0] >= pair[1]), "{}", charl);
+}
+
+#[test]
+fn a_typo_in_an_exact_lookup_suggests_the_name() {
+    let dir = class("fuzzy-suggest");
+    let typo = stderr(roster(&dir, &["show", "Charlei"]));
+    let nothing = stderr(roster(&dir, &["show", "xyz"]));
+    // exact lookups ignore case, but not typos
+    let case = stdout(roster(&dir, &["show", "charlie"]));
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(typo, "error: no student named \"Charlei\", did you mean \"Charlie\"?\n");
+    assert_eq!(nothing, "error: no student named \"xyz\"\n");
+    assert_eq!(case, "Charlie (20) active\n");
+}
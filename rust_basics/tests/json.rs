@@ -0,0 +1,82 @@
+// Decodes students, emails, tweets and articles with `learning_project json`
+// and checks that bad input is refused with a message saying what is wrong.
+
+mod common;
+
+use common::{run, stderr, stdout, temp_dir};
+use std::fs;
+use std::path::Path;
+
+fn decode(dir: &Path, kind: &str, json: &str) -> std::process::Output {
+    let file = dir.join(format!("{}.json", kind));
+    fs::write(&file, json).unwrap();
+    run(dir, &[], &["json", kind, file.to_str().unwrap()])
+}
+
+#[test]
+fn numbers_too_big_for_f64_are_refused() {
+    let dir = temp_dir("json-range");
+    let huge = stderr(decode(&dir, "student", r#"{"name": "ann", "age": 1e400}"#));
+    let tiny = stderr(decode(&dir, "student", "[-1e309]"));
+    // the biggest f64 still reads, it's only a bad age
+    let max = stderr(decode(&dir, "student", r#"{"name": "ann", "age": 1.7976931348623157e308}"#));
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(huge, "error: line 1, column 24: number is out of range\n");
+    assert_eq!(tiny, "error: line 1, column 2: number is out of range\n");
+    assert_eq!(max, "error: field \"age\": expected a whole number from 0 to 255\n");
+}
+
+#[test]
+fn a_student_goes_through_the_same_checks_as_create_student() {
+    let dir = temp_dir("json-student");
+    let ok = stdout(decode(&dir, "student", r#"{"name": "ann", "age": 25, "status": "pending"}"#));
+    let legacy = stdout(decode(&dir, "student", r#"{"name": "ann", "age": 25, "is_active": false}"#));
+    let errors: Vec<String> = [
+        r#"{"name": "ann", "age": 2}"#,
+        r#"{"name": "ann", "age": 25.5}"#,
+        r#"{"name": "ann", "age": "25"}"#,
+        r#"{"name": "ann"}"#,
+        r#"{"name": "ann", "age": 25, "nick": "a"}"#,
+        r#"{"name": "ann", "age": 25, "status": "asleep"}"#,
+        r#"{"name": "ann", "age": 25, "status": "active", "is_active": true}"#,
+        r#"{"name": "ann", "age": 25, "status": "inactive", "history": [
+            {"at": "2026-10-18T07:51:11Z", "from": "pending", "to": "active", "reason": "ok"}]}"#,
+    ]
+    .iter()
+    .map(|json| stderr(decode(&dir, "student", json)))
+    .collect();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(ok, "ann (25), pending\n{\"name\":\"ann\",\"age\":25,\"status\":\"pending\",\"history\":[]}\n");
+    assert!(legacy.starts_with("ann (25), inactive\n"), "{}", legacy);
+    assert_eq!(
+        errors,
+        [
+            "error: invalid student: age 2 is too young, students must be at least 18\n",
+            "error: field \"age\": expected a whole number from 0 to 255\n",
+            "error: field \"age\": expected a number\n",
+            "error: field \"age\": missing\n",
+            "error: field \"nick\": unknown field\n",
+            "error: field \"status\": unknown status\n",
+            "error: field \"is_active\": can't be used together with status\n",
+            "error: field \"status\": does not match the end of the history\n",
+        ]
+    );
+}
+
+#[test]
+fn emails_tweets_and_articles_are_checked_too() {
+    let dir = temp_dir("json-items");
+    let article = stdout(decode(&dir, "article", r#"{"headline": "Rust", "author": "me", "published": "2026-10-18T07:51:11Z"}"#));
+    let email = stderr(decode(&dir, "email", r#"{"from": "nobody", "subject": "hi"}"#));
+    let tweet = stderr(decode(&dir, "tweet", r#"{"username": "two words", "content": "hi"}"#));
+    let headline = stderr(decode(&dir, "article", r#"{"headline": " ", "author": "me"}"#));
+    let published = stderr(decode(&dir, "article", r#"{"headline": "Rust", "author": "me", "published": "yesterday"}"#));
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(article, "Rust by me\n{\"headline\":\"Rust\",\"author\":\"me\",\"published\":\"2026-10-18T07:51:11Z\"}\n");
+    assert_eq!(email, "error: field \"from\": column 7: missing @ and domain\n");
+    assert_eq!(tweet, "error: field \"username\": must be one word\n");
+    assert_eq!(headline, "error: field \"headline\": must not be empty\n");
+    assert_eq!(published, "error: field \"published\": expected a time like 2026-10-18T07:51:11Z\n");
+}
@@ -0,0 +1,79 @@
+// Runs `learning_project notify` several times in a row against one data
+// directory, the limiter has to remember what it saw between the processes.
+
+mod common;
+
+use common::{program, stderr, temp_dir};
+use std::fs;
+use std::path::Path;
+use std::process::Output;
+
+// every run notifies into notes.log in the data directory
+fn run(dir: &Path, env: &[(&str, &str)], args: &[&str]) -> Output {
+    let log = dir.join("notes.log");
+    program(dir)
+        .env("LEARNING_PROJECT_NOTIFY", format!("file:{}", log.display()))
+        .envs(env.iter().copied())
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+// what a successful notify says about held back notifications
+fn notify(dir: &Path, env: &[(&str, &str)], text: &str) -> String {
+    let output = run(dir, env, &["notify", text]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stderr).unwrap()
+}
+
+fn delivered(dir: &Path) -> usize {
+    fs::read_to_string(dir.join("notes.log")).map(|log| log.lines().count()).unwrap_or(0)
+}
+
+fn stdout(dir: &Path, args: &[&str]) -> String {
+    common::stdout(run(dir, &[], args))
+}
+
+#[test]
+fn the_same_content_is_only_sent_once() {
+    let dir = temp_dir("limiter-dedup");
+    assert_eq!(notify(&dir, &[], "ann was added"), "");
+    assert_eq!(notify(&dir, &[], "ann was added"), "1 duplicate suppressed\n");
+    assert_eq!(notify(&dir, &[], "bob was added"), "");
+    assert_eq!(delivered(&dir), 2);
+    assert!(stdout(&dir, &["outbox", "limits"]).starts_with("suppressed 1, deferred 0\n"));
+
+    // with the window off it goes out every time
+    assert_eq!(notify(&dir, &[("LEARNING_PROJECT_DEDUP_WINDOW", "off")], "ann was added"), "");
+    assert_eq!(delivered(&dir), 3);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn over_the_rate_it_waits_in_the_outbox() {
+    let dir = temp_dir("limiter-rate");
+    // 2 in a row, then one every half hour
+    let env = [("LEARNING_PROJECT_RATE", "2/3600")];
+    assert_eq!(notify(&dir, &env, "one"), "");
+    assert_eq!(notify(&dir, &env, "two"), "");
+    assert_eq!(notify(&dir, &env, "three"), "1 deferred by the rate limit\n");
+    // a new process still knows the bucket is empty
+    assert_eq!(notify(&dir, &env, "four"), "1 deferred by the rate limit\n");
+    assert_eq!(delivered(&dir), 2);
+
+    let pending = stdout(&dir, &["outbox", "list"]);
+    assert!(pending.contains("notification: three"), "{}", pending);
+    assert!(pending.contains("notification: four"), "{}", pending);
+    // nothing is due yet, they wait for their token
+    assert_eq!(stdout(&dir, &["outbox", "deliver"]), "delivered 0\n");
+    assert!(stdout(&dir, &["outbox", "limits"]).starts_with("suppressed 0, deferred 2\n"));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn a_bad_rate_is_an_error() {
+    let dir = temp_dir("limiter-bad");
+    let error = stderr(run(&dir, &[("LEARNING_PROJECT_CHANNEL_RATE", "lots")], &["notify", "hi"]));
+    assert!(error.contains("LEARNING_PROJECT_CHANNEL_RATE must be COUNT/SECONDS like 10/60 or off"));
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,104 @@
+// Runs two or more copies of learning_project against the same roster.txt
+// and checks that the file lock keeps them from overwriting each other.
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("lp-lock-{}-{}", std::process::id(), name));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn program(dir: &Path) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_learning_project"));
+    command.arg("--data-dir").arg(dir);
+    command
+}
+
+fn roster(dir: &Path, args: &[&str], timeout: &str) -> Output {
+    program(dir)
+        .arg("roster")
+        .args(args)
+        .env("LEARNING_PROJECT_LOCK_TIMEOUT", timeout)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn a_writer_waits_for_the_shell_and_names_its_pid() {
+    let dir = scratch_dir("shell");
+    // the shell holds the roster lock until its input ends
+    let mut shell = program(&dir)
+        .arg("shell")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+        .unwrap();
+    let pid = shell.id().to_string();
+
+    // the lock file gets the holder's PID once the lock is taken
+    let lock_file = dir.join(".roster.txt.lock");
+    let started = Instant::now();
+    while fs::read_to_string(&lock_file).map(|text| text.trim() != pid).unwrap_or(true) {
+        assert!(started.elapsed() < Duration::from_secs(10), "shell never took the lock");
+        thread::sleep(Duration::from_millis(20));
+    }
+
+    for args in [&["add", "bob", "30"][..], &["list"][..]] {
+        let blocked = roster(&dir, args, "0.3");
+        assert!(!blocked.status.success(), "{:?} ran while the shell held the lock", args);
+        let message = String::from_utf8_lossy(&blocked.stderr);
+        assert!(message.contains(&format!("held by process {}", pid)), "unexpected error: {}", message);
+    }
+
+    let mut input = shell.stdin.take().unwrap();
+    input.write_all(b"add ann 25\nsave\nquit\n").unwrap();
+    drop(input);
+    assert!(shell.wait().unwrap().success());
+
+    // with the shell gone the same command goes through, and ann is still there
+    assert!(roster(&dir, &["add", "bob", "30"], "0.3").status.success());
+    let list = roster(&dir, &["list"], "0.3");
+    assert_eq!(String::from_utf8_lossy(&list.stdout), "ann (25) active\nbob (30) active\n");
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn writers_at_the_same_time_all_get_saved() {
+    let dir = scratch_dir("many");
+    let children: Vec<_> = (0..8)
+        .map(|n| {
+            program(&dir)
+                .args(["roster", "add", &format!("student{}", n), "20"])
+                .stdout(Stdio::null())
+                .spawn()
+                .unwrap()
+        })
+        .collect();
+    for mut child in children {
+        assert!(child.wait().unwrap().success());
+    }
+
+    // without the lock, two adds that read the file at the same time lose one student
+    let list = roster(&dir, &["list"], "5");
+    assert_eq!(String::from_utf8_lossy(&list.stdout).lines().count(), 8);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn the_lock_file_is_empty_once_everyone_is_done() {
+    let dir = scratch_dir("cleared");
+    assert!(roster(&dir, &["add", "ann", "25"], "5").status.success());
+    // list only reads, it used to leave its PID behind
+    assert!(roster(&dir, &["list"], "5").status.success());
+    assert!(roster(&dir, &["show", "ann"], "5").status.success());
+    let left = fs::read_to_string(dir.join(".roster.txt.lock")).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(left, "");
+}
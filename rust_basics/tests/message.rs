@@ -0,0 +1,182 @@
+// Runs `learning_project email build` and checks the raw message it prints.
+
+use std::fs;
+use std::process::Command;
+
+fn build(args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_learning_project"))
+        .args(["email", "build", "--from", "ekom@example.com", "--date", "2026-10-18T07:51:11Z"])
+        .args(["--message-id", "fixed@example.com"])
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+// the header block, with folded lines joined back up
+fn headers(message: &str) -> Vec<String> {
+    let (head, _) = message.split_once("\r\n\r\n").unwrap();
+    let mut headers: Vec<String> = Vec::new();
+    for line in head.split("\r\n") {
+        match line.strip_prefix(' ') {
+            Some(rest) => headers.last_mut().unwrap().push_str(&format!(" {}", rest)),
+            None => headers.push(line.to_string()),
+        }
+    }
+    headers
+}
+
+#[test]
+fn a_text_message_has_the_standard_headers() {
+    let message = build(&[
+        "--to",
+        "ann@example.com",
+        "--cc",
+        "bob@example.com",
+        "--bcc",
+        "eve@example.com",
+        "--reply-to",
+        "help@example.com",
+        "--subject",
+        "learning rust",
+        "hello",
+        "there",
+    ]);
+
+    assert_eq!(
+        headers(&message),
+        [
+            "Date: Sun, 18 Oct 2026 07:51:11 +0000",
+            "From: <ekom@example.com>",
+            "Reply-To: <help@example.com>",
+            "To: <ann@example.com>",
+            "Cc: <bob@example.com>",
+            "Subject: learning rust",
+            "Message-ID: <fixed@example.com>",
+            "MIME-Version: 1.0",
+            "Content-Type: text/plain; charset=utf-8",
+            "Content-Transfer-Encoding: 7bit",
+        ]
+    );
+    assert!(message.ends_with("\r\n\r\nhello there"));
+    // Bcc recipients get the message but nobody can see them
+    assert!(!message.contains("eve@example.com"));
+    // every line break is \r\n
+    assert_eq!(message.matches('\n').count(), message.matches("\r\n").count());
+}
+
+#[test]
+fn long_and_non_ascii_headers_are_encoded_and_folded() {
+    let subject = "Grüße aus Köln, this subject is long enough that it has to be folded over lines";
+    let to: Vec<String> = (0..6).map(|n| format!("student{}@example.com", n)).collect();
+    let mut args = vec!["--subject", subject];
+    for address in &to {
+        args.extend(["--to", address.as_str()]);
+    }
+    let message = build(&args);
+    let (head, _) = message.split_once("\r\n\r\n").unwrap();
+
+    for line in head.split("\r\n") {
+        assert!(line.len() <= 78, "header line too long: {:?}", line);
+        assert!(line.is_ascii(), "header line isn't ASCII: {:?}", line);
+    }
+    let subject_header = headers(&message).into_iter().find(|h| h.starts_with("Subject:")).unwrap();
+    assert!(subject_header.starts_with("Subject: =?utf-8?B?R3LDvMOfZSBhdXMgS8O2bG4s"), "{}", subject_header);
+    let to_header = headers(&message).into_iter().find(|h| h.starts_with("To:")).unwrap();
+    assert!(to_header.ends_with("<student4@example.com>, <student5@example.com>"), "{}", to_header);
+}
+
+#[test]
+fn html_and_attachments_make_a_multipart_message() {
+    let dir = std::env::temp_dir().join(format!("lp-message-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let html = dir.join("body.html");
+    let attachment = dir.join("report.pdf");
+    fs::write(&html, "<p>hello</p>").unwrap();
+    fs::write(&attachment, b"%PDF\x00\x01").unwrap();
+
+    let message = build(&[
+        "--to",
+        "ann@example.com",
+        "--subject",
+        "report",
+        "--html",
+        html.to_str().unwrap(),
+        "--attach",
+        attachment.to_str().unwrap(),
+        "hello",
+    ]);
+    fs::remove_dir_all(&dir).unwrap();
+
+    let content_type = headers(&message).into_iter().find(|h| h.starts_with("Content-Type:")).unwrap();
+    let boundary = content_type.split("boundary=\"").nth(1).unwrap().trim_end_matches('"').to_string();
+    assert!(content_type.starts_with("Content-Type: multipart/mixed;"));
+    assert!(message.ends_with(&format!("--{}--", boundary)));
+
+    // the text and html versions side by side, then the attachment in base64
+    assert!(message.contains("Content-Type: multipart/alternative;"));
+    let text = message.find("Content-Type: text/plain; charset=utf-8").unwrap();
+    let html = message.find("Content-Type: text/html; charset=utf-8").unwrap();
+    let pdf = message.find("Content-Type: application/pdf; name=\"report.pdf\"").unwrap();
+    assert!(text < html && html < pdf);
+    assert!(message.contains("Content-Disposition: attachment; filename=\"report.pdf\""));
+    assert!(message.contains("\r\n\r\nJVBERgAB\r\n"));
+}
+
+#[test]
+fn non_ascii_attachment_names_use_rfc_2231() {
+    let dir = std::env::temp_dir().join(format!("lp-message-names-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let short = dir.join("Grüße….pdf");
+    let long = dir.join(format!("{}.txt", "Übung ".repeat(12).trim_end()));
+    fs::write(&short, b"%PDF").unwrap();
+    fs::write(&long, b"notes").unwrap();
+
+    let message = build(&[
+        "--to",
+        "ann@example.com",
+        "--subject",
+        "notes",
+        "--attach",
+        short.to_str().unwrap(),
+        "--attach",
+        long.to_str().unwrap(),
+        "hello",
+    ]);
+    fs::remove_dir_all(&dir).unwrap();
+
+    // a plain ASCII name for old mail programs, then the real one, never an encoded word in quotes
+    let unfolded = message.replace("\r\n ", " ");
+    assert!(unfolded.contains("Content-Type: application/pdf; name=\"Gr__e_.pdf\""), "{}", message);
+    assert!(
+        unfolded.contains("Content-Disposition: attachment; filename=\"Gr__e_.pdf\"; filename*=UTF-8''Gr%C3%BC%C3%9Fe%E2%80%A6.pdf\r\n"),
+        "{}",
+        message
+    );
+    assert!(!message.contains("=?utf-8?"));
+
+    // the long name is cut into numbered pieces so every line stays under 78 characters
+    for line in message.lines() {
+        assert!(line.len() <= 78, "line too long: {:?}", line);
+    }
+    let disposition = unfolded.lines().find(|line| line.contains("filename=\"_bung")).unwrap();
+    let pieces: Vec<&str> = disposition.split("; ").skip(2).collect();
+    assert!(pieces.len() > 1, "{}", disposition);
+    let mut joined = String::new();
+    for (i, piece) in pieces.iter().enumerate() {
+        let value = piece.strip_prefix(&format!("filename*{}*=", i)).unwrap();
+        joined.push_str(value.trim_end());
+    }
+    assert_eq!(joined, format!("UTF-8''{}.txt", "%C3%9Cbung%20".repeat(12).trim_end_matches("%20")));
+}
+
+#[test]
+fn a_message_needs_a_recipient() {
+    let output = Command::new(env!("CARGO_BIN_EXE_learning_project"))
+        .args(["email", "build", "--from", "ekom@example.com", "--subject", "hi", "hello"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("message has no To, Cc or Bcc recipients"));
+}
@@ -0,0 +1,82 @@
+// Runs `learning_project migrate` on version 1 files and checks the backups,
+// and that nothing else writes over a file that hasn't been migrated yet.
+
+mod common;
+
+use common::{run, stderr, stdout, temp_dir};
+use std::fs;
+use std::path::Path;
+
+const OLD_STUDENT: &str = "Name:john\nAge:25\nis_activetrue";
+const OLD_ROSTER: &str = "Name:ann\nAge:20\nis_active:true\n\nName:bob\nAge:30\nis_active:false\n";
+
+fn files(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .filter(|name| !name.ends_with(".lock"))
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn migrate_keeps_a_backup_and_only_runs_once() {
+    let dir = temp_dir("migrate-backup");
+    fs::write(dir.join("student.txt"), OLD_STUDENT).unwrap();
+    fs::write(dir.join("roster.txt"), OLD_ROSTER).unwrap();
+
+    let out = stdout(run(&dir, &[], &["migrate"]));
+    assert!(out.contains("student.txt: version 1 -> 2, original kept in "), "{}", out);
+    assert!(out.contains("roster.txt: version 1 -> 2, original kept in "), "{}", out);
+    assert_eq!(fs::read_to_string(dir.join("student.txt")).unwrap(), "Version:2\nName:john\nAge:25\nStatus:active");
+    assert_eq!(
+        fs::read_to_string(dir.join("roster.txt")).unwrap(),
+        "Version:2\nName:ann\nAge:20\nStatus:active\n\nName:bob\nAge:30\nStatus:inactive\n"
+    );
+    assert_eq!(fs::read_to_string(dir.join("student.txt.v1.bak")).unwrap(), OLD_STUDENT);
+    assert_eq!(fs::read_to_string(dir.join("roster.txt.v1.bak")).unwrap(), OLD_ROSTER);
+
+    // the second time there is nothing to do and no new backup
+    let out = stdout(run(&dir, &[], &["migrate"]));
+    assert!(out.contains("student.txt: already version 2\n") && out.contains("roster.txt: already version 2\n"), "{}", out);
+    assert_eq!(files(&dir), ["roster.txt", "roster.txt.v1.bak", "student.txt", "student.txt.v1.bak"]);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn a_version_from_the_future_is_left_alone() {
+    let dir = temp_dir("migrate-future");
+    let newer = "Version:9\nName:john\nAge:25\nStatus:active\nMentor:ann\n";
+    fs::write(dir.join("student.txt"), newer).unwrap();
+
+    let error = stderr(run(&dir, &[], &["migrate"]));
+    assert!(error.contains("student.txt: line 1: file is version 9 but this program only understands up to version 2"), "{}", error);
+    assert_eq!(fs::read_to_string(dir.join("student.txt")).unwrap(), newer);
+    assert_eq!(files(&dir), ["student.txt"]);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn old_files_can_be_read_but_not_saved_over() {
+    let dir = temp_dir("migrate-refuse");
+    fs::write(dir.join("student.txt"), OLD_STUDENT).unwrap();
+    fs::write(dir.join("roster.txt"), OLD_ROSTER).unwrap();
+
+    // reading upgrades in memory only
+    assert_eq!(stdout(run(&dir, &[], &["roster", "list"])), "ann (20) active\nbob (30) inactive\n");
+    for args in [&["student", "save", "jane", "30"][..], &["roster", "add", "cy", "20"][..]] {
+        let output = run(&dir, &[], args);
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "", "{:?} printed something", args);
+        let error = stderr(output);
+        assert!(error.contains("is version 1, run `learning_project migrate` first"), "{:?}: {}", args, error);
+    }
+    assert_eq!(fs::read_to_string(dir.join("student.txt")).unwrap(), OLD_STUDENT);
+    assert_eq!(fs::read_to_string(dir.join("roster.txt")).unwrap(), OLD_ROSTER);
+
+    // after migrate the same commands go through
+    stdout(run(&dir, &[], &["migrate"]));
+    stdout(run(&dir, &[], &["student", "save", "jane", "30"]));
+    assert_eq!(stdout(run(&dir, &[], &["roster", "add", "cy", "20"])), "cy (20) active\n");
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,120 @@
+// Runs `learning_project notify` with several sinks in LEARNING_PROJECT_NOTIFY,
+// the webhook one pointed at a tiny HTTP server in a thread of the test.
+// Each test has its own data directory, the outbox lives there.
+
+mod common;
+
+use common::{program, temp_dir};
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::path::Path;
+use std::process::Output;
+use std::thread::{self, JoinHandle};
+
+// answers one request with `status`, the handle gives back the request line and body
+fn start(status: &'static str) -> (String, JoinHandle<(String, String)>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/hooks/notes", listener.local_addr().unwrap());
+    let handle = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line).unwrap();
+        let mut length = 0;
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if line == "\r\n" {
+                break;
+            }
+            if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                length = value.trim().parse().unwrap();
+            }
+        }
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).unwrap();
+        let mut writer = stream;
+        writer.write_all(format!("HTTP/1.1 {}\r\nContent-Length: 0\r\n\r\n", status).as_bytes()).unwrap();
+        (request_line.trim_end().to_string(), String::from_utf8(body).unwrap())
+    });
+    (url, handle)
+}
+
+fn notify(dir: &Path, sinks: &str, args: &[&str]) -> Output {
+    program(dir).env("LEARNING_PROJECT_NOTIFY", sinks).arg("notify").args(args).output().unwrap()
+}
+
+fn maildir_messages(dir: &Path) -> Vec<String> {
+    fs::read_dir(dir.join("new")).unwrap().map(|entry| fs::read_to_string(entry.unwrap().path()).unwrap()).collect()
+}
+
+#[test]
+fn every_sink_gets_the_notification_even_when_one_fails() {
+    let dir = temp_dir("notify-all");
+    let (url, server) = start("204 No Content");
+    let log = dir.join("notes.log");
+    let mbox = dir.join("notes.mbox");
+    let maildir = dir.join("Maildir");
+    // the broken sink is in the middle, the ones after it still have to run
+    let sinks = format!(
+        "stdout,file:{},nonsense:1,webhook:{},mbox:{},maildir:{}",
+        log.display(),
+        url,
+        mbox.display(),
+        maildir.display()
+    );
+
+    let output = notify(&dir, &sinks, &["--title", "roster", "ann was added"]);
+    let (request_line, body) = server.join().unwrap();
+    let log = fs::read_to_string(&log).unwrap();
+    let mbox = fs::read_to_string(&mbox).unwrap();
+    let messages = maildir_messages(&maildir);
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.starts_with("error: nonsense:1: unknown sink"), "{}", stderr);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "roster: ann was added\n");
+
+    assert!(log.ends_with(" roster: ann was added\n"), "{:?}", log);
+    assert_eq!(request_line, "POST /hooks/notes HTTP/1.1");
+    assert!(body.starts_with(r#"{"title":"roster","text":"ann was added","at":""#), "{}", body);
+    assert!(mbox.starts_with("From learning_project@localhost "), "{}", mbox);
+    assert!(mbox.contains("\nSubject: roster\n") && mbox.ends_with("\n\nann was added\n\n"), "{:?}", mbox);
+    assert_eq!(messages.len(), 1);
+    assert!(messages[0].contains("Subject: roster\n") && messages[0].ends_with("\n\nann was added\n"));
+}
+
+#[test]
+fn a_webhook_that_answers_with_an_error_is_reported() {
+    let dir = temp_dir("notify-webhook");
+    let (url, server) = start("500 Internal Server Error");
+    let log = dir.join("notes.log");
+
+    let output = notify(&dir, &format!("webhook:{},file:{}", url, log.display()), &["hello"]);
+    server.join().unwrap();
+    let log = fs::read_to_string(&log).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains(&format!("webhook:{}: the server answered \"HTTP/1.1 500 Internal Server Error\"", url)), "{}", stderr);
+    assert!(log.ends_with(" notification: hello\n"));
+}
+
+#[test]
+fn mbox_quotes_lines_that_look_like_a_new_message() {
+    let dir = temp_dir("notify-mbox");
+    let mbox = dir.join("notes.mbox");
+    let sinks = format!("mbox:{}", mbox.display());
+
+    assert!(notify(&dir, &sinks, &["From here on"]).status.success());
+    assert!(notify(&dir, &sinks, &["second"]).status.success());
+    let text = fs::read_to_string(&mbox).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    // two messages, so two separator lines, the text's "From " is quoted
+    assert_eq!(text.lines().filter(|line| line.starts_with("From ")).count(), 2);
+    assert!(text.contains("\n>From here on\n"));
+}
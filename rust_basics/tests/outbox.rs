@@ -0,0 +1,79 @@
+// Checks the notification outbox: a failed sink is retried from the file in the
+// data directory by a later process, and gives up into the dead letter file.
+// The sinks are files in folders that don't exist yet, so they fail until the test makes the folder.
+
+mod common;
+
+use common::{run, stderr, stdout, temp_dir};
+use std::fs;
+
+#[test]
+fn pending_notifications_are_delivered_by_a_later_process() {
+    let dir = temp_dir("outbox-restart");
+    let good = dir.join("good.log");
+    let later = dir.join("later").join("notes.log");
+    let sinks = format!("file:{},file:{}", good.display(), later.display());
+    let env = [("LEARNING_PROJECT_NOTIFY", sinks.as_str()), ("LEARNING_PROJECT_OUTBOX_BACKOFF", "0")];
+
+    assert!(stderr(run(&dir, &env, &["notify", "ann was added"])).contains("(attempt 1, trying again at "));
+    let pending = stdout(run(&dir, &[], &["outbox", "list"]));
+    assert!(pending.contains("notification: ann was added"), "{}", pending);
+    assert!(pending.contains("1 attempts"), "{}", pending);
+
+    // the folder turns up, the next worker delivers to the sink that failed
+    fs::create_dir_all(later.parent().unwrap()).unwrap();
+    assert!(stdout(run(&dir, &[], &["outbox", "work"])).contains("delivered 1"));
+    assert_eq!(stdout(run(&dir, &[], &["outbox", "list"])), "(none)\n");
+
+    let good = fs::read_to_string(&good).unwrap();
+    let later = fs::read_to_string(&later).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    // the sink that worked the first time isn't sent it again
+    assert_eq!(good.lines().count(), 1);
+    assert!(later.ends_with(" notification: ann was added\n"));
+}
+
+#[test]
+fn retries_wait_longer_each_time() {
+    let dir = temp_dir("outbox-backoff");
+    let missing = dir.join("missing").join("notes.log");
+    let sinks = format!("file:{}", missing.display());
+    let env = [("LEARNING_PROJECT_NOTIFY", sinks.as_str()), ("LEARNING_PROJECT_OUTBOX_BACKOFF", "600")];
+
+    stderr(run(&dir, &env, &["notify", "hello"]));
+    // not due yet, so nothing is tried
+    assert_eq!(stdout(run(&dir, &env, &["outbox", "deliver"])), "delivered 0\n");
+    let record = fs::read_to_string(dir.join("outbox.jsonl")).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    let time = |field: &str| {
+        let start = record.find(&format!("\"{}\":\"", field)).unwrap() + field.len() + 4;
+        let text = &record[start..start + 20];
+        let (h, m, s): (i64, i64, i64) = (text[11..13].parse().unwrap(), text[14..16].parse().unwrap(), text[17..19].parse().unwrap());
+        h * 3600 + m * 60 + s
+    };
+    // 600s with jitter is somewhere from 300 to 600 seconds (plus a second for the attempt itself)
+    let wait = (time("next_attempt") - time("at")).rem_euclid(86_400);
+    assert!((300..=601).contains(&wait), "waits {}s: {}", wait, record);
+}
+
+#[test]
+fn a_notification_that_keeps_failing_goes_to_the_dead_letter_file() {
+    let dir = temp_dir("outbox-dead");
+    let env = [
+        ("LEARNING_PROJECT_NOTIFY", "nonsense"),
+        ("LEARNING_PROJECT_OUTBOX_BACKOFF", "0"),
+        ("LEARNING_PROJECT_OUTBOX_ATTEMPTS", "2"),
+    ];
+
+    stderr(run(&dir, &env, &["notify", "hello"]));
+    let delivered = String::from_utf8(run(&dir, &env, &["outbox", "deliver"]).stdout).unwrap();
+    let dead = stdout(run(&dir, &env, &["outbox", "dead"]));
+    let pending = stdout(run(&dir, &env, &["outbox", "list"]));
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(delivered.contains("(gave up after 2 attempts, moved to outbox.dead.jsonl)"), "{}", delivered);
+    assert_eq!(pending, "(none)\n");
+    assert!(dead.contains("notification: hello  to nonsense, 2 attempts"), "{}", dead);
+    assert!(dead.contains("last error: nonsense: unknown sink"), "{}", dead);
+}
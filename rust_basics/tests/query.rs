@@ -0,0 +1,107 @@
+// Runs `roster query` against a small roster to check the filter syntax,
+// quoted values, sorting and paging, and the messages for bad filters.
+
+mod common;
+
+use common::{run, stderr, stdout, temp_dir};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn roster(dir: &Path, args: &[&str]) -> std::process::Output {
+    let mut all = vec!["roster"];
+    all.extend_from_slice(args);
+    run(dir, &[], &all)
+}
+
+fn class(name: &str) -> PathBuf {
+    let dir = temp_dir(name);
+    for (name, age) in [("Mary Ann", "20"), ("Mary", "30"), ("Jo \"Jo\" Smith", "40"), ("john", "18")] {
+        stdout(roster(&dir, &["add", name, age]));
+    }
+    stdout(roster(&dir, &["suspend", "Mary"]));
+    dir
+}
+
+fn query(dir: &Path, filter: &str) -> String {
+    stdout(roster(dir, &["query", filter]))
+}
+
+#[test]
+fn filters_sorting_and_paging() {
+    let dir = class("query-filters");
+    let young = query(&dir, "age=18..25 sort=-age");
+    let older = query(&dir, "age>20 active=true");
+    let starts = query(&dir, "name^=J sort=name");
+    let paged = query(&dir, "sort=age limit=2 offset=1");
+    let status = query(&dir, "status=inactive");
+    let count = stdout(roster(&dir, &["query", "--count", "name~=mary"]));
+    let stats = stdout(roster(&dir, &["query", "--stats", "age<=30"]));
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(young, "Mary Ann (20) active\njohn (18) active\n");
+    assert_eq!(older, "Jo \"Jo\" Smith (40) active\n");
+    assert_eq!(starts, "Jo \"Jo\" Smith (40) active\njohn (18) active\n");
+    assert_eq!(paged, "Mary Ann (20) active\nMary (30) inactive\n");
+    assert_eq!(status, "Mary (30) inactive\n");
+    assert_eq!(count, "2\n");
+    assert_eq!(stats, "count: 3, average age: 22.7, youngest: 18, oldest: 30\n");
+}
+
+#[test]
+fn quoted_values_can_have_spaces_and_quotes() {
+    let dir = class("query-quoted");
+    let exact = query(&dir, "name=\"Mary Ann\"");
+    // without the quotes "Ann" is a term of its own
+    let unquoted = stderr(roster(&dir, &["query", "name=Mary Ann"]));
+    let contains = query(&dir, "name~=\"ry a\" sort=name");
+    let escaped = query(&dir, r#"name="jo \"jo\" smith""#);
+    let operator = query(&dir, "name=\"a=b\"");
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(exact, "Mary Ann (20) active\n");
+    assert_eq!(unquoted, "error: in \"Ann\": expected KEY=VALUE\n");
+    assert_eq!(contains, "Mary Ann (20) active\n");
+    assert_eq!(escaped, "Jo \"Jo\" Smith (40) active\n");
+    assert_eq!(operator, "");
+}
+
+#[test]
+fn bad_filters_say_what_is_wrong() {
+    let dir = class("query-errors");
+    let errors: Vec<String> = [
+        "name=\"Mary",
+        "name=\"\"",
+        "nothing",
+        "colour=red",
+        "age=abc",
+        "age=30..20",
+        "age>255",
+        "age<0",
+        "active=maybe",
+        "status=asleep",
+        "sort=height",
+        "limit=-1",
+    ]
+    .iter()
+    .map(|filter| stderr(roster(&dir, &["query", filter])))
+    .collect();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(
+        errors,
+        [
+            "error: in \"name=\\\"Mary\": quote is never closed\n",
+            "error: in \"name=\\\"\\\"\": missing value\n",
+            "error: in \"nothing\": expected KEY=VALUE\n",
+            "error: in \"colour=red\": unknown filter\n",
+            "error: in \"age=abc\": expected a number from 0 to 255\n",
+            "error: in \"age=30..20\": range is backwards\n",
+            "error: in \"age>255\": no age is above 255\n",
+            "error: in \"age<0\": no age is below 0\n",
+            "error: in \"active=maybe\": expected true or false\n",
+            "error: in \"status=asleep\": expected active, inactive, pending or withdrawn\n",
+            "error: in \"sort=height\": can sort by name, age or status\n",
+            "error: in \"limit=-1\": expected a number\n",
+        ]
+    );
+}
@@ -0,0 +1,77 @@
+// Adds, updates and removes students with `learning_project roster` and checks
+// that every record is kept in roster.txt and read back by the next run.
+
+mod common;
+
+use common::{run, stderr, stdout, temp_dir};
+use std::fs;
+use std::path::Path;
+
+fn roster(dir: &Path, args: &[&str]) -> std::process::Output {
+    let mut all = vec!["roster"];
+    all.extend_from_slice(args);
+    run(dir, &[], &all)
+}
+
+#[test]
+fn every_student_is_kept_between_runs() {
+    let dir = temp_dir("roster-store");
+    assert_eq!(stdout(roster(&dir, &["list"])), "");
+    assert_eq!(stdout(roster(&dir, &["add", "ann", "20"])), "ann (20) active\n");
+    assert_eq!(stdout(roster(&dir, &["add", "bob", "30", "--pending"])), "bob (30) pending\n");
+    assert_eq!(stdout(roster(&dir, &["add", "Ekom Otu", "22"])), "Ekom Otu (22) active\n");
+    assert_eq!(stdout(roster(&dir, &["show", "bob"])), "bob (30) pending\n");
+    assert_eq!(stdout(roster(&dir, &["update", "ann", "--name", "anna", "--age", "21"])), "anna (21) active\n");
+    assert_eq!(stdout(roster(&dir, &["remove", "bob"])), "removed bob\n");
+    let list = stdout(roster(&dir, &["list"]));
+    let file = fs::read_to_string(dir.join("roster.txt")).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(list, "anna (21) active\nEkom Otu (22) active\n");
+    assert_eq!(file, "Version:2\nName:anna\nAge:21\nStatus:active\n\nName:Ekom Otu\nAge:22\nStatus:active\n");
+}
+
+#[test]
+fn names_are_unique_and_create_student_rules_still_apply() {
+    let dir = temp_dir("roster-rules");
+    stdout(roster(&dir, &["add", "ann", "20"]));
+    stdout(roster(&dir, &["add", "bob", "30"]));
+    let errors: Vec<String> = [
+        &["add", "ANN", "22"][..],
+        &["add", "kid", "12"],
+        &["update", "bob", "--name", "Ann"],
+        &["update", "bob", "--age", "10"],
+        &["show", "zed"],
+        &["remove", "zed"],
+        &["add", "ann"],
+    ]
+    .iter()
+    .map(|args| stderr(roster(&dir, args)))
+    .collect();
+    let list = stdout(roster(&dir, &["list"]));
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(errors[0], "error: a student named \"ANN\" already exists\n");
+    assert_eq!(errors[1], "error: invalid student: age 12 is too young, students must be at least 18\n");
+    assert_eq!(errors[2], "error: a student named \"Ann\" already exists\n");
+    assert_eq!(errors[3], "error: invalid student: age 10 is too young, students must be at least 18\n");
+    assert_eq!(errors[4], "error: no student named \"zed\"\n");
+    assert_eq!(errors[5], "error: no student named \"zed\"\n");
+    assert!(errors[6].starts_with("error: usage: roster <add NAME AGE"), "{}", errors[6]);
+    // nothing that failed was saved
+    assert_eq!(list, "ann (20) active\nbob (30) active\n");
+}
+
+#[test]
+fn a_bad_roster_file_is_refused() {
+    let dir = temp_dir("roster-bad-file");
+    let file = dir.join("roster.txt");
+    fs::write(&file, "Version:2\nName:ann\nAge:20\nStatus:active\n\nName:bob\nAge:x\nStatus:active\n").unwrap();
+    let malformed = stderr(roster(&dir, &["list"]));
+    fs::write(&file, "Version:2\nName:ann\nAge:20\nStatus:active\n\nName:ANN\nAge:30\nStatus:active\n").unwrap();
+    let duplicate = stderr(roster(&dir, &["list"]));
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(malformed, "error: roster file is malformed: line 7: Age must be a number from 0 to 255, got \"x\"\n");
+    assert_eq!(duplicate, "error: a student named \"ANN\" already exists\n");
+}
@@ -0,0 +1,88 @@
+// Drives `learning_project shell`, from a script file with --script and with
+// commands piped into stdin, and checks the output and what was saved.
+
+mod common;
+
+use common::{program, run, stderr, stdout, temp_dir};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Output, Stdio};
+
+fn script(dir: &Path, commands: &str) -> Output {
+    let file = dir.join("script.txt");
+    fs::write(&file, commands).unwrap();
+    run(dir, &[], &["shell", "--script", file.to_str().unwrap()])
+}
+
+// stdin is a pipe and not a terminal, so there is no prompt
+fn piped(dir: &Path, input: &str) -> Output {
+    let mut child = program(dir).arg("shell").stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn().unwrap();
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn a_script_runs_every_command_and_saves() {
+    let dir = temp_dir("shell-script");
+    let out = stdout(script(
+        &dir,
+        "# a comment\n\
+         add \"Ekom Otu\" 22\n\
+         add ann 20\n\
+         \n\
+         list\n\
+         edit ann name=anna age=21\n\
+         deactivate anna left early\n\
+         delete \"Ekom Otu\"\n\
+         save\n",
+    ));
+    let roster = stdout(run(&dir, &[], &["roster", "history", "anna"]));
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(
+        out,
+        "Ekom Otu (22) active\n\
+         ann (20) active\n\
+         Ekom Otu (22) active\n\
+         ann (20) active\n\
+         anna (21) active\n\
+         anna (21) inactive\n\
+         deleted Ekom Otu\n\
+         saved 1 students\n"
+    );
+    assert!(roster.ends_with(" active -> inactive: left early\nnow inactive\n"), "{}", roster);
+}
+
+#[test]
+fn a_script_stops_at_the_first_error_and_at_quit() {
+    let dir = temp_dir("shell-script-stop");
+    let error = stderr(script(&dir, "add bob 30\nsave\nfrobnicate\nadd carl 40\nsave\n"));
+    let quit = stdout(script(&dir, "add dan 50\nquit\nsave\n"));
+    let list = stdout(run(&dir, &[], &["roster", "list"]));
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(error.ends_with("script.txt line 3: unknown command or wrong arguments for \"frobnicate\", try help\n"), "{}", error);
+    assert_eq!(quit, "dan (50) active\nscript ended with unsaved changes\n");
+    assert_eq!(list, "bob (30) active\n");
+}
+
+#[test]
+fn piped_input_keeps_going_after_errors_and_ends_at_eof() {
+    let dir = temp_dir("shell-piped");
+    let out = stdout(piped(&dir, "add eve 30\nadd kid 12\nadd eve 31\nedit eve colour=red\nlisx\u{7f}t\n"));
+    let list = stdout(run(&dir, &[], &["roster", "list"]));
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(
+        out,
+        "eve (30) active\n\
+         error: invalid student: age 12 is too young, students must be at least 18\n\
+         error: a student named \"eve\" already exists\n\
+         error: expected name=NEW or age=AGE, got \"colour=red\"\n\
+         eve (30) active\n\
+         unsaved changes were not written, use save next time\n"
+    );
+    // nothing was saved
+    assert_eq!(list, "");
+}
@@ -0,0 +1,152 @@
+// Runs `learning_project email send` against a tiny SMTP server in a thread of
+// the test, which records the conversation and refuses some recipients.
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::process::{Command, Output};
+use std::thread::{self, JoinHandle};
+
+// how the stand-in server behaves
+struct Server {
+    // EHLO lines after the greeting, e.g. "AUTH PLAIN"
+    features: &'static [&'static str],
+    // the AUTH PLAIN token it accepts
+    token: Option<&'static str>,
+}
+
+// starts the server for one connection, the handle gives back every line the client sent
+fn start(server: Server) -> (String, JoinHandle<Vec<String>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    let handle = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut writer = stream;
+        let mut received = Vec::new();
+        let mut reply = |text: &str| writer.write_all(format!("{}\r\n", text).as_bytes()).unwrap();
+
+        reply("220 stand-in ready");
+        let mut in_data = false;
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line).unwrap() == 0 {
+                break;
+            }
+            let line = line.trim_end_matches("\r\n").to_string();
+            received.push(line.clone());
+            if in_data {
+                if line == "." {
+                    in_data = false;
+                    reply("250 queued");
+                }
+                continue;
+            }
+            match line.split_whitespace().next().unwrap_or("") {
+                "EHLO" => {
+                    let mut lines = vec!["stand-in"];
+                    lines.extend(server.features);
+                    for (i, text) in lines.iter().enumerate() {
+                        let separator = if i + 1 == lines.len() { ' ' } else { '-' };
+                        reply(&format!("250{}{}", separator, text));
+                    }
+                }
+                "AUTH" if Some(line.trim_start_matches("AUTH PLAIN ")) == server.token => reply("235 welcome"),
+                "AUTH" => reply("535 bad login"),
+                "MAIL" => reply("250 ok"),
+                "RCPT" if line.contains("nobody@") => reply("550 no such user"),
+                "RCPT" if line.contains("full@") => reply("452 mailbox full"),
+                "RCPT" => reply("250 ok"),
+                "DATA" => {
+                    in_data = true;
+                    reply("354 go ahead");
+                }
+                "QUIT" => {
+                    reply("221 bye");
+                    break;
+                }
+                _ => reply("500 what?"),
+            }
+        }
+        received
+    });
+    (address, handle)
+}
+
+fn send(server: &str, to: &[&str], login: Option<(&str, &str)>, body: &str) -> Output {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_learning_project"));
+    command
+        .args(["email", "send", "--from", "ekom@example.com", "--subject", "hello", "--smtp", server])
+        .env_remove("LEARNING_PROJECT_SMTP_USER")
+        .env_remove("LEARNING_PROJECT_SMTP_PASSWORD");
+    for address in to {
+        command.args(["--to", address]);
+    }
+    if let Some((user, password)) = login {
+        command.env("LEARNING_PROJECT_SMTP_USER", user).env("LEARNING_PROJECT_SMTP_PASSWORD", password);
+    }
+    command.arg(body).output().unwrap()
+}
+
+#[test]
+fn sends_the_message_and_reports_each_refused_recipient() {
+    let (address, server) = start(Server { features: &[], token: None });
+    let output = send(&address, &["ann@example.com", "nobody@example.com", "full@example.com"], None, ".hi");
+    let received = server.join().unwrap();
+
+    assert!(!output.status.success(), "refused recipients should fail the command");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("sent to ann@example.com"), "{}", stdout);
+    assert!(stdout.contains("refused nobody@example.com: 550 no such user"), "{}", stdout);
+    assert!(stdout.contains("refused full@example.com: 452 mailbox full"), "{}", stdout);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("2 of 3 recipients were refused"));
+
+    assert_eq!(received[0], "EHLO localhost");
+    assert_eq!(received[1], "MAIL FROM:<ekom@example.com>");
+    assert_eq!(received[2], "RCPT TO:<ann@example.com>");
+    assert!(received.contains(&String::from("Subject: hello")));
+    // a body line starting with "." gets a second one so it can't end the message early
+    assert!(received.contains(&String::from("..hi")));
+    assert_eq!(received.last().unwrap(), "QUIT");
+}
+
+#[test]
+fn nothing_is_sent_when_every_recipient_is_refused() {
+    let (address, server) = start(Server { features: &[], token: None });
+    let output = send(&address, &["nobody@example.com"], None, "hi");
+    let received = server.join().unwrap();
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("every recipient was refused; nobody@example.com: 550"));
+    assert!(!received.contains(&String::from("DATA")));
+}
+
+#[test]
+fn logs_in_with_auth_plain() {
+    // base64 of "\0ekom\0secret"
+    let (address, server) = start(Server { features: &["SIZE 1000", "AUTH LOGIN PLAIN"], token: Some("AGVrb20Ac2VjcmV0") });
+    let output = send(&address, &["ann@example.com"], Some(("ekom", "secret")), "hi");
+    let received = server.join().unwrap();
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(received[1], "AUTH PLAIN AGVrb20Ac2VjcmV0");
+}
+
+#[test]
+fn a_bad_login_is_an_error() {
+    let (address, server) = start(Server { features: &["AUTH PLAIN"], token: Some("AGVrb20Ac2VjcmV0") });
+    let output = send(&address, &["ann@example.com"], Some(("ekom", "wrong")), "hi");
+    drop(server);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("mail server refused AUTH PLAIN: 535 bad login"));
+}
+
+#[test]
+fn a_login_needs_a_server_that_offers_auth() {
+    let (address, server) = start(Server { features: &[], token: None });
+    let output = send(&address, &["ann@example.com"], Some(("ekom", "secret")), "hi");
+    drop(server);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("doesn't support AUTH PLAIN"));
+}
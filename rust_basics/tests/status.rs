@@ -0,0 +1,87 @@
+// Moves a student through the status lifecycle with `roster activate|suspend|...`
+// and checks which moves are allowed and what ends up in the history.
+
+mod common;
+
+use common::{run, stderr, stdout, temp_dir};
+use std::fs;
+use std::path::Path;
+
+fn roster(dir: &Path, args: &[&str]) -> std::process::Output {
+    let mut all = vec!["roster"];
+    all.extend_from_slice(args);
+    run(dir, &[], &all)
+}
+
+#[test]
+fn allowed_moves_are_written_to_the_history() {
+    let dir = temp_dir("status-history");
+    stdout(roster(&dir, &["add", "ann", "20", "--pending"]));
+    assert_eq!(stdout(roster(&dir, &["approve", "ann", "passed", "the", "interview"])), "ann (20) active\n");
+    assert_eq!(stdout(roster(&dir, &["suspend", "ann", "unpaid fees"])), "ann (20) inactive\n");
+    assert_eq!(stdout(roster(&dir, &["activate", "ann"])), "ann (20) active\n");
+    assert_eq!(stdout(roster(&dir, &["withdraw", "ann", "  moved away  "])), "ann (20) withdrawn\n");
+    let history = stdout(roster(&dir, &["history", "ann"]));
+    fs::remove_dir_all(&dir).unwrap();
+
+    // every line starts with the time, e.g. 2026-10-18T07:51:11Z
+    let lines: Vec<&str> = history.lines().collect();
+    for line in &lines[..4] {
+        assert_eq!(&line[10..11], "T", "{}", line);
+        assert_eq!(&line[19..21], "Z ", "{}", line);
+    }
+    let moves: Vec<&str> = lines[..4].iter().map(|line| &line[21..]).collect();
+    assert_eq!(
+        moves,
+        [
+            "pending -> active: passed the interview",
+            "active -> inactive: unpaid fees",
+            "inactive -> active: ",
+            "active -> withdrawn: moved away",
+        ]
+    );
+    assert_eq!(lines[4], "now withdrawn");
+}
+
+#[test]
+fn forbidden_moves_are_refused_and_change_nothing() {
+    let dir = temp_dir("status-forbidden");
+    stdout(roster(&dir, &["add", "ann", "20", "--pending"]));
+    stdout(roster(&dir, &["add", "bob", "30"]));
+    let errors: Vec<String> = [
+        &["activate", "ann"][..],
+        &["suspend", "ann"],
+        &["approve", "bob"],
+        &["activate", "bob"],
+        &["suspend", "bob", "tab\tin the reason"],
+    ]
+    .iter()
+    .map(|args| stderr(roster(&dir, args)))
+    .collect();
+    stdout(roster(&dir, &["withdraw", "bob"]));
+    let after_withdraw: Vec<String> = ["activate", "suspend", "approve", "withdraw"]
+        .iter()
+        .map(|action| stderr(roster(&dir, &[action, "bob"])))
+        .collect();
+    let ann = stdout(roster(&dir, &["history", "ann"]));
+    let bob = stdout(roster(&dir, &["history", "bob"]));
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(
+        errors,
+        [
+            "error: cannot activate a student who is pending\n",
+            "error: cannot suspend a student who is pending\n",
+            "error: cannot approve a student who is active\n",
+            "error: cannot activate a student who is active\n",
+            "error: reason must not contain control characters\n",
+        ]
+    );
+    // there is no way back from withdrawn
+    for (error, action) in after_withdraw.iter().zip(["activate", "suspend", "approve", "withdraw"]) {
+        assert_eq!(error, &format!("error: cannot {} a student who is withdrawn\n", action));
+    }
+    assert_eq!(ann, "now pending\n");
+    assert_eq!(bob.lines().count(), 2, "{}", bob);
+    assert!(bob.ends_with("active -> withdrawn: \nnow withdrawn\n"), "{}", bob);
+}
@@ -0,0 +1,82 @@
+// Writes student.txt with `student save` (or by hand) and reads it back with
+// `student show`, which goes through load_student.
+
+mod common;
+
+use common::{run, stderr, stdout, temp_dir};
+use std::fs;
+use std::path::Path;
+
+fn show(dir: &Path, content: &str) -> std::process::Output {
+    fs::write(dir.join("student.txt"), content).unwrap();
+    run(dir, &[], &["student", "show"])
+}
+
+#[test]
+fn what_save_writes_load_reads_back() {
+    let dir = temp_dir("student-round-trip");
+    assert_eq!(stdout(run(&dir, &[], &["student", "save", "Ekom Otu", "25"])), "saved Ekom Otu\n");
+    let written = fs::read_to_string(dir.join("student.txt")).unwrap();
+    let shown = stdout(run(&dir, &[], &["student", "show"]));
+    // the history comes back too, and the status has to agree with it
+    let history = stdout(show(
+        &dir,
+        "Version:2\nName:ann\nAge:20\nStatus:inactive\nHistory:2026-10-18T07:51:11Z|active|inactive|unpaid fees\n",
+    ));
+    // CRLF from an editor on Windows and blank lines are fine
+    let crlf = stdout(show(&dir, "Version:2\r\nName:bob\r\n\r\nAge:30\r\nStatus:pending\r\n"));
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(written, "Version:2\nName:Ekom Otu\nAge:25\nStatus:active");
+    assert_eq!(shown, "Ekom Otu (25), active\n");
+    assert_eq!(history, "ann (20), inactive\n");
+    assert_eq!(crlf, "bob (30), pending\n");
+}
+
+#[test]
+fn the_old_format_without_a_colon_still_loads() {
+    let dir = temp_dir("student-old");
+    // what save_student used to write: no version and is_active glued to its value
+    let glued = stdout(show(&dir, "Name:bob\nAge:30\nis_activetrue"));
+    let inactive = stdout(show(&dir, "Name:bob\nAge:30\nis_active:false"));
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(glued, "bob (30), active\n");
+    assert_eq!(inactive, "bob (30), inactive\n");
+}
+
+#[test]
+fn bad_files_say_which_line_is_wrong() {
+    let dir = temp_dir("student-errors");
+    let missing = stderr(run(&dir, &[], &["student", "show"]));
+    let errors: Vec<String> = [
+        "Version:2\nName:ann\nAge:abc\nStatus:active\n",
+        "Version:2\nName:ann\nAge:20\n",
+        "Version:2\nName:ann\nAge:20\nStatus:active\nfoo\n",
+        "Version:2\nName:ann\nName:bob\nAge:20\nStatus:active\n",
+        "Version:2\nName:ann\nAge:20\nStatus:asleep\n",
+        "Version:2\nName:ann\nAge:20\nStatus:active\nHistory:yesterday\n",
+        "Version:2\nName:ann\nAge:20\nStatus:active\nHistory:2026-10-18T07:51:11Z|active|inactive|fees\n",
+        // the file goes through the same checks as create_student
+        "Version:2\nName:ann\nAge:12\nStatus:active\n",
+    ]
+    .iter()
+    .map(|content| stderr(show(&dir, content)))
+    .collect();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(missing.starts_with("error: could not read student file: "), "{}", missing);
+    assert!(missing.contains("student.txt"), "{}", missing);
+    assert_eq!(
+        errors,
+        [
+            "error: line 3: Age must be a number from 0 to 255, got \"abc\"\n",
+            "error: line 4: missing Status line\n",
+            "error: line 5: unexpected line \"foo\"\n",
+            "error: line 3: Name appears more than once\n",
+            "error: line 4: unknown status \"asleep\"\n",
+            "error: line 5: History must look like TIME|FROM|TO|REASON, got \"yesterday\"\n",
+            "error: line 4: Status is active but the history ends at inactive\n",
+            "error: line 3: age 12 is too young, students must be at least 18\n",
+        ]
+    );
+}
@@ -0,0 +1,117 @@
+// Subscribes two file recipients to different topics, publishes a digest
+// file and checks that each file only got what it subscribed to.
+
+mod common;
+
+use common::{program, stderr, stdout, temp_dir};
+use std::fs;
+use std::path::Path;
+use std::process::Output;
+
+const ITEMS: &str = r#"[
+    {"type": "tweet", "item": {"username": "Ekom", "content": "traits are cool #rust"}},
+    {"type": "article", "item": {"headline": "Rust takes over", "author": "The Times"}},
+    {"type": "email", "item": {"from": "Ekom Otu <ekom@example.com>", "subject": "learning go"}},
+    {"type": "tweet", "item": {"username": "bob", "content": "I trust nothing"}}
+]"#;
+
+fn run(dir: &Path, args: &[&str]) -> Output {
+    program(dir).arg("subscription").args(args).output().unwrap()
+}
+
+// the token from "unsubscribe token: ..."
+fn subscribe(dir: &Path, recipient: &str, topic: &str) -> String {
+    let out = stdout(run(dir, &["add", recipient, topic]));
+    out.lines().find_map(|line| line.strip_prefix("unsubscribe token: ")).unwrap().to_string()
+}
+
+fn lines(path: &Path) -> Vec<String> {
+    fs::read_to_string(path).map(|log| log.lines().map(String::from).collect()).unwrap_or_default()
+}
+
+#[test]
+fn items_only_go_to_their_subscribers() {
+    let dir = temp_dir("subscriptions-route");
+    let items = dir.join("items.json");
+    fs::write(&items, ITEMS).unwrap();
+    let ann = dir.join("ann.log");
+    let bob = dir.join("bob.log");
+    let ann_spec = format!("file:{}", ann.display());
+    let bob_spec = format!("file:{}", bob.display());
+    let user = subscribe(&dir, &ann_spec, "user:@ekom");
+    let keyword = subscribe(&dir, &ann_spec, "keyword:rust");
+    subscribe(&dir, &bob_spec, "author:the times");
+    subscribe(&dir, &bob_spec, "from:ekom@example.com");
+
+    let out = stdout(run(&dir, &["publish", items.to_str().unwrap()]));
+    assert!(out.starts_with(&format!("@Ekom: traits are cool #rust -> {}\n", ann_spec)), "{}", out);
+    assert!(out.ends_with("@bob: I trust nothing -> nobody\n"), "{}", out);
+
+    let ann = lines(&ann);
+    let bob = lines(&bob);
+    fs::remove_dir_all(&dir).unwrap();
+    // the tweet and the article (it says rust), not the email or bob's tweet
+    assert_eq!(ann.len(), 2, "{:?}", ann);
+    assert!(ann[0].contains("new tweet from @Ekom: @Ekom: traits are cool #rust"));
+    // the tweet matched both of ann's subscriptions: one notification, both in the footer
+    assert!(ann[0].contains(&format!("you get this for user:ekom, unsubscribe: learning_project subscription remove {}", user)));
+    assert!(ann[0].contains(&format!("you get this for keyword:rust, unsubscribe: learning_project subscription remove {}", keyword)));
+    assert!(!ann[1].contains("user:ekom"), "{:?}", ann);
+    assert!(ann[1].contains("Rust takes over by The Times"));
+    assert_eq!(bob.len(), 2, "{:?}", bob);
+    assert!(bob[0].contains("you get this for author:the times"));
+    assert!(bob[1].contains("new email from ekom@example.com"));
+}
+
+#[test]
+fn the_token_unsubscribes() {
+    let dir = temp_dir("subscriptions-token");
+    let items = dir.join("items.json");
+    fs::write(&items, ITEMS).unwrap();
+    let ann = dir.join("ann.log");
+    let spec = format!("file:{}", ann.display());
+    let token = subscribe(&dir, &spec, "user:ekom");
+    // subscribing again to the same thing gives the same token
+    assert_eq!(subscribe(&dir, &spec, "user:ekom"), token);
+    assert_eq!(token.len(), 32);
+
+    assert_eq!(stdout(run(&dir, &["remove", &token])), format!("{} unsubscribed from user:ekom\n", spec));
+    assert_eq!(stdout(run(&dir, &["list"])), "(none)\n");
+    assert!(stderr(run(&dir, &["remove", &token])).contains("no subscription has the token"));
+
+    stdout(run(&dir, &["publish", items.to_str().unwrap()]));
+    let delivered = lines(&ann);
+    fs::remove_dir_all(&dir).unwrap();
+    assert!(delivered.is_empty(), "{:?}", delivered);
+}
+
+#[test]
+fn bad_topics_and_recipients_are_refused() {
+    let dir = temp_dir("subscriptions-bad");
+    let topic = stderr(run(&dir, &["add", "stdout", "colour:blue"]));
+    let recipient = stderr(run(&dir, &["add", "carrier-pigeon", "user:ekom"]));
+    let address = stderr(run(&dir, &["add", "stdout", "from:not an address"]));
+    fs::remove_dir_all(&dir).unwrap();
+    assert!(topic.contains("bad topic: unknown kind \"colour\""), "{}", topic);
+    assert!(recipient.contains("bad recipient \"carrier-pigeon\": unknown sink"), "{}", recipient);
+    assert!(address.contains("bad topic: from:not an address"), "{}", address);
+}
+
+#[test]
+fn notification_txt_in_the_data_dir_changes_the_message() {
+    let dir = temp_dir("subscriptions-template");
+    let items = dir.join("items.json");
+    fs::write(&items, ITEMS).unwrap();
+    // escape runs after truncate, so the &amp; from "&" is never cut in half
+    fs::write(dir.join("notification.txt"), "FYI {{ title }}\n{{ summary | escape | truncate(13) }} ({{ subscriptions.0.topic }})\n").unwrap();
+    let ann = dir.join("ann.log");
+    subscribe(&dir, &format!("file:{}", ann.display()), "user:bob");
+    fs::write(&items, r#"[{"type": "tweet", "item": {"username": "bob", "content": "fish & chips & more"}}]"#).unwrap();
+    stdout(run(&dir, &["publish", items.to_str().unwrap()]));
+
+    let ann = lines(&ann);
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(ann.len(), 1, "{:?}", ann);
+    assert!(ann[0].contains("FYI new tweet from @bob"), "{:?}", ann);
+    assert!(ann[0].contains("@bob: fish &amp;… (user:bob)"), "{:?}", ann);
+}
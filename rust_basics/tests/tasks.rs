@@ -0,0 +1,76 @@
+// Runs `learning_project task` against task.txt in a fresh data directory and
+// checks that what one command saves the next one reads back the same.
+
+mod common;
+
+use common::{program, stderr, stdout, temp_dir};
+use std::fs;
+use std::path::Path;
+use std::process::Output;
+
+fn task(dir: &Path, args: &[&str]) -> Output {
+    program(dir).arg("task").args(args).output().unwrap()
+}
+
+#[test]
+fn titles_that_look_like_metadata_come_back_as_titles() {
+    let dir = temp_dir("tasks-escape");
+    assert_eq!(stdout(task(&dir, &["add", "Pay", "rent", "due:friday"])), "[ ] 1 (medium) Pay rent \\due:friday\n");
+    assert_eq!(
+        stdout(task(&dir, &["add", "Fix", "issue", "#42", "--tag", "work", "--due", "2026-11-01"])),
+        "[ ] 2 (medium) Fix issue \\#42 due:2026-11-01 #work\n"
+    );
+    // a backslash the user typed survives too
+    stdout(task(&dir, &["add", "\\#not", "a", "tag"]));
+
+    let saved = fs::read_to_string(dir.join("task.txt")).unwrap();
+    assert!(saved.contains("[ ] 3 (medium) \\\\#not a tag\n"), "{}", saved);
+    // the tag search only finds the real tag, #42 stays part of the title
+    assert_eq!(stdout(task(&dir, &["list", "--tag", "42"])), "");
+    assert_eq!(stdout(task(&dir, &["list", "--tag", "work"])), "[ ] 2 (medium) Fix issue \\#42 due:2026-11-01 #work\n");
+
+    // changing a task loads and saves the file again, nothing moves around
+    stdout(task(&dir, &["done", "1"]));
+    assert_eq!(fs::read_to_string(dir.join("task.txt")).unwrap(), saved.replacen("[ ] 1", "[x] 1", 1));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn a_hand_written_file_is_read_and_sorted() {
+    let dir = temp_dir("tasks-file");
+    fs::write(
+        dir.join("task.txt"),
+        "[~] 2 (low) Read the Rust book #learning\n\
+         [ ] 1 (high) Write the report due:2026-11-01 #work #urgent\n\
+         \n\
+         Buy milk #shop\n\
+         [x] 3 Install rustup\n",
+    )
+    .unwrap();
+
+    // open ones first, high priority first, the plain line is a new todo
+    assert_eq!(
+        stdout(task(&dir, &["list"])),
+        "[ ] 1 (high) Write the report due:2026-11-01 #work #urgent\n\
+         [ ] 4 (medium) Buy milk \\#shop\n\
+         [~] 2 (low) Read the Rust book #learning\n"
+    );
+    assert_eq!(stdout(task(&dir, &["list", "--status", "done"])), "[x] 3 (medium) Install rustup\n");
+    stdout(task(&dir, &["priority", "2", "high"]));
+    assert_eq!(stdout(task(&dir, &["list", "--status", "doing"])), "[~] 2 (high) Read the Rust book #learning\n");
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn mistakes_in_the_file_and_the_command_are_reported() {
+    let dir = temp_dir("tasks-bad");
+    fs::write(dir.join("task.txt"), "[ ] 1 Pay rent\n[ ] 2 Call ann due:friday\n").unwrap();
+    let error = stderr(task(&dir, &["list"]));
+    assert!(error.contains("task.txt line 2: due date must be YYYY-MM-DD, got \"friday\""), "{}", error);
+
+    fs::write(dir.join("task.txt"), "").unwrap();
+    assert!(stderr(task(&dir, &["add", "--due", "friday", "x"])).contains("due date must be YYYY-MM-DD, got \"friday\""));
+    assert!(stderr(task(&dir, &["add", "--tag", "work"])).contains("a task needs a title"));
+    assert!(stderr(task(&dir, &["done", "7"])).contains("no task with id 7"));
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,88 @@
+// Runs `learning_project render` with template files on a digest file.
+
+use std::fs;
+use std::process::{Command, Output};
+
+const ITEMS: &str = r#"[
+    {"type": "tweet", "item": {"username": "ekom", "content": "traits are <cool> 🦀🦀 #rust @ann"}, "published": "2026-10-18T07:00:00Z"},
+    {"type": "article", "item": {"headline": "Rust & friends", "author": "The Times", "link": "http://example.com/r?a=1&b=2"}, "priority": "high"}
+]"#;
+
+fn render(name: &str, file_name: &str, template: &str, args: &[&str]) -> Output {
+    let dir = std::env::temp_dir().join(format!("lp-template-{}-{}", name, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let items = dir.join("items.json");
+    fs::write(&items, ITEMS).unwrap();
+    let template_file = dir.join(file_name);
+    fs::write(&template_file, template).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_learning_project"))
+        .arg("--data-dir")
+        .arg(&dir)
+        .arg("render")
+        .arg(&template_file)
+        .arg(&items)
+        .args(args)
+        .output()
+        .unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    output
+}
+
+fn rendered(name: &str, file_name: &str, template: &str, args: &[&str]) -> String {
+    let output = render(name, file_name, template, args);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+fn error(name: &str, template: &str) -> String {
+    let output = render(name, "bad.txt", template, &[]);
+    assert!(!output.status.success());
+    String::from_utf8(output.stderr).unwrap()
+}
+
+#[test]
+fn loops_conditions_and_filters() {
+    let template = "\
+{# the lines with only a tag on them don't show up #}
+{{ title | upper }}
+{% for item in items %}
+{{ loop.index }}. {{ item.summary | truncate(24) }} [{{ item.priority }}]
+   {{ item.published | date(\"%a %e %b %Y\") | default(\"no date\") }}
+  {% if item.hashtags %}
+   tags: {{ item.hashtags | join(\" \") }}, {{ item.mentions | length }} mention
+  {% endif %}
+{% else %}
+nothing today
+{% endfor %}
+";
+    assert_eq!(
+        rendered("text", "digest.txt", template, &["--title", "Morning"]),
+        "MORNING\n\
+         1. @ekom: traits are <cool… [medium]\n   Sun 18 Oct 2026\n   tags: rust, 1 mention\n\
+         2. Rust & friends by The T… [high]\n   no date\n"
+    );
+}
+
+#[test]
+fn html_templates_escape_unless_raw() {
+    let template = "<ul>\n{% for item in items %}\n  <li>{% if item.link %}<a href=\"{{ item.link }}\">{{ item.headline }}</a>{% else %}{{ item.summary }}{% endif %}{% if not loop.last %},{% endif %}</li>\n{% endfor %}\n</ul>\n{{ title | raw }}\n";
+    assert_eq!(
+        rendered("html", "digest.html", template, &["--title", "<b>news</b>"]),
+        "<ul>\n  <li>@ekom: traits are &lt;cool&gt; 🦀🦀 #rust @ann,</li>\n  \
+         <li><a href=\"http://example.com/r?a=1&amp;b=2\">Rust &amp; friends</a></li>\n</ul>\n<b>news</b>\n"
+    );
+}
+
+#[test]
+fn template_mistakes_say_where() {
+    assert!(error("filter", "ok\n  {{ title | shout }}").contains("bad.txt: line 2, column 14: unknown filter shout"));
+    assert!(error("open", "{% for item in items %}\n{{ item.kind }}\n").contains("line 1, column 1: {% for %} is never closed, expected {% endfor %}"));
+    assert!(error("close", "{% if title %}\n{% endfor %}").contains("line 2, column 1: {% endfor %} can't close {% if %}, expected {% endif %}"));
+    assert!(error("braces", "{{ title ").contains("line 1, column 10: expected }}"));
+}
+
+#[test]
+fn data_that_does_not_fit_says_where() {
+    assert!(error("date", "\n\n   {{ title | date }}").contains("line 3, column 15: \"Daily digest\" is not a time"));
+    assert!(error("list", "{{ items }}").contains("line 1, column 4: can't print a list, use join or a for loop"));
+}
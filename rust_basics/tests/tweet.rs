@@ -0,0 +1,57 @@
+// Runs `learning_project tweet` on text with accents, emoji, flags and links.
+
+use std::process::{Command, Output};
+
+fn tweet(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_learning_project")).arg("tweet").args(args).output().unwrap()
+}
+
+fn stdout(output: Output) -> String {
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn length_counts_what_people_see_as_one_character() {
+    // e + combining accent, a skin tone, a flag and a ZWJ family are one each
+    let text = "cafe\u{301} 🦀 👍🏽 🇳🇬 👨\u{200d}👩\u{200d}👧";
+    assert!(stdout(tweet(&["ekom", text])).ends_with("\n12/280 characters\n"));
+}
+
+#[test]
+fn a_link_counts_the_same_however_long_it_is() {
+    let out = stdout(tweet(&["ekom", "read https://example.com/a/very/long/path/that/goes/on/and/on."]));
+    // "read " + 23 for the link + "."
+    assert!(out.ends_with("\n29/280 characters\n"), "{}", out);
+
+    let long_url = format!("https://example.com/{}", "a".repeat(100));
+    let text = format!("{} {}", "x".repeat(256), long_url);
+    assert!(stdout(tweet(&["ekom", &text])).contains("\n280/280 characters\n"));
+
+    // any case, but "http://" on its own or cut short by the end isn't a link
+    assert!(stdout(tweet(&["ekom", "HTTPS://Example.com"])).ends_with("\n23/280 characters\n"));
+    assert!(stdout(tweet(&["ekom", "see http://"])).ends_with("\n11/280 characters\n"));
+    assert!(stdout(tweet(&["ekom", "é http:/"])).ends_with("\n8/280 characters\n"));
+}
+
+#[test]
+fn more_than_280_is_refused() {
+    assert!(stdout(tweet(&["ekom", &"🦀".repeat(280)])).ends_with("\n280/280 characters\n"));
+    let output = tweet(&["ekom", &"🦀".repeat(281)]);
+    assert!(!output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "error: tweet is 281 characters long, the most is 280\n");
+}
+
+#[test]
+fn mentions_and_hashtags_are_found_once() {
+    let out = stdout(tweet(&["ekom", "hi @Ann and @ann, mail ann@example.com about C# #rust #2024 #日本 https://example.com/#anchor"]));
+    assert!(out.ends_with("\nmentions: @Ann\nhashtags: #rust #日本\n"), "{}", out);
+}
+
+#[test]
+fn width_counts_emoji_and_cjk_as_two_columns() {
+    assert_eq!(stdout(tweet(&["--width", "20", "ekom", "🦀🦀🦀🦀🦀🦀🦀🦀 crab"])).lines().next(), Some("@ekom: 🦀🦀🦀🦀🦀🦀…"));
+    assert_eq!(stdout(tweet(&["--width", "12", "ekom", "漢字漢字漢字"])).lines().next(), Some("@ekom: 漢字…"));
+    // short enough already, nothing is cut
+    assert_eq!(stdout(tweet(&["--width", "80", "ekom", "漢字 🦀"])).lines().next(), Some("@ekom: 漢字 🦀"));
+}
@@ -0,0 +1,76 @@
+// Makes students with bad names and ages through `student save`, `roster add`
+// and a hand-written student.txt, and checks that every problem is reported at once
+// and that each one points at the right field.
+
+mod common;
+
+use common::{run, stderr, temp_dir};
+use std::fs;
+
+#[test]
+fn every_bad_field_is_reported_together() {
+    let dir = temp_dir("validation-together");
+    let long_name = "a".repeat(70);
+    let errors: Vec<String> = [
+        &["student", "save", "", "12"][..],
+        &["student", "save", "   ", "17"],
+        &["student", "save", &long_name, "130"],
+        &["student", "save", "bad\tname", "20"],
+        &["roster", "add", "ann", "121"],
+    ]
+    .iter()
+    .map(|args| stderr(run(&dir, &[], args)))
+    .collect();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(
+        errors,
+        [
+            "error: name must not be empty; age 12 is too young, students must be at least 18\n",
+            "error: name must not be empty; age 17 is too young, students must be at least 18\n",
+            "error: name is 70 characters long, the limit is 64; age 130 is not plausible, the limit is 120\n",
+            "error: name has a control character at position 3\n",
+            "error: invalid student: age 121 is not plausible, the limit is 120\n",
+        ]
+    );
+}
+
+#[test]
+fn the_limits_themselves_are_allowed() {
+    let dir = temp_dir("validation-limits");
+    let longest = "é".repeat(64);
+    let youngest = run(&dir, &[], &["roster", "add", "ann", "18"]);
+    let oldest = run(&dir, &[], &["roster", "add", "bob", "120"]);
+    let name = run(&dir, &[], &["roster", "add", &longest, "30"]);
+    fs::remove_dir_all(&dir).unwrap();
+    for output in [youngest, oldest, name] {
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    }
+}
+
+#[test]
+fn a_bad_field_in_a_file_points_at_its_line() {
+    let dir = temp_dir("validation-lines");
+    let file = dir.join("student.txt");
+    let mut errors = Vec::new();
+    for content in [
+        // the name is the first field that failed, so its line is reported
+        "Version:2\nName:\nAge:12\nStatus:active\n",
+        "Version:2\nName:ann\nAge:130\nStatus:active\n",
+        // the fields can come in any order
+        "Version:2\nStatus:active\nAge:12\nName:ann\n",
+    ] {
+        fs::write(&file, content).unwrap();
+        errors.push(stderr(run(&dir, &[], &["student", "show"])));
+    }
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(
+        errors,
+        [
+            "error: line 2: name must not be empty; age 12 is too young, students must be at least 18\n",
+            "error: line 3: age 130 is not plausible, the limit is 120\n",
+            "error: line 3: age 12 is too young, students must be at least 18\n",
+        ]
+    );
+}